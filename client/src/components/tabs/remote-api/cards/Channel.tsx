@@ -8,7 +8,7 @@ import ErrorCallout from "../components/ErrorCallout";
 export default function ChannelCard({ api }: { api: RemoteApi }) {
   const [remoteId, setRemoteId] = useState<string>("");
   const [amountSat, setAmountSat] = useState<string>("");
-  const [announceRaw, setAnnounceRaw] = useState<string>("");
+  const [isPrivate, setIsPrivate] = useState<string>("");
 
   const createReq = useMutation({
     mutationKey: ["remote", api.baseUrl, "channel-request"],
@@ -23,26 +23,25 @@ export default function ChannelCard({ api }: { api: RemoteApi }) {
     mutationKey: ["remote", api.baseUrl, "open-channel"],
     mutationFn: async () => {
       if (!request) throw new Error("Call /channel-request first");
-      if (!remoteId.trim()) throw new Error("remote_id is required");
+      if (!remoteId.trim()) throw new Error("remoteid is required");
 
       const amount = amountSat.trim() ? Number(amountSat) : undefined;
       if (amount !== undefined && (!Number.isFinite(amount) || amount < 0)) {
         throw new Error("Amount must be a positive number");
       }
 
-      const announce = (() => {
-        const trimmed = announceRaw.trim().toLowerCase();
+      const privateFlag = (() => {
+        const trimmed = isPrivate.trim();
         if (!trimmed) return undefined;
-        if (trimmed === "true") return true;
-        if (trimmed === "false") return false;
-        throw new Error("announce must be 'true' or 'false'");
+        if (trimmed === "0" || trimmed === "1") return trimmed;
+        throw new Error("private must be 0 or 1");
       })();
 
       return api.callCallback(request.callback, {
         k1: request.k1,
-        remote_id: remoteId.trim(),
+        remoteid: remoteId.trim(),
         amount: amount === undefined ? undefined : Math.floor(amount),
-        announce,
+        private: privateFlag,
       });
     },
   });
@@ -117,7 +116,7 @@ export default function ChannelCard({ api }: { api: RemoteApi }) {
               onChange={(e: ChangeEvent<HTMLInputElement>) =>
                 setRemoteId(e.target.value)
               }
-              placeholder="remote_id - node pubkey of the remote peer"
+              placeholder="remoteid - node pubkey of the wallet"
             />
 
             <TextField.Root
@@ -125,16 +124,16 @@ export default function ChannelCard({ api }: { api: RemoteApi }) {
               onChange={(e: ChangeEvent<HTMLInputElement>) =>
                 setAmountSat(e.target.value)
               }
-              placeholder="amount (sat) — optional"
+              placeholder="amount (sat) — optional, defaults to the minimum channel size"
               inputMode="numeric"
             />
 
             <TextField.Root
-              value={announceRaw}
+              value={isPrivate}
               onChange={(e: ChangeEvent<HTMLInputElement>) =>
-                setAnnounceRaw(e.target.value)
+                setIsPrivate(e.target.value)
               }
-              placeholder="announce: true | false (optional)"
+              placeholder="private: 0 | 1 (optional)"
             />
 
             {callback.isError ? (
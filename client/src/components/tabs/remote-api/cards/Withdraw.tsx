@@ -24,15 +24,17 @@ export default function WithdrawCard({ api }: { api: RemoteApi }) {
       if (!request) throw new Error("Call /withdraw-request first");
       if (!destination.trim()) throw new Error("Destination is required");
 
-      const amount = amountSat.trim() ? Number(amountSat) : undefined;
-      if (amount !== undefined && (!Number.isFinite(amount) || amount < 0)) {
+      if (!amountSat.trim()) throw new Error("Amount (sat) is required");
+
+      const amount = Number(amountSat);
+      if (!Number.isFinite(amount) || amount <= 0) {
         throw new Error("Amount must be a positive number");
       }
 
       return api.callCallback(request.callback, {
         k1: request.k1,
         destination: destination.trim(),
-        amount: amount === undefined ? undefined : Math.floor(amount),
+        amount: Math.floor(amount),
       });
     },
   });
@@ -115,7 +117,7 @@ export default function WithdrawCard({ api }: { api: RemoteApi }) {
               onChange={(e: ChangeEvent<HTMLInputElement>) =>
                 setAmountSat(e.target.value)
               }
-              placeholder="amount (sat) — optional"
+              placeholder="amount (sat)"
               inputMode="numeric"
             />
 
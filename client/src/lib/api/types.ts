@@ -4,6 +4,22 @@
  */
 
 export interface paths {
+  "/auth/session": {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    get: operations["getAuthSession"];
+    put?: never;
+    post?: never;
+    delete: operations["logout"];
+    options?: never;
+    head?: never;
+    patch?: never;
+    trace?: never;
+  };
   "/callbacks/issue-withdraw": {
     parameters: {
       query?: never;
@@ -68,6 +84,102 @@ export interface paths {
     patch?: never;
     trace?: never;
   };
+  "/channels": {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    get: operations["listChannels"];
+    put?: never;
+    post?: never;
+    delete?: never;
+    options?: never;
+    head?: never;
+    patch?: never;
+    trace?: never;
+  };
+  "/channels/{id}/close": {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    get?: never;
+    put?: never;
+    post: operations["closeChannel"];
+    delete?: never;
+    options?: never;
+    head?: never;
+    patch?: never;
+    trace?: never;
+  };
+  "/channels/{id}/fees": {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    get?: never;
+    put?: never;
+    post: operations["updateChannelFees"];
+    delete?: never;
+    options?: never;
+    head?: never;
+    patch?: never;
+    trace?: never;
+  };
+  "/config/reload": {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    get?: never;
+    put?: never;
+    post: operations["reloadConfig"];
+    delete?: never;
+    options?: never;
+    head?: never;
+    patch?: never;
+    trace?: never;
+  };
+  "/events": {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    get: operations["invoiceEvents"];
+    put?: never;
+    post?: never;
+    delete?: never;
+    options?: never;
+    head?: never;
+    patch?: never;
+    trace?: never;
+  };
+  "/events/ticket": {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    get?: never;
+    put?: never;
+    post: operations["createStreamTicket"];
+    delete?: never;
+    options?: never;
+    head?: never;
+    patch?: never;
+    trace?: never;
+  };
   "/health": {
     parameters: {
       query?: never;
@@ -84,6 +196,38 @@ export interface paths {
     patch?: never;
     trace?: never;
   };
+  "/invoices": {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    get: operations["listInvoices"];
+    put?: never;
+    post: operations["createInvoice"];
+    delete?: never;
+    options?: never;
+    head?: never;
+    patch?: never;
+    trace?: never;
+  };
+  "/invoices/{label}": {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    get: operations["getInvoice"];
+    put?: never;
+    post?: never;
+    delete: operations["deleteInvoice"];
+    options?: never;
+    head?: never;
+    patch?: never;
+    trace?: never;
+  };
   "/lnurl-auth-request": {
     parameters: {
       query?: never;
@@ -100,30 +244,30 @@ export interface paths {
     patch?: never;
     trace?: never;
   };
-  "/recent-requests": {
+  "/lnurl-auth-status/{k1}": {
     parameters: {
       query?: never;
       header?: never;
       path?: never;
       cookie?: never;
     };
-    get: operations["recent_requests"];
+    get: operations["lnurlAuthStatus"];
     put?: never;
     post?: never;
-    delete: operations["clear_recent_requests"];
+    delete?: never;
     options?: never;
     head?: never;
     patch?: never;
     trace?: never;
   };
-  "/withdraw-request": {
+  "/lnurl-auth-status/{k1}/events": {
     parameters: {
       query?: never;
       header?: never;
       path?: never;
       cookie?: never;
     };
-    get: operations["withdrawRequest"];
+    get: operations["lnurlAuthStatusEvents"];
     put?: never;
     post?: never;
     delete?: never;
@@ -132,293 +276,2967 @@ export interface paths {
     patch?: never;
     trace?: never;
   };
-}
-export type webhooks = Record<string, never>;
-export interface components {
-  schemas: {
-    BitcoinInfo: {
-      /**
-       * Format: int64
-       * @description Current number of blocks.
-       */
-      blocks: number;
-      /** @description Blockchain name (e.g. bitcoin, testnet, regtest). */
-      chain: string;
-      /**
-       * Format: int64
-       * @description Number of connections to peers.
-       */
-      connections: number;
-      /**
-       * Format: int64
-       * @description Current number of headers.
-       */
-      headers: number;
-      /** @description Whether bitcoind is in initial block download. */
-      initial_block_download: boolean;
-      /** @description Overall status of the bitcoind JSON-RPC connection. */
-      status: components["schemas"]["BitcoinStatus"];
-      /** @description Bitcoind subversion string. */
-      subversion: string;
-      /**
-       * Format: double
-       * @description Verification progress (0.0 to 1.0).
-       */
-      verification_progress: number;
-      /**
-       * Format: int64
-       * @description Bitcoind version number.
-       */
-      version: number;
-      /** @description Any warnings reported by bitcoind. */
-      warnings?: string | null;
-    };
-    /** @enum {string} */
-    BitcoinStatus: "ok" | "unreachable" | "notconfigured";
-    ChannelRequestResponse: {
-      /** @description Second-level URL to trigger OpenChannel */
-      callback: string;
-      /** @description Wallet identifier */
-      k1: string;
-      /** @description Type of request, must be "channelRequest" */
-      tag: string;
-      /** @description Remote node address of form node_key@ip_address:port_number */
-      uri: string;
+  "/lsps": {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
     };
-    HealthResponse: {
-      /** @description Overall status of the bitcoind JSON-RPC connection. */
-      bitcoin: components["schemas"]["BitcoinInfo"];
-      /** @description Overall status of the CoreLightning node. */
-      lightning: components["schemas"]["LightningInfo"];
-      /**
-       * Format: int64
-       * @description Maximum withdrawable amount in millisatoshis.
-       */
-      max_withdrawable_msat: number;
-      /**
-       * Format: int64
-       * @description Minimum withdrawable amount in millisatoshis.
-       */
-      min_withdrawable_msat: number;
-      /** @description Warning message when bitcoind is not in sync (may be absent). */
-      warning_bitcoind_sync?: string | null;
-      /** @description Warning message when lightningd is not in sync (may be absent). */
-      warning_lightningd_sync?: string | null;
+    get?: never;
+    put?: never;
+    post: operations["lsps"];
+    delete?: never;
+    options?: never;
+    head?: never;
+    patch?: never;
+    trace?: never;
+  };
+  "/lsps1/refunds": {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
     };
-    IssueWithdrawRequest: {
-      /** Format: int64 */
-      amount?: number | null;
-      destination: string;
-      k1: string;
+    get: operations["lsps1Refunds"];
+    put?: never;
+    post?: never;
+    delete?: never;
+    options?: never;
+    head?: never;
+    patch?: never;
+    trace?: never;
+  };
+  "/lsps1/refunds/{order_id}": {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
     };
-    IssueWithdrawResponse: {
-      psbt: string;
-      tx: string;
-      txid: string;
+    get?: never;
+    put?: never;
+    post: operations["lsps1MarkRefunded"];
+    delete?: never;
+    options?: never;
+    head?: never;
+    patch?: never;
+    trace?: never;
+  };
+  "/lsps2/htlc": {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
     };
-    LightningInfo: {
-      /** @description Node alias (may be absent). */
-      alias?: string | null;
-      /** @description CoreLightning version string. */
-      cln_version: string;
-      /**
-       * Format: int32
-       * @description Number of active channels.
-       */
-      num_active_channels: number;
+    get?: never;
+    put?: never;
+    post: operations["lsps2Htlc"];
+    delete?: never;
+    options?: never;
+    head?: never;
+    patch?: never;
+    trace?: never;
+  };
+  "/metrics": {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    get: operations["metrics"];
+    put?: never;
+    post?: never;
+    delete?: never;
+    options?: never;
+    head?: never;
+    patch?: never;
+    trace?: never;
+  };
+  "/offers": {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    get: operations["listOffers"];
+    put?: never;
+    post: operations["createOffer"];
+    delete?: never;
+    options?: never;
+    head?: never;
+    patch?: never;
+    trace?: never;
+  };
+  "/offers/pay": {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    get?: never;
+    put?: never;
+    post: operations["payOffer"];
+    delete?: never;
+    options?: never;
+    head?: never;
+    patch?: never;
+    trace?: never;
+  };
+  "/offers/{offer_id}/disable": {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    get?: never;
+    put?: never;
+    post: operations["disableOffer"];
+    delete?: never;
+    options?: never;
+    head?: never;
+    patch?: never;
+    trace?: never;
+  };
+  "/payments": {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    get?: never;
+    put?: never;
+    post: operations["payInvoice"];
+    delete?: never;
+    options?: never;
+    head?: never;
+    patch?: never;
+    trace?: never;
+  };
+  "/payments/keysend": {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    get?: never;
+    put?: never;
+    post: operations["keysend"];
+    delete?: never;
+    options?: never;
+    head?: never;
+    patch?: never;
+    trace?: never;
+  };
+  "/payments/{payment_hash}": {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    get: operations["getPayment"];
+    put?: never;
+    post?: never;
+    delete?: never;
+    options?: never;
+    head?: never;
+    patch?: never;
+    trace?: never;
+  };
+  "/peers": {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    get: operations["listPeers"];
+    put?: never;
+    post: operations["connectPeer"];
+    delete?: never;
+    options?: never;
+    head?: never;
+    patch?: never;
+    trace?: never;
+  };
+  "/peers/{id}": {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    get?: never;
+    put?: never;
+    post?: never;
+    delete: operations["disconnectPeer"];
+    options?: never;
+    head?: never;
+    patch?: never;
+    trace?: never;
+  };
+  "/policy/usage": {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    get: operations["policyUsage"];
+    put?: never;
+    post?: never;
+    delete?: never;
+    options?: never;
+    head?: never;
+    patch?: never;
+    trace?: never;
+  };
+  "/recent-requests": {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    get: operations["recent_requests"];
+    put?: never;
+    post?: never;
+    delete: operations["clear_recent_requests"];
+    options?: never;
+    head?: never;
+    patch?: never;
+    trace?: never;
+  };
+  "/webhooks/deliveries": {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    get: operations["listWebhookDeliveries"];
+    put?: never;
+    post?: never;
+    delete?: never;
+    options?: never;
+    head?: never;
+    patch?: never;
+    trace?: never;
+  };
+  "/webhooks/deliveries/{id}/redeliver": {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    get?: never;
+    put?: never;
+    post: operations["redeliverWebhook"];
+    delete?: never;
+    options?: never;
+    head?: never;
+    patch?: never;
+    trace?: never;
+  };
+  "/withdraw-request": {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    get: operations["withdrawRequest"];
+    put?: never;
+    post?: never;
+    delete?: never;
+    options?: never;
+    head?: never;
+    patch?: never;
+    trace?: never;
+  };
+  "/ws": {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    get: operations["invoiceEventsWs"];
+    put?: never;
+    post?: never;
+    delete?: never;
+    options?: never;
+    head?: never;
+    patch?: never;
+    trace?: never;
+  };
+}
+export type webhooks = Record<string, never>;
+export interface components {
+  schemas: {
+    AuthSession: {
+      action?: null | components["schemas"]["LnUrlAuthRequestAction"];
+      /** @description Whether the linking key is allowed to use administrative routes. */
+      admin: boolean;
       /**
-       * Format: int32
-       * @description Number of connected peers.
+       * Format: int64
+       * @description Unix timestamp (seconds) when the session was issued.
        */
-      num_peers: number;
+      created_at: number;
       /**
-       * Format: int32
-       * @description Number of pending channels.
+       * Format: int64
+       * @description Unix timestamp (seconds) after which the session is no longer valid.
        */
-      num_pending_channels: number;
-      /** @description Node public key (hex). */
-      pubkey: string;
-      /** @description Human-readable overall status; clients treat ok/healthy/ready as operational. */
-      status: components["schemas"]["LightningStatus"];
+      expires_at: number;
+      /** @description LNURL-auth linking key (compressed secp256k1 public key hex). */
+      linking_key: string;
+      /** @description Opaque bearer token, sent as `Authorization: Bearer <token>`. */
+      token: string;
+    };
+    BitcoinInfo: {
+      /**
+       * Format: int64
+       * @description Current number of blocks.
+       */
+      blocks: number;
+      /** @description Blockchain name (e.g. bitcoin, testnet, regtest). */
+      chain: string;
+      /**
+       * Format: int64
+       * @description Number of connections to peers.
+       */
+      connections: number;
+      /**
+       * Format: int64
+       * @description Current number of headers.
+       */
+      headers: number;
+      /** @description Whether bitcoind is in initial block download. */
+      initial_block_download: boolean;
+      /** @description Overall status of the bitcoind JSON-RPC connection. */
+      status: components["schemas"]["BitcoinStatus"];
+      /** @description Bitcoind subversion string. */
+      subversion: string;
+      /**
+       * Format: double
+       * @description Verification progress (0.0 to 1.0).
+       */
+      verification_progress: number;
+      /**
+       * Format: int64
+       * @description Bitcoind version number.
+       */
+      version: number;
+      /** @description Any warnings reported by bitcoind. */
+      warnings?: string | null;
+    };
+    /** @enum {string} */
+    BitcoinStatus: "ok" | "unreachable" | "notconfigured";
+    /** @description Params of `lsps2.buy`. */
+    BuyParams: {
+      opening_fee_params: components["schemas"]["OpeningFeeParams"];
+      /** @description Set for "MPP mode"; without it the first HTLC is the whole payment. */
+      payment_size_msat?: string | null;
+      /** @description Node id of the client. Over BOLT8 this comes from the transport; over HTTP it is required. */
+      public_key: string;
+    };
+    /** @description Result of `lsps2.buy`. */
+    BuyResult: {
+      client_trusts_lsp: boolean;
+      /** @description SCID to use in the route hint of the invoice, as `<block>x<tx>x<output>`. */
+      jit_channel_scid: string;
+      /** Format: int32 */
+      lsp_cltv_expiry_delta: number;
+    };
+    ChannelEntry: {
+      /** @description Channel id (hex); absent while the channel is being negotiated. */
+      channel_id?: string | null;
+      fees?: null | components["schemas"]["ChannelFees"];
+      /** Format: int32 */
+      funding_outnum?: number | null;
+      funding_txid?: string | null;
+      opener: components["schemas"]["ChannelOpener"];
+      peer_connected: boolean;
+      /** @description Node id of the peer. */
+      peer_id: string;
+      /** @description Whether the channel is unannounced. */
+      private?: boolean | null;
+      /**
+       * Format: int64
+       * @description What we can currently receive, in millisatoshis.
+       */
+      receivable_msat?: number | null;
+      /** @description Short channel id (`<block>x<tx>x<output>`), once the funding is confirmed. */
+      short_channel_id?: string | null;
+      /**
+       * Format: int64
+       * @description What we can currently send, in millisatoshis.
+       */
+      spendable_msat?: number | null;
+      /** @description CLN channel state, e.g. `CHANNELD_NORMAL` or `ONCHAIN`. */
+      state: string;
+      /**
+       * Format: int64
+       * @description Our balance in millisatoshis.
+       */
+      to_us_msat?: number | null;
+      /**
+       * Format: int64
+       * @description Channel capacity in millisatoshis.
+       */
+      total_msat?: number | null;
+    };
+    /**
+     * @description Reason a channel request was rejected, returned as `code` next to the LUD-02 `reason`.
+     * @enum {string}
+     */
+    ChannelErrorCode:
+      | "invalid_request"
+      | "invalid_remote_id"
+      | "invalid_flag"
+      | "amount_below_minimum"
+      | "amount_above_maximum"
+      | "spending_limit_exceeded"
+      | "peer_unreachable"
+      | "invalid_k1"
+      | "rune_rejected"
+      | "node_error";
+    /** @description Fees and HTLC limits this node advertises for a channel. */
+    ChannelFees: {
+      /** Format: int32 */
+      cltv_expiry_delta: number;
+      /** Format: int64 */
+      fee_base_msat: number;
+      /**
+       * Format: int32
+       * @description Proportional fee in parts per million.
+       */
+      fee_ppm: number;
+      /** Format: int64 */
+      htlc_maximum_msat: number;
+      /** Format: int64 */
+      htlc_minimum_msat: number;
+    };
+    ChannelFeesEntry: {
+      channel_id: string;
+      /** Format: int64 */
+      fee_base_msat: number;
+      /** Format: int32 */
+      fee_ppm: number;
+      /** Format: int64 */
+      htlc_maximum_msat: number;
+      /** Format: int64 */
+      htlc_minimum_msat: number;
+      peer_id: string;
+      short_channel_id?: string | null;
+      /** @description CLN adjusted an HTLC limit to what the channel allows. */
+      warnings: string[];
+    };
+    /** @enum {string} */
+    ChannelOpener: "local" | "remote";
+    ChannelRequestResponse: {
+      /** @description Second-level URL to trigger OpenChannel */
+      callback: string;
+      /** @description Wallet identifier */
+      k1: string;
+      /** @description Type of request, must be "channelRequest" */
+      tag: string;
+      /** @description Remote node address of form node_key@ip_address:port_number */
+      uri: string;
+    };
+    CloseChannelRequest: {
+      /** @description Bitcoin address receiving our balance instead of the node wallet. */
+      destination?: string | null;
+      /**
+       * Format: int32
+       * @description Seconds to negotiate a mutual close before force-closing; `0` never force-closes.
+       * Defaults to CLN's 48 hours. The request returns once the closing transaction is
+       * broadcast.
+       */
+      unilateral_timeout_secs?: number | null;
+    };
+    CloseChannelResponse: {
+      /** @description Closing transaction ids (none for `unopened`). */
+      txids: string[];
+      type: components["schemas"]["CloseKind"];
+    };
+    /** @enum {string} */
+    CloseKind: "mutual" | "unilateral" | "unopened";
+    /** @description A setting changed by a reload. */
+    ConfigChange: {
+      /** @description Value after the reload (null when unset). */
+      new: unknown;
+      /** @description Value before the reload (null when unset). */
+      old: unknown;
+      /** @description Setting name, as in the config file. */
+      setting: string;
+    };
+    ConfigReloadResponse: {
+      /** @description Settings changed by this reload (empty when nothing changed). */
+      changes: components["schemas"]["ConfigChange"][];
+      /** @description Short hash of the reloadable settings now in effect. */
+      hash: string;
+      /**
+       * Format: int64
+       * @description Version of the runtime config now in effect.
+       */
+      version: number;
+    };
+    ConnectPeerRequest: {
+      /** @description `id@host:port`, or a bare node id to use the addresses it announced in the gossip. */
+      id: string;
+    };
+    ConnectPeerResponse: {
+      /** @description Address of the connection (`host:port`, or a local socket path). */
+      address?: string | null;
+      direction: components["schemas"]["PeerDirection"];
+      /** @description Feature bitmap (hex) the peer sent in `init`. */
+      features: string;
+      id: string;
+      /** @description Names of the well-known features in `features`. */
+      known_features: string[];
+    };
+    CreateInvoiceRequest: {
+      /**
+       * Format: int64
+       * @description Amount in millisatoshis. Omit to let the payer choose ("any" amount).
+       */
+      amount_msat?: number | null;
+      /** @description Invoice description shown to the payer. */
+      description?: string;
+      /** @description Commit only the SHA256 hash of `description` in the invoice (e.g. for LNURL-pay). */
+      description_hash_only?: boolean | null;
+      /**
+       * Format: int64
+       * @description Expiry in seconds (CoreLightning defaults to one week).
+       */
+      expiry?: number | null;
+    };
+    CreateInvoiceResponse: {
+      /** @description BOLT11 payment request. */
+      bolt11: string;
+      /**
+       * Format: int64
+       * @description Unix timestamp (seconds) after which the invoice can no longer be paid.
+       */
+      expires_at: number;
+      /** @description Unique label generated by the gateway, used for lookups. */
+      label: string;
+      /** @description Payment hash (hex). */
+      payment_hash: string;
+    };
+    CreateOfferRequest: {
+      /**
+       * Format: int64
+       * @description Amount in millisatoshis. Omit to let the payer choose ("any" amount).
+       */
+      amount_msat?: number | null;
+      /** @description Offer description shown to the payer (required when `amount_msat` is set). */
+      description?: string | null;
+      /** @description Name of the issuer shown to the payer. */
+      issuer?: string | null;
+      /**
+       * Format: int64
+       * @description Maximum quantity a payer may request in one invoice.
+       */
+      quantity_max?: number | null;
+      /** @description Recurrence period, e.g. `1month` or `2weeks`. */
+      recurrence?: string | null;
+      /** @description Disable the offer after its first payment. */
+      single_use?: boolean | null;
+    };
+    /** @description Params of `lsps1.create_order`. */
+    CreateOrderParams: {
+      announce_channel: boolean;
+      /** Format: int32 */
+      channel_expiry_blocks: number;
+      client_balance_sat?: string;
+      /** Format: int32 */
+      funding_confirms_within_blocks: number;
+      lsp_balance_sat: string;
+      /** @description Node id of the client. Over BOLT8 this comes from the transport; over HTTP it is required. */
+      public_key: string;
+      refund_onchain_address?: string | null;
+      /** Format: int32 */
+      required_channel_confirmations: number;
+      token?: string | null;
+    };
+    /** @description Params of `lsps2.get_info`. */
+    GetInfoParams: {
+      /** @description Accepted and ignored: every client gets the same menu. */
+      token?: string | null;
+    };
+    /** @description Params of `lsps1.get_order`. */
+    GetOrderParams: {
+      order_id: string;
+    };
+    HealthResponse: {
+      /** @description Overall status of the bitcoind JSON-RPC connection. */
+      bitcoin: components["schemas"]["BitcoinInfo"];
+      /** @description Short hash of the reloadable settings in effect. */
+      config_hash: string;
+      /**
+       * Format: int64
+       * @description Version of the runtime config, incremented by every reload that changed a setting.
+       */
+      config_version: number;
+      /** @description Overall status of the CoreLightning node. */
+      lightning: components["schemas"]["LightningInfo"];
+      /**
+       * Format: int64
+       * @description Maximum withdrawable amount in millisatoshis.
+       */
+      max_withdrawable_msat: number;
+      /**
+       * Format: int64
+       * @description Minimum withdrawable amount in millisatoshis.
+       */
+      min_withdrawable_msat: number;
+      /** @description Warning message when bitcoind is not in sync (may be absent). */
+      warning_bitcoind_sync?: string | null;
+      /** @description Warning message when lightningd is not in sync (may be absent). */
+      warning_lightningd_sync?: string | null;
+    };
+    /** @description Payload of CLN's `htlc_accepted` hook; only the fields used here. */
+    HtlcAccepted: {
+      htlc?: components["schemas"]["HtlcInfo"];
+      onion: components["schemas"]["HtlcOnion"];
+    };
+    HtlcInfo: {
+      payment_hash?: string | null;
+    };
+    HtlcOnion: {
+      /**
+       * Format: int64
+       * @description Amount to forward, as a number or an `<n>msat` string (older CLN).
+       */
+      forward_msat?: number | null;
+      /** @description Hex TLV payload of this hop. */
+      payload: string;
+      /** @description Next channel; absent when this node is the final hop. */
+      short_channel_id?: string | null;
+    };
+    /** @description Answer to the `htlc_accepted` hook, returned as is by the plugin. */
+    HtlcResolution: {
+      /** @description Hex BOLT4 failure message. */
+      failure_message?: string | null;
+      /** @description Channel id the HTLC is forwarded to. */
+      forward_to?: string | null;
+      /** @description Replacement hop payload, with `amt_to_forward` reduced by the opening fee. */
+      payload?: string | null;
+      result: components["schemas"]["HtlcResult"];
+    };
+    /** @enum {string} */
+    HtlcResult: "continue" | "fail";
+    InvoiceEntry: {
+      /**
+       * Format: int64
+       * @description Requested amount in millisatoshis (absent for "any amount" invoices).
+       */
+      amount_msat?: number | null;
+      /**
+       * Format: int64
+       * @description Amount actually received in millisatoshis (only once paid).
+       */
+      amount_received_msat?: number | null;
+      /** @description BOLT11 payment request (absent for BOLT12 invoices). */
+      bolt11?: string | null;
+      /**
+       * Format: int64
+       * @description Creation index, usable as `start` for pagination.
+       */
+      created_index?: number | null;
+      /** @description Invoice description (absent when only its hash was committed). */
+      description?: string | null;
+      /**
+       * Format: int64
+       * @description Unix timestamp (seconds) after which the invoice can no longer be paid.
+       */
+      expires_at: number;
+      /** @description Unique label assigned by the gateway. */
+      label: string;
+      /**
+       * Format: int64
+       * @description Unix timestamp (seconds) when the invoice was paid.
+       */
+      paid_at?: number | null;
+      /**
+       * Format: int64
+       * @description Monotonic index of the payment among all paid invoices.
+       */
+      pay_index?: number | null;
+      /** @description Payment hash (hex). */
+      payment_hash: string;
+      /** @description Payment preimage (hex), only once paid. */
+      payment_preimage?: string | null;
+      /** @description Current invoice status. */
+      status: components["schemas"]["InvoiceStatus"];
+    };
+    InvoiceSettledEvent: {
+      /**
+       * Format: int64
+       * @description Requested amount in millisatoshis (absent for "any amount" invoices).
+       */
+      amount_msat?: number | null;
+      /**
+       * Format: int64
+       * @description Amount actually received in millisatoshis.
+       */
+      amount_received_msat?: number | null;
+      /** @description BOLT11 payment request (absent for BOLT12 invoices). */
+      bolt11?: string | null;
+      /** @description Invoice description (absent when only its hash was committed). */
+      description?: string | null;
+      /** @description Invoice label. */
+      label: string;
+      /**
+       * Format: int64
+       * @description Unix timestamp (seconds) when the invoice was paid.
+       */
+      paid_at?: number | null;
+      /**
+       * Format: int64
+       * @description Monotonic index of the payment among all paid invoices.
+       */
+      pay_index: number;
+      /** @description Payment hash (hex). */
+      payment_hash: string;
+      /** @description Payment preimage (hex). */
+      payment_preimage?: string | null;
+    };
+    /** @enum {string} */
+    InvoiceStatus: "unpaid" | "paid" | "expired";
+    IssueWithdrawRequest: {
+      /** @description Amount in satoshis, or `all` when sweeping is enabled. */
+      amount?: string | null;
+      destination: string;
+      k1: string;
+    };
+    IssueWithdrawResponse: {
+      psbt: string;
+      /** @description Always `OK`; failures are returned as errors. */
+      status: string;
+      tx: string;
+      txid: string;
+    };
+    KeysendRequest: {
+      /**
+       * Format: int64
+       * @description Amount in millisatoshis.
+       */
+      amount_msat: number;
+      /** @description Destination node public key (hex). */
+      destination: string;
+      /** @description Extra TLV records attached to the payment (e.g. podcasting 2.0 metadata). */
+      tlv_records?: components["schemas"]["TlvRecord"][];
+    };
+    LightningInfo: {
+      /** @description Node alias (may be absent). */
+      alias?: string | null;
+      /** @description CoreLightning version string. */
+      cln_version: string;
+      /**
+       * Format: int32
+       * @description Number of active channels.
+       */
+      num_active_channels: number;
+      /**
+       * Format: int32
+       * @description Number of connected peers.
+       */
+      num_peers: number;
+      /**
+       * Format: int32
+       * @description Number of pending channels.
+       */
+      num_pending_channels: number;
+      /** @description Node public key (hex). */
+      pubkey: string;
+      /** @description Human-readable overall status; clients treat ok/healthy/ready as operational. */
+      status: components["schemas"]["LightningStatus"];
+    };
+    /** @enum {string} */
+    LightningStatus: "ok" | "syncing";
+    ListInvoicesResponse: {
+      /** @description Invoices in this page, filtered by `status` when requested. */
+      invoices: components["schemas"]["InvoiceEntry"][];
+      /**
+       * Format: int64
+       * @description Creation index of the next page (absent when there are no more invoices).
+       */
+      next_start?: number | null;
+    };
+    LnUrlAuthQuery: {
+      /** @description One-time challenge (32 bytes hex) */
+      k1: string;
+      /** @description Compressed (33-byte) secp256k1 public key hex */
+      key: string;
+      /** @description DER-hex-encoded ECDSA signature for `k1` (some wallets may use compact 64-byte) */
+      sig: string;
+    };
+    /** @enum {string} */
+    LnUrlAuthRequestAction: "register" | "login" | "link" | "auth";
+    LnUrlAuthRequestQuery: {
+      action?: null | components["schemas"]["LnUrlAuthRequestAction"];
+    };
+    LnUrlAuthRequestResponse: {
+      action?: null | components["schemas"]["LnUrlAuthRequestAction"];
+      /** @description Callback URL to be called by the wallet with `sig` and `key` */
+      callback: string;
+      /** @description (32 bytes hex) One-time challenge */
+      k1: string;
+      /** @description Type of request, must be "login" per LUD-04 */
+      tag: string;
+    };
+    LnUrlAuthResponse: {
+      error?: string | null;
+      ok: boolean;
+      result?: unknown;
+    };
+    /** @enum {string} */
+    LnUrlAuthStatus: "pending" | "authenticated";
+    LnUrlAuthStatusResponse: {
+      session?: null | components["schemas"]["AuthSession"];
+      status: components["schemas"]["LnUrlAuthStatus"];
+    };
+    /**
+     * @description Body of an LNURL callback error (LUD-02/LUD-03), as written by `ApiResponse::into_response`.
+     * Only used to document those routes.
+     */
+    LnurlErrorBody_ChannelErrorCode: {
+      /**
+       * @description Reason a channel request was rejected, returned as `code` next to the LUD-02 `reason`.
+       * @enum {string}
+       */
+      code:
+        | "invalid_request"
+        | "invalid_remote_id"
+        | "invalid_flag"
+        | "amount_below_minimum"
+        | "amount_above_maximum"
+        | "spending_limit_exceeded"
+        | "peer_unreachable"
+        | "invalid_k1"
+        | "rune_rejected"
+        | "node_error";
+      /** @description Human-readable reason, shown by wallets. */
+      reason: string;
+      /** @description Always `ERROR`. */
+      status: string;
+    };
+    /**
+     * @description Body of an LNURL callback error (LUD-02/LUD-03), as written by `ApiResponse::into_response`.
+     * Only used to document those routes.
+     */
+    LnurlErrorBody_WithdrawErrorCode: {
+      /**
+       * @description Reason a withdraw was rejected, returned as `code` next to the LUD-03 `reason`.
+       * @enum {string}
+       */
+      code:
+        | "invalid_request"
+        | "amount_required"
+        | "invalid_amount"
+        | "sweep_disabled"
+        | "amount_below_minimum"
+        | "amount_above_maximum"
+        | "invalid_destination"
+        | "wrong_network"
+        | "spending_limit_exceeded"
+        | "invalid_k1"
+        | "rune_rejected"
+        | "node_error";
+      /** @description Human-readable reason, shown by wallets. */
+      reason: string;
+      /** @description Always `ERROR`. */
+      status: string;
+    };
+    Lsps1Bolt11Payment: {
+      expires_at: string;
+      fee_total_sat: string;
+      invoice: string;
+      order_total_sat: string;
+      state: components["schemas"]["PaymentState"];
+    };
+    Lsps1Channel: {
+      /** @description Estimate based on `channel_expiry_blocks`. */
+      expires_at: string;
+      funded_at: string;
+      /** @description `<txid>:<vout>` of the funding output. */
+      funding_outpoint: string;
+    };
+    Lsps1OnchainPayment: {
+      address: string;
+      expires_at: string;
+      fee_total_sat: string;
+      /**
+       * Format: int64
+       * @description Always null: unconfirmed payments are not accepted.
+       */
+      min_fee_for_0conf?: number | null;
+      /** Format: int32 */
+      min_onchain_payment_confirmations: number;
+      order_total_sat: string;
+      refund_onchain_address?: string | null;
+      state: components["schemas"]["PaymentState"];
+    };
+    /** @description Result of `lsps1.get_info`. */
+    Lsps1Options: {
+      max_channel_balance_sat: string;
+      /** Format: int32 */
+      max_channel_expiry_blocks: number;
+      max_initial_client_balance_sat: string;
+      max_initial_lsp_balance_sat: string;
+      min_channel_balance_sat: string;
+      /** Format: int32 */
+      min_funding_confirms_within_blocks: number;
+      min_initial_client_balance_sat: string;
+      min_initial_lsp_balance_sat: string;
+      /** Format: int32 */
+      min_required_channel_confirmations: number;
+      supports_zero_channel_reserve: boolean;
+    };
+    /** @description Order as returned by `lsps1.create_order` and `lsps1.get_order`. */
+    Lsps1Order: {
+      announce_channel: boolean;
+      channel?: null | components["schemas"]["Lsps1Channel"];
+      /** Format: int32 */
+      channel_expiry_blocks: number;
+      client_balance_sat: string;
+      /** @description RFC 3339 timestamp. */
+      created_at: string;
+      /** Format: int32 */
+      funding_confirms_within_blocks: number;
+      lsp_balance_sat: string;
+      order_id: string;
+      order_state: components["schemas"]["OrderState"];
+      payment: components["schemas"]["Lsps1Payment"];
+      /** Format: int32 */
+      required_channel_confirmations: number;
+      token: string;
+    };
+    Lsps1Payment: {
+      bolt11: components["schemas"]["Lsps1Bolt11Payment"];
+      onchain?: null | components["schemas"]["Lsps1OnchainPayment"];
+    };
+    /**
+     * @description Paid order that failed: the node owes the client its payment back. Neither payment method
+     * can be refunded automatically (a BOLT11 payment has no return path, an on-chain refund needs an
+     * operator's fee decision), so these are listed until marked refunded.
+     */
+    Lsps1Refund: {
+      /**
+       * Format: int64
+       * @description What the client paid, in satoshis.
+       */
+      amount_sat: number;
+      client_node_id: string;
+      order_id: string;
+      paid_at: string;
+      /** @description `bolt11` or `onchain`. */
+      paid_via: string;
+      /** @description Why the order failed. */
+      reason?: string | null;
+      /** @description Refund address the client gave with the order, if any. */
+      refund_onchain_address?: string | null;
+    };
+    Lsps1RefundsResponse: {
+      refunds: components["schemas"]["Lsps1Refund"][];
+    };
+    /** @description Result of `lsps2.get_info`. */
+    Lsps2Info: {
+      opening_fee_params_menu: components["schemas"]["OpeningFeeParams"][];
+    };
+    /** @description JSON-RPC 2.0 error object. */
+    LspsError: {
+      /** Format: int32 */
+      code: number;
+      data?: Record<string, never> | null;
+      message: string;
+    };
+    /** @description JSON-RPC 2.0 request. LSPS0 requires `params` to be an object. */
+    LspsRequest: {
+      /** @description Request id, echoed in the response. */
+      id: Record<string, never>;
+      /** @description Always `2.0`. */
+      jsonrpc: string;
+      /** @description Method name, e.g. `lsps1.get_info`. */
+      method: string;
+      /** @description Method parameters. */
+      params?: Record<string, never>;
+    };
+    /** @description JSON-RPC 2.0 response; exactly one of `result` and `error` is set. */
+    LspsResponse: {
+      error?: null | components["schemas"]["LspsError"];
+      id: Record<string, never>;
+      jsonrpc: string;
+      result?: Record<string, never> | null;
+    };
+    OfferEntry: {
+      /** @description Whether the offer can still be paid. */
+      active: boolean;
+      /** @description BOLT12 offer string to share with payers. */
+      bolt12: string;
+      /** @description Offer id (hex), used to disable the offer. */
+      offer_id: string;
+      /** @description Whether the offer expires after a single payment. */
+      single_use: boolean;
+      /** @description Whether the offer has been paid at least once. */
+      used: boolean;
+    };
+    OpenChannelRequest: {
+      /**
+       * Format: int64
+       * @description Channel size in satoshis; defaults to the configured minimum.
+       */
+      amount?: number | null;
+      /** @description `1` to abandon the request instead of opening a channel. */
+      cancel?: string | null;
+      k1: string;
+      /** @description `1` to open an unannounced channel. */
+      private?: string | null;
+      /** @description Node id of the wallet; `remote_id` is accepted for older clients. */
+      remoteid: string;
+    };
+    OpenChannelResponse: {
+      /** @description Id of the new channel (absent when cancelled). */
+      channel_id?: string | null;
+      /** @description Always `OK`; failures are returned as errors. */
+      status: string;
+      /** @description Funding transaction id (absent when cancelled). */
+      txid?: string | null;
+    };
+    /**
+     * @description Entry of the `lsps2.get_info` fee menu. `promise` signs the other fields, so `buy` can
+     * check them without keeping the menus around.
+     */
+    OpeningFeeParams: {
+      /** Format: int32 */
+      max_client_to_self_delay: number;
+      max_payment_size_msat: string;
+      min_fee_msat: string;
+      /** Format: int32 */
+      min_lifetime: number;
+      min_payment_size_msat: string;
+      promise: string;
+      /**
+       * Format: int32
+       * @description Parts per million of the payment.
+       */
+      proportional: number;
+      /** @description RFC 3339 timestamp. */
+      valid_until: string;
+    };
+    /** @enum {string} */
+    OrderState: "CREATED" | "COMPLETED" | "FAILED";
+    PayInvoiceRequest: {
+      /**
+       * Format: int64
+       * @description Amount in millisatoshis, required only for invoices without an amount.
+       */
+      amount_msat?: number | null;
+      /** @description BOLT11 invoice to pay. */
+      bolt11: string;
+    };
+    PayOfferRequest: {
+      /**
+       * Format: int64
+       * @description Amount in millisatoshis, required only for offers without an amount.
+       */
+      amount_msat?: number | null;
+      /** @description BOLT12 offer to pay. */
+      offer: string;
+      /** @description Note sent to the offer issuer along with the invoice request. */
+      payer_note?: string | null;
+      /**
+       * Format: int64
+       * @description Quantity to request, for offers with `quantity_max`.
+       */
+      quantity?: number | null;
+    };
+    PaymentEntry: {
+      /**
+       * Format: int64
+       * @description Amount delivered to the destination in millisatoshis.
+       */
+      amount_msat?: number | null;
+      /**
+       * Format: int64
+       * @description Amount sent including routing fees in millisatoshis.
+       */
+      amount_sent_msat?: number | null;
+      /** @description BOLT11 invoice that was paid. */
+      bolt11?: string | null;
+      /**
+       * Format: int64
+       * @description Unix timestamp (seconds) when the payment completed.
+       */
+      completed_at?: number | null;
+      /**
+       * Format: int64
+       * @description Unix timestamp (seconds) when the payment was started.
+       */
+      created_at: number;
+      /** @description Invoice description. */
+      description?: string | null;
+      /** @description Destination node public key (hex). */
+      destination?: string | null;
+      /**
+       * Format: int64
+       * @description Routing fee paid in millisatoshis, when both amounts are known.
+       */
+      fee_msat?: number | null;
+      /**
+       * Format: int64
+       * @description Number of HTLC parts the payment was split into (not a route length).
+       */
+      htlc_parts?: number | null;
+      /** @description Payment hash (hex). */
+      payment_hash: string;
+      /** @description Payment preimage (hex), only once complete. */
+      payment_preimage?: string | null;
+      /** @description Payment status. */
+      status: components["schemas"]["PaymentStatus"];
+    };
+    /**
+     * @description Outcome of a payment. CLN's `pay`, `xpay` and `keysend` don't report the route they used, so
+     * there is no route length; `htlc_parts` counts the parts of a multi-part payment instead.
+     */
+    PaymentResult: {
+      /**
+       * Format: int64
+       * @description Amount delivered to the destination in millisatoshis.
+       */
+      amount_msat: number;
+      /**
+       * Format: int64
+       * @description Amount sent including routing fees in millisatoshis.
+       */
+      amount_sent_msat: number;
+      /** @description Destination node public key (hex). */
+      destination?: string | null;
+      /**
+       * Format: int64
+       * @description Routing fee paid in millisatoshis.
+       */
+      fee_msat: number;
+      /**
+       * Format: int32
+       * @description Number of HTLC parts the payment was split into (not a route length).
+       */
+      htlc_parts: number;
+      /** @description Payment hash (hex). */
+      payment_hash: string;
+      /** @description Payment preimage (hex), proof of payment. */
+      payment_preimage: string;
+      /** @description Payment status. */
+      status: components["schemas"]["PaymentStatus"];
+    };
+    /** @enum {string} */
+    PaymentState: "EXPECT_PAYMENT" | "HOLD" | "PAID" | "REFUNDED";
+    /** @enum {string} */
+    PaymentStatus: "pending" | "complete" | "failed";
+    /** @enum {string} */
+    PeerDirection: "in" | "out";
+    PeerEntry: {
+      connected: boolean;
+      /** @description Feature bitmap (hex) the peer sent in `init`. */
+      features?: string | null;
+      /** @description Node id of the peer. */
+      id: string;
+      /** @description Names of the well-known features in `features`. */
+      known_features: string[];
+      /** @description Addresses of the current connection. */
+      netaddr: string[];
+      /**
+       * Format: int32
+       * @description Channels with the peer, in any state.
+       */
+      num_channels: number;
+      /** @description Our address as seen by the peer. */
+      remote_addr?: string | null;
+    };
+    PolicyUsageResponse: {
+      /**
+       * Format: int64
+       * @description Version of the runtime config the limits come from.
+       */
+      config_version: number;
+      /** @description Usage per flow (`withdraw`, `channel`, `lightning`), then the total across flows. */
+      usage: components["schemas"]["SpendingUsage"][];
+    };
+    RecentRequestEntry: {
+      /** @description Best-effort client address (usually from X-Forwarded-For when behind nginx). */
+      client_addr: string;
+      /** @description HTTP method (e.g. GET). */
+      method: string;
+      /** @description Convenience boolean: true when status < 400. */
+      ok: boolean;
+      /** @description Request path (no query string). */
+      path: string;
+      /**
+       * Format: int32
+       * @description HTTP status code returned by the gateway.
+       */
+      status: number;
+      /**
+       * Format: int64
+       * @description Unix timestamp in milliseconds.
+       */
+      ts_ms: number;
+    };
+    /**
+     * @description Kind of outgoing funds movement counted against the spending limits.
+     * @enum {string}
+     */
+    SpendFlow: "withdraw" | "channel" | "lightning";
+    /** @description Usage of one flow, or of all flows together. */
+    SpendingUsage: {
+      /** @description Rolling last 24 hours. */
+      day: components["schemas"]["WindowUsage"];
+      flow?: null | components["schemas"]["SpendFlow"];
+      /** @description Rolling last hour. */
+      hour: components["schemas"]["WindowUsage"];
+      /**
+       * Format: int64
+       * @description Maximum amount of a single spend in millisatoshis (absent when unlimited).
+       */
+      per_request_limit_msat?: number | null;
+    };
+    /**
+     * @description One-time ticket opening an `/events` or `/ws` stream, for browsers that can't set headers on
+     * `EventSource` or `WebSocket`.
+     */
+    StreamTicket: {
+      /**
+       * Format: int64
+       * @description Unix timestamp (seconds) after which the ticket is no longer accepted.
+       */
+      expires_at: number;
+      /** @description Opaque ticket, sent as `?ticket=<ticket>`. */
+      ticket: string;
+    };
+    TlvRecord: {
+      /**
+       * Format: int64
+       * @description Record type: odd and in the custom range (>= 65536).
+       */
+      type: number;
+      /** @description Record value (hex). */
+      value: string;
+    };
+    UpdateChannelFeesRequest: {
+      /** Format: int64 */
+      fee_base_msat?: number | null;
+      /**
+       * Format: int32
+       * @description Proportional fee in parts per million.
+       */
+      fee_ppm?: number | null;
+      /** Format: int64 */
+      htlc_maximum_msat?: number | null;
+      /** Format: int64 */
+      htlc_minimum_msat?: number | null;
+    };
+    WebhookDelivery: {
+      /**
+       * Format: int32
+       * @description Number of attempts made so far.
+       */
+      attempts: number;
+      /**
+       * Format: int64
+       * @description Unix timestamp in milliseconds when the delivery was created.
+       */
+      created_ms: number;
+      /** @description Endpoint the payload is delivered to. */
+      endpoint: string;
+      /** @description Event type (e.g. invoice.settled). */
+      event_type: string;
+      /** @description Unique delivery identifier (also sent as `X-Webhook-Id`). */
+      id: string;
+      /** @description Error reported by the last failed attempt. */
+      last_error?: string | null;
+      /**
+       * Format: int64
+       * @description Unix timestamp in milliseconds of the next attempt (pending deliveries only).
+       */
+      next_attempt_ms: number;
+      /** @description JSON body POSTed to the endpoint. */
+      payload: unknown;
+      /** @description Pending deliveries are retried; failed ones exhausted their attempts. */
+      status: components["schemas"]["WebhookDeliveryStatus"];
+    };
+    /** @enum {string} */
+    WebhookDeliveryStatus: "pending" | "failed";
+    /** @description Usage of one window. */
+    WindowUsage: {
+      /**
+       * Format: int64
+       * @description Configured ceiling in millisatoshis (absent when unlimited).
+       */
+      limit_msat?: number | null;
+      /**
+       * Format: int64
+       * @description Amount still available in millisatoshis (absent when unlimited).
+       */
+      remaining_msat?: number | null;
+      /**
+       * Format: int64
+       * @description Amount spent (or reserved by calls in progress) in the window, in millisatoshis.
+       */
+      used_msat: number;
+    };
+    /**
+     * @description Reason a withdraw was rejected, returned as `code` next to the LUD-03 `reason`.
+     * @enum {string}
+     */
+    WithdrawErrorCode:
+      | "invalid_request"
+      | "amount_required"
+      | "invalid_amount"
+      | "sweep_disabled"
+      | "amount_below_minimum"
+      | "amount_above_maximum"
+      | "invalid_destination"
+      | "wrong_network"
+      | "spending_limit_exceeded"
+      | "invalid_k1"
+      | "rune_rejected"
+      | "node_error";
+    WithdrawRequestResponse: {
+      /** @description Second-level URL to trigger WithdrawCallback */
+      callback: string;
+      /** @description Default description for the withdrawal invoice */
+      defaultDescription: string;
+      /** @description Unique identifier for this withdrawal request */
+      k1: string;
+      /**
+       * Format: int64
+       * @description Maximum withdrawable amount in millisatoshis
+       */
+      maxWithdrawable: number;
+      /**
+       * Format: int64
+       * @description Minimum withdrawable amount in millisatoshis
+       */
+      minWithdrawable: number;
+      /** @description Type of request, must be "withdrawRequest" */
+      tag: string;
+    };
+  };
+  responses: never;
+  parameters: never;
+  requestBodies: never;
+  headers: never;
+  pathItems: never;
+}
+export type $defs = Record<string, never>;
+export interface operations {
+  getAuthSession: {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    requestBody?: never;
+    responses: {
+      /** @description The session of the bearer token */
+      200: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["AuthSession"];
+        };
+      };
+      /** @description Missing, unknown or expired session token */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+    };
+  };
+  logout: {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    requestBody?: never;
+    responses: {
+      /** @description Session revoked */
+      200: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Missing, unknown or expired session token */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+    };
+  };
+  withdraw: {
+    parameters: {
+      query: {
+        /** @description One-time token from /withdraw-request */
+        k1: string;
+        /** @description Bitcoin address on the node's network */
+        destination: string;
+        /** @description Withdraw amount in satoshis, within the advertised minWithdrawable/maxWithdrawable (msat); `all` sweeps the confirmed balance when SERVER_WITHDRAW_ALLOW_ALL is set */
+        amount: string;
+      };
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    requestBody?: never;
+    responses: {
+      /** @description Withdraw result */
+      200: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["IssueWithdrawResponse"];
+        };
+      };
+      /** @description Invalid amount, destination or k1; see `code` */
+      400: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["LnurlErrorBody_WithdrawErrorCode"];
+        };
+      };
+      /** @description The presented rune does not allow withdraw, or a spending limit would be exceeded */
+      403: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["LnurlErrorBody_WithdrawErrorCode"];
+        };
+      };
+      /** @description The CoreLightning node encountered an error */
+      502: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["LnurlErrorBody_WithdrawErrorCode"];
+        };
+      };
+    };
+  };
+  lnurlAuthCallback: {
+    parameters: {
+      query: {
+        /** @description Challenge token from /lnurl-auth */
+        k1: string;
+        /** @description Signature of k1 (hex, compact or DER) */
+        sig: string;
+        /** @description Signing public key (hex, compressed) */
+        key: string;
+        /** @description Optional LNURL-auth action */
+        action?: string;
+        /** @description Optional LNURL-auth tag */
+        tag?: string;
+      };
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    requestBody?: never;
+    responses: {
+      /** @description LNURL-auth callback response */
+      200: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["LnUrlAuthResponse"];
+        };
+      };
+    };
+  };
+  openChannel: {
+    parameters: {
+      query: {
+        /** @description One-time token from /channel-request */
+        k1: string;
+        /** @description Node id of the wallet (`remote_id` is accepted too) */
+        remoteid: string;
+        /** @description `1` to open an unannounced channel */
+        private?: string;
+        /** @description `1` to abandon the request */
+        cancel?: string;
+        /** @description Channel size in satoshis, within SERVER_CHANNEL_MIN_SAT and SERVER_CHANNEL_MAX_SAT; defaults to the minimum */
+        amount?: number;
+      };
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    requestBody?: never;
+    responses: {
+      /** @description Channel funded, or request cancelled */
+      200: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["OpenChannelResponse"];
+        };
+      };
+      /** @description Invalid remoteid, flag, amount or k1; see `code` */
+      400: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["LnurlErrorBody_ChannelErrorCode"];
+        };
+      };
+      /** @description The presented rune does not allow fundchannel, or a spending limit would be exceeded */
+      403: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["LnurlErrorBody_ChannelErrorCode"];
+        };
+      };
+      /** @description The node could not reach the wallet, or CoreLightning failed to fund the channel */
+      502: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["LnurlErrorBody_ChannelErrorCode"];
+        };
+      };
+    };
+  };
+  channelRequest: {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    requestBody?: never;
+    responses: {
+      /** @description LNURL Channel Request */
+      200: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["ChannelRequestResponse"];
+        };
+      };
+      /** @description Admin session or API key required */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Not an admin session, or the API key lacks the scope */
+      403: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description The CoreLightning node encountered an error */
+      502: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description The server is shutting down */
+      503: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+    };
+  };
+  listChannels: {
+    parameters: {
+      query?: {
+        /** @description Only return the channels with this peer. */
+        peer_id?: string | null;
+      };
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    requestBody?: never;
+    responses: {
+      /** @description Channels of the node, including pending and closing ones */
+      200: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["ChannelEntry"][];
+        };
+      };
+      /** @description Invalid peer id */
+      400: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Admin session or API key required */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Not an admin session, or the API key lacks the scope */
+      403: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description The CoreLightning node encountered an error */
+      502: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+    };
+  };
+  closeChannel: {
+    parameters: {
+      query?: never;
+      header?: never;
+      path: {
+        /** @description Channel id, short channel id, or peer id (when there is a single channel with the peer) */
+        id: string;
+      };
+      cookie?: never;
+    };
+    requestBody: {
+      content: {
+        "application/json": components["schemas"]["CloseChannelRequest"];
+      };
+    };
+    responses: {
+      /** @description Closing transaction broadcast */
+      200: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["CloseChannelResponse"];
+        };
+      };
+      /** @description Invalid destination, or several channels with the peer */
+      400: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Admin session or API key required */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Not an admin session, the API key lacks the scope, or the rune does not allow close */
+      403: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description No such channel */
+      404: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description The CoreLightning node encountered an error */
+      502: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+    };
+  };
+  updateChannelFees: {
+    parameters: {
+      query?: never;
+      header?: never;
+      path: {
+        /** @description Channel id, short channel id, peer id (all its channels), or `all` */
+        id: string;
+      };
+      cookie?: never;
+    };
+    requestBody: {
+      content: {
+        "application/json": components["schemas"]["UpdateChannelFeesRequest"];
+      };
+    };
+    responses: {
+      /** @description The updated channels */
+      200: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["ChannelFeesEntry"][];
+        };
+      };
+      /** @description Nothing to update, or htlc_minimum_msat above htlc_maximum_msat */
+      400: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Admin session or API key required */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Not an admin session, the API key lacks the scope, or the rune does not allow setchannel */
+      403: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description No such channel */
+      404: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description The CoreLightning node encountered an error */
+      502: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+    };
+  };
+  reloadConfig: {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    requestBody?: never;
+    responses: {
+      /** @description Reloadable settings re-read from .env and the config file */
+      200: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["ConfigReloadResponse"];
+        };
+      };
+      /** @description Admin session or API key required */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Not an admin session, or the API key lacks the scope */
+      403: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description The new settings are invalid; the current ones stay in effect */
+      422: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+    };
+  };
+  invoiceEvents: {
+    parameters: {
+      query?: {
+        /** @description Only forward settlements of the invoice with this label. */
+        label?: string | null;
+        /** @description Only forward settlements of the invoice with this payment hash (hex). */
+        payment_hash?: string | null;
+        /** @description One-time ticket from `POST /events/ticket`, for browsers that can't send credentials in headers */
+        ticket?: string;
+      };
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    requestBody?: never;
+    responses: {
+      /** @description Server-Sent Events stream of `invoice_settled` events */
+      200: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "text/event-stream": components["schemas"]["InvoiceSettledEvent"];
+        };
+      };
+      /** @description Admin session, API key or a valid ticket required */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Not an admin session, or the API key lacks the scope */
+      403: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+    };
+  };
+  createStreamTicket: {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    requestBody?: never;
+    responses: {
+      /** @description One-time ticket for `/events` or `/ws` */
+      200: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["StreamTicket"];
+        };
+      };
+      /** @description Admin session or API key required */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Not an admin session, or the API key lacks the scope */
+      403: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+    };
+  };
+  health: {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    requestBody?: never;
+    responses: {
+      /** @description Gateway and CoreLightning status */
+      200: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["HealthResponse"];
+        };
+      };
+      /** @description API key or admin session required (once `SERVER_API_KEYS` is set) */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description The API key lacks the scope, or not an admin session */
+      403: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description The CoreLightning node encountered an error */
+      502: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+    };
+  };
+  listInvoices: {
+    parameters: {
+      query?: {
+        /** @description Only return invoices with this status. */
+        status?: null | components["schemas"]["InvoiceStatus"];
+        /** @description Creation index to start from (use `next_start` from the previous page). */
+        start?: number | null;
+        /** @description Maximum number of invoices scanned for this page (default: 25, max: 100). */
+        limit?: number | null;
+      };
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    requestBody?: never;
+    responses: {
+      /** @description Page of invoices in creation order */
+      200: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["ListInvoicesResponse"];
+        };
+      };
+      /** @description Admin session or API key required */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Not an admin session, or the API key lacks the scope */
+      403: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description The CoreLightning node encountered an error */
+      502: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+    };
+  };
+  createInvoice: {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    requestBody: {
+      content: {
+        "application/json": components["schemas"]["CreateInvoiceRequest"];
+      };
+    };
+    responses: {
+      /** @description Invoice created */
+      201: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["CreateInvoiceResponse"];
+        };
+      };
+      /** @description Invalid invoice parameters */
+      400: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Admin session or API key required, or a rune (`SERVER_REQUIRE_RUNE`) */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Not an admin session, the API key lacks the scope, or the rune does not allow invoice */
+      403: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description The CoreLightning node encountered an error */
+      502: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+    };
+  };
+  getInvoice: {
+    parameters: {
+      query?: never;
+      header?: never;
+      path: {
+        /** @description Invoice label returned by POST /invoices */
+        label: string;
+      };
+      cookie?: never;
+    };
+    requestBody?: never;
+    responses: {
+      /** @description Invoice details */
+      200: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["InvoiceEntry"];
+        };
+      };
+      /** @description Admin session or API key required */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Not an admin session, or the API key lacks the scope */
+      403: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description No invoice with this label */
+      404: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description The CoreLightning node encountered an error */
+      502: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+    };
+  };
+  deleteInvoice: {
+    parameters: {
+      query?: never;
+      header?: never;
+      path: {
+        /** @description Invoice label returned by POST /invoices */
+        label: string;
+      };
+      cookie?: never;
+    };
+    requestBody?: never;
+    responses: {
+      /** @description The deleted invoice */
+      200: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["InvoiceEntry"];
+        };
+      };
+      /** @description Admin session or API key required */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Not an admin session, or the API key lacks the scope */
+      403: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description No invoice with this label */
+      404: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description The CoreLightning node encountered an error */
+      502: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+    };
+  };
+  lnurlAuthRequest: {
+    parameters: {
+      query?: {
+        /** @description Optional action enum: register | login | link | auth */
+        action?: components["schemas"]["LnUrlAuthRequestAction"];
+      };
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    requestBody?: never;
+    responses: {
+      /** @description LNURL-auth challenge */
+      200: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["LnUrlAuthRequestResponse"];
+        };
+      };
+      /** @description The server is shutting down */
+      503: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+    };
+  };
+  lnurlAuthStatus: {
+    parameters: {
+      query?: never;
+      header?: never;
+      path: {
+        /** @description Challenge returned by /lnurl-auth-request */
+        k1: string;
+      };
+      cookie?: never;
+    };
+    requestBody?: never;
+    responses: {
+      /** @description Login status, with the session once signed */
+      200: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["LnUrlAuthStatusResponse"];
+        };
+      };
+      /** @description Unknown k1 */
+      404: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description The session was already handed out or has expired, or the challenge expired */
+      410: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+    };
+  };
+  lnurlAuthStatusEvents: {
+    parameters: {
+      query?: never;
+      header?: never;
+      path: {
+        /** @description Challenge returned by /lnurl-auth-request */
+        k1: string;
+      };
+      cookie?: never;
+    };
+    requestBody?: never;
+    responses: {
+      /** @description Server-Sent Events stream with a single `authenticated` event */
+      200: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "text/event-stream": components["schemas"]["LnUrlAuthStatusResponse"];
+        };
+      };
+      /** @description Unknown k1 */
+      404: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description The session was already handed out or has expired, or the challenge expired */
+      410: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+    };
+  };
+  lsps: {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    requestBody: {
+      content: {
+        "application/json": components["schemas"]["LspsRequest"];
+      };
+    };
+    responses: {
+      /** @description JSON-RPC 2.0 response; failures are reported in `error` */
+      200: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["LspsResponse"];
+        };
+      };
+    };
+  };
+  lsps1Refunds: {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    requestBody?: never;
+    responses: {
+      /** @description Paid LSPS1 orders that failed and have to be refunded by hand */
+      200: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["Lsps1RefundsResponse"];
+        };
+      };
+      /** @description Admin session or API key required */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Not an admin session, or the API key lacks the scope */
+      403: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+    };
+  };
+  lsps1MarkRefunded: {
+    parameters: {
+      query?: never;
+      header?: never;
+      path: {
+        /** @description LSPS1 order id */
+        order_id: string;
+      };
+      cookie?: never;
+    };
+    requestBody?: never;
+    responses: {
+      /** @description The order, its payment now `REFUNDED`. Only records the refund, which has to be sent by other means */
+      200: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["Lsps1Order"];
+        };
+      };
+      /** @description Admin session or API key required */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Not an admin session, or the API key lacks the scope */
+      403: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description No order with this id awaits a refund */
+      404: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+    };
+  };
+  lsps2Htlc: {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    requestBody: {
+      content: {
+        "application/json": components["schemas"]["HtlcAccepted"];
+      };
+    };
+    responses: {
+      /** @description Answer for CLN's htlc_accepted hook; HTLCs to an LSPS2 intercept SCID are held until the channel is open */
+      200: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["HtlcResolution"];
+        };
+      };
+      /** @description API key required */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description The API key lacks the scope */
+      403: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+    };
+  };
+  metrics: {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    requestBody?: never;
+    responses: {
+      /** @description Prometheus text exposition of the gateway counters */
+      200: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "text/plain": string;
+        };
+      };
+      /** @description API key required */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description The API key lacks the scope */
+      403: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+    };
+  };
+  listOffers: {
+    parameters: {
+      query?: {
+        /** @description Only return offers that can still be paid. */
+        active_only?: boolean | null;
+      };
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    requestBody?: never;
+    responses: {
+      /** @description Offers created on this node */
+      200: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["OfferEntry"][];
+        };
+      };
+      /** @description Admin session or API key required */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Not an admin session, or the API key lacks the scope */
+      403: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description The CoreLightning node encountered an error */
+      502: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+    };
+  };
+  createOffer: {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    requestBody: {
+      content: {
+        "application/json": components["schemas"]["CreateOfferRequest"];
+      };
+    };
+    responses: {
+      /** @description An identical offer already exists */
+      200: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["OfferEntry"];
+        };
+      };
+      /** @description Offer created */
+      201: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["OfferEntry"];
+        };
+      };
+      /** @description Invalid offer parameters */
+      400: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Admin session or API key required, or a rune (`SERVER_REQUIRE_RUNE`) */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Not an admin session, the API key lacks the scope, or the rune does not allow offer */
+      403: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description The CoreLightning node encountered an error */
+      502: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
     };
-    /** @enum {string} */
-    LightningStatus: "ok" | "syncing";
-    LnUrlAuthQuery: {
-      /** @description One-time challenge (32 bytes hex) */
-      k1: string;
-      /** @description Compressed (33-byte) secp256k1 public key hex */
-      key: string;
-      /** @description DER-hex-encoded ECDSA signature for `k1` (some wallets may use compact 64-byte) */
-      sig: string;
+  };
+  payOffer: {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
     };
-    /** @enum {string} */
-    LnUrlAuthRequestAction: "register" | "login" | "link" | "auth";
-    LnUrlAuthRequestQuery: {
-      action?: null | components["schemas"]["LnUrlAuthRequestAction"];
+    requestBody: {
+      content: {
+        "application/json": components["schemas"]["PayOfferRequest"];
+      };
     };
-    LnUrlAuthRequestResponse: {
-      action?: null | components["schemas"]["LnUrlAuthRequestAction"];
-      /** @description Callback URL to be called by the wallet with `sig` and `key` */
-      callback: string;
-      /** @description (32 bytes hex) One-time challenge */
-      k1: string;
-      /** @description Type of request, must be "login" per LUD-04 */
-      tag: string;
+    responses: {
+      /** @description Payment result */
+      200: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["PaymentResult"];
+        };
+      };
+      /** @description Invalid offer or amount */
+      400: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Admin session or API key required */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Not an admin session, the API key lacks the scope, the rune does not allow pay, or the payment violates the spending policy or a spending limit */
+      403: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description The CoreLightning node encountered an error */
+      502: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
     };
-    LnUrlAuthResponse: {
-      error?: string | null;
-      ok: boolean;
-      result?: unknown;
+  };
+  disableOffer: {
+    parameters: {
+      query?: never;
+      header?: never;
+      path: {
+        /** @description Offer id (hex) */
+        offer_id: string;
+      };
+      cookie?: never;
     };
-    OpenChannelRequest: {
-      /** Format: int64 */
-      amount?: number | null;
-      announce?: boolean | null;
-      k1: string;
-      remote_id: string;
+    requestBody?: never;
+    responses: {
+      /** @description The disabled offer */
+      200: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["OfferEntry"];
+        };
+      };
+      /** @description Invalid offer id */
+      400: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Admin session or API key required */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Not an admin session, or the API key lacks the scope */
+      403: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description No offer with this id */
+      404: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description The CoreLightning node encountered an error */
+      502: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
     };
-    OpenChannelResponse: {
-      error?: string | null;
-      ok: boolean;
-      result?: unknown;
+  };
+  payInvoice: {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
     };
-    RecentRequestEntry: {
-      /** @description Best-effort client address (usually from X-Forwarded-For when behind nginx). */
-      client_addr: string;
-      /** @description HTTP method (e.g. GET). */
-      method: string;
-      /** @description Convenience boolean: true when status < 400. */
-      ok: boolean;
-      /** @description Request path (no query string). */
-      path: string;
-      /**
-       * Format: int32
-       * @description HTTP status code returned by the gateway.
-       */
-      status: number;
-      /**
-       * Format: int64
-       * @description Unix timestamp in milliseconds.
-       */
-      ts_ms: number;
+    requestBody: {
+      content: {
+        "application/json": components["schemas"]["PayInvoiceRequest"];
+      };
     };
-    WithdrawRequestResponse: {
-      /** @description Second-level URL to trigger WithdrawCallback */
-      callback: string;
-      /** @description Default description for the withdrawal invoice */
-      defaultDescription: string;
-      /** @description Unique identifier for this withdrawal request */
-      k1: string;
-      /**
-       * Format: int64
-       * @description Maximum withdrawable amount in millisatoshis
-       */
-      maxWithdrawable: number;
-      /**
-       * Format: int64
-       * @description Minimum withdrawable amount in millisatoshis
-       */
-      minWithdrawable: number;
-      /** @description Type of request, must be "withdrawRequest" */
-      tag: string;
+    responses: {
+      /** @description Payment result */
+      200: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["PaymentResult"];
+        };
+      };
+      /** @description Invalid invoice or amount */
+      400: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Admin session or API key required */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Not an admin session, the API key lacks the scope, the rune does not allow pay, or the payment violates the spending policy or a spending limit */
+      403: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description The CoreLightning node encountered an error */
+      502: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+    };
+  };
+  keysend: {
+    parameters: {
+      query?: never;
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    requestBody: {
+      content: {
+        "application/json": components["schemas"]["KeysendRequest"];
+      };
+    };
+    responses: {
+      /** @description Payment result */
+      200: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["PaymentResult"];
+        };
+      };
+      /** @description Invalid destination, amount or TLV records */
+      400: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Admin session or API key required */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Not an admin session, the API key lacks the scope, the rune does not allow keysend, or the payment violates the spending policy or a spending limit */
+      403: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description The CoreLightning node encountered an error */
+      502: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+    };
+  };
+  getPayment: {
+    parameters: {
+      query?: never;
+      header?: never;
+      path: {
+        /** @description Payment hash (hex) */
+        payment_hash: string;
+      };
+      cookie?: never;
+    };
+    requestBody?: never;
+    responses: {
+      /** @description Payment details */
+      200: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["PaymentEntry"];
+        };
+      };
+      /** @description Invalid payment hash */
+      400: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Admin session or API key required */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Not an admin session, or the API key lacks the scope */
+      403: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description No payment with this hash */
+      404: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description The CoreLightning node encountered an error */
+      502: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
     };
   };
-  responses: never;
-  parameters: never;
-  requestBodies: never;
-  headers: never;
-  pathItems: never;
-}
-export type $defs = Record<string, never>;
-export interface operations {
-  withdraw: {
+  listPeers: {
     parameters: {
-      query: {
-        /** @description One-time token from /withdraw-request */
-        k1: string;
-        /** @description Bitcoin address (or other supported withdraw destination) */
-        destination: string;
-        /** @description Withdraw amount in satoshis */
-        amount?: number;
-      };
+      query?: never;
       header?: never;
       path?: never;
       cookie?: never;
     };
     requestBody?: never;
     responses: {
-      /** @description Withdraw result */
+      /** @description Connected peers and peers we have channels with */
       200: {
         headers: {
           [name: string]: unknown;
         };
         content: {
-          "application/json": components["schemas"]["IssueWithdrawResponse"];
+          "application/json": components["schemas"]["PeerEntry"][];
+        };
+      };
+      /** @description Admin session or API key required */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Not an admin session, or the API key lacks the scope */
+      403: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description The CoreLightning node encountered an error */
+      502: {
+        headers: {
+          [name: string]: unknown;
         };
+        content?: never;
       };
     };
   };
-  lnurlAuthCallback: {
+  connectPeer: {
     parameters: {
-      query: {
-        /** @description Challenge token from /lnurl-auth */
-        k1: string;
-        /** @description Signature of k1 (hex, compact or DER) */
-        sig: string;
-        /** @description Signing public key (hex, compressed) */
-        key: string;
-        /** @description Optional LNURL-auth action */
-        action?: string;
-        /** @description Optional LNURL-auth tag */
-        tag?: string;
-      };
+      query?: never;
       header?: never;
       path?: never;
       cookie?: never;
     };
-    requestBody?: never;
+    requestBody: {
+      content: {
+        "application/json": components["schemas"]["ConnectPeerRequest"];
+      };
+    };
     responses: {
-      /** @description LNURL-auth callback response */
+      /** @description Connected (or already connected) */
       200: {
         headers: {
           [name: string]: unknown;
         };
         content: {
-          "application/json": components["schemas"]["LnUrlAuthResponse"];
+          "application/json": components["schemas"]["ConnectPeerResponse"];
+        };
+      };
+      /** @description Invalid node id or address */
+      400: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Admin session or API key required */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Not an admin session, the API key lacks the scope, or the rune does not allow connect */
+      403: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description No address given and none known from the gossip */
+      404: {
+        headers: {
+          [name: string]: unknown;
         };
+        content?: never;
+      };
+      /** @description The CoreLightning node encountered an error or the peer is unreachable */
+      502: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
       };
     };
   };
-  openChannel: {
+  disconnectPeer: {
     parameters: {
-      query: {
-        /** @description One-time token from /channel-request */
-        k1: string;
-        /** @description Remote node pubkey */
-        remote_id: string;
-        /** @description Channel funding amount in satoshis */
-        amount?: number;
-        /** @description Whether to announce channel */
-        announce?: boolean;
+      query?: {
+        /** @description Also disconnect when channels with the peer are active. */
+        force?: boolean | null;
       };
       header?: never;
-      path?: never;
+      path: {
+        /** @description Node id of the peer */
+        id: string;
+      };
       cookie?: never;
     };
     requestBody?: never;
     responses: {
-      /** @description Open channel result */
+      /** @description The disconnected peer */
       200: {
         headers: {
           [name: string]: unknown;
         };
         content: {
-          "application/json": components["schemas"]["OpenChannelResponse"];
+          "application/json": components["schemas"]["PeerEntry"];
+        };
+      };
+      /** @description Invalid node id */
+      400: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Admin session or API key required */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Not an admin session, the API key lacks the scope, or the rune does not allow disconnect */
+      403: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Not connected to this peer */
+      404: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description The CoreLightning node encountered an error, e.g. active channels without force */
+      502: {
+        headers: {
+          [name: string]: unknown;
         };
+        content?: never;
       };
     };
   };
-  channelRequest: {
+  policyUsage: {
     parameters: {
       query?: never;
       header?: never;
@@ -427,17 +3245,24 @@ export interface operations {
     };
     requestBody?: never;
     responses: {
-      /** @description LNURL Channel Request */
+      /** @description Spending over the rolling windows against the configured limits */
       200: {
         headers: {
           [name: string]: unknown;
         };
         content: {
-          "application/json": components["schemas"]["ChannelRequestResponse"];
+          "application/json": components["schemas"]["PolicyUsageResponse"];
         };
       };
-      /** @description The CoreLightning node encountered an error */
-      502: {
+      /** @description Admin session or API key required */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Not an admin session, or the API key lacks the scope */
+      403: {
         headers: {
           [name: string]: unknown;
         };
@@ -445,26 +3270,36 @@ export interface operations {
       };
     };
   };
-  health: {
+  recent_requests: {
     parameters: {
-      query?: never;
+      query?: {
+        /** @description Maximum number of entries returned (default: 15, max: 50). */
+        limit?: number | null;
+      };
       header?: never;
       path?: never;
       cookie?: never;
     };
     requestBody?: never;
     responses: {
-      /** @description Gateway and CoreLightning status */
+      /** @description Most recent requests processed by the gateway */
       200: {
         headers: {
           [name: string]: unknown;
         };
         content: {
-          "application/json": components["schemas"]["HealthResponse"];
+          "application/json": components["schemas"]["RecentRequestEntry"][];
         };
       };
-      /** @description The CoreLightning node encountered an error */
-      502: {
+      /** @description Admin session or API key required */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Not an admin session, or the API key lacks the scope */
+      403: {
         headers: {
           [name: string]: unknown;
         };
@@ -472,34 +3307,43 @@ export interface operations {
       };
     };
   };
-  lnurlAuthRequest: {
+  clear_recent_requests: {
     parameters: {
-      query?: {
-        /** @description Optional action enum: register | login | link | auth */
-        action?: components["schemas"]["LnUrlAuthRequestAction"];
-      };
+      query?: never;
       header?: never;
       path?: never;
       cookie?: never;
     };
     requestBody?: never;
     responses: {
-      /** @description LNURL-auth challenge */
+      /** @description Clears the recent requests log */
       200: {
         headers: {
           [name: string]: unknown;
         };
-        content: {
-          "application/json": components["schemas"]["LnUrlAuthRequestResponse"];
+        content?: never;
+      };
+      /** @description Admin session or API key required */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Not an admin session, or the API key lacks the scope */
+      403: {
+        headers: {
+          [name: string]: unknown;
         };
+        content?: never;
       };
     };
   };
-  recent_requests: {
+  listWebhookDeliveries: {
     parameters: {
       query?: {
-        /** @description Maximum number of entries returned (default: 15, max: 50). */
-        limit?: number | null;
+        /** @description Only return deliveries with this status (e.g. failed). */
+        status?: null | components["schemas"]["WebhookDeliveryStatus"];
       };
       header?: never;
       path?: never;
@@ -507,28 +3351,68 @@ export interface operations {
     };
     requestBody?: never;
     responses: {
-      /** @description Most recent requests processed by the gateway */
+      /** @description Webhook deliveries not yet delivered */
       200: {
         headers: {
           [name: string]: unknown;
         };
         content: {
-          "application/json": components["schemas"]["RecentRequestEntry"][];
+          "application/json": components["schemas"]["WebhookDelivery"][];
+        };
+      };
+      /** @description Admin session or API key required */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Not an admin session, or the API key lacks the scope */
+      403: {
+        headers: {
+          [name: string]: unknown;
         };
+        content?: never;
       };
     };
   };
-  clear_recent_requests: {
+  redeliverWebhook: {
     parameters: {
       query?: never;
       header?: never;
-      path?: never;
+      path: {
+        /** @description Delivery identifier */
+        id: string;
+      };
       cookie?: never;
     };
     requestBody?: never;
     responses: {
-      /** @description Clears the recent requests log */
+      /** @description Delivery scheduled for an immediate retry */
       200: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["WebhookDelivery"];
+        };
+      };
+      /** @description Admin session or API key required */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Not an admin session, or the API key lacks the scope */
+      403: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description No queued delivery with this identifier */
+      404: {
         headers: {
           [name: string]: unknown;
         };
@@ -554,6 +3438,68 @@ export interface operations {
           "application/json": components["schemas"]["WithdrawRequestResponse"];
         };
       };
+      /** @description Admin session or API key required */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Not an admin session, or the API key lacks the scope */
+      403: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description The server is shutting down */
+      503: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+    };
+  };
+  invoiceEventsWs: {
+    parameters: {
+      query?: {
+        /** @description Only forward settlements of the invoice with this label. */
+        label?: string | null;
+        /** @description Only forward settlements of the invoice with this payment hash (hex). */
+        payment_hash?: string | null;
+        /** @description One-time ticket from `POST /events/ticket`, for browsers that can't send credentials in headers */
+        ticket?: string;
+      };
+      header?: never;
+      path?: never;
+      cookie?: never;
+    };
+    requestBody?: never;
+    responses: {
+      /** @description WebSocket upgrade; each text frame is a JSON InvoiceSettledEvent */
+      101: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content: {
+          "application/json": components["schemas"]["InvoiceSettledEvent"];
+        };
+      };
+      /** @description Admin session, API key or a valid ticket required */
+      401: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
+      /** @description Not an admin session, or the API key lacks the scope */
+      403: {
+        headers: {
+          [name: string]: unknown;
+        };
+        content?: never;
+      };
     };
   };
 }
@@ -23,4 +23,10 @@ if [[ -z "$BTC_RPC_USER" || -z "$BTC_RPC_PASSWORD" ]]; then
 fi
 
 envsubst <"$template_path" >"$out_path"
+
+# LSPS2 just-in-time channels: hand htlc_accepted to the gateway.
+if [[ -n "${LSPS2_HOOK_URL:-}" ]]; then
+  echo "plugin=/usr/local/libexec/ln-gateway/lsps2_hook.py" >>"$out_path"
+fi
+
 exec lightningd --conf="$out_path"
@@ -0,0 +1,123 @@
+#!/usr/bin/env python3
+"""CoreLightning plugin passing `htlc_accepted` to the gateway's POST /lsps2/htlc.
+
+The gateway holds HTLCs to LSPS2 intercept SCIDs until the just-in-time channel is open and
+answers `continue` for everything else. Configured through the environment of lightningd:
+
+  LSPS2_HOOK_URL      e.g. http://server:3000/lsps2/htlc (unset: every HTLC continues)
+  LSPS2_HOOK_API_KEY  API key with the lsps2:hook scope
+  LSPS2_HOOK_TIMEOUT  seconds to wait for the gateway (default 300)
+
+When the gateway can't be reached the HTLC continues, so regular forwards never depend on it.
+Only the standard library is used.
+"""
+
+import json
+import os
+import sys
+import threading
+import urllib.error
+import urllib.request
+
+URL = os.environ.get("LSPS2_HOOK_URL", "").strip()
+API_KEY = os.environ.get("LSPS2_HOOK_API_KEY", "").strip()
+TIMEOUT = float(os.environ.get("LSPS2_HOOK_TIMEOUT", "300"))
+
+CONTINUE = {"result": "continue"}
+
+write_lock = threading.Lock()
+
+
+def send(message):
+    with write_lock:
+        sys.stdout.write(json.dumps(message) + "\n\n")
+        sys.stdout.flush()
+
+
+def log(message, level="info"):
+    send({"jsonrpc": "2.0", "method": "log", "params": {"level": level, "message": message}})
+
+
+def resolve(params):
+    if not URL:
+        return CONTINUE
+
+    headers = {"content-type": "application/json"}
+    if API_KEY:
+        headers["x-api-key"] = API_KEY
+    request = urllib.request.Request(URL, json.dumps(params).encode(), headers, method="POST")
+
+    try:
+        with urllib.request.urlopen(request, timeout=TIMEOUT) as response:
+            return json.loads(response.read())
+    except urllib.error.HTTPError as e:
+        log(f"lsps2 hook: gateway answered {e.code}; continuing", "unusual")
+    except (OSError, ValueError) as e:
+        log(f"lsps2 hook: gateway unreachable ({e}); continuing", "unusual")
+    return CONTINUE
+
+
+def handle_htlc(request_id, params):
+    send({"jsonrpc": "2.0", "id": request_id, "result": resolve(params)})
+
+
+def handle(request):
+    method = request.get("method")
+    request_id = request.get("id")
+
+    if method == "getmanifest":
+        send(
+            {
+                "jsonrpc": "2.0",
+                "id": request_id,
+                "result": {
+                    "options": [],
+                    "rpcmethods": [],
+                    "hooks": [{"name": "htlc_accepted"}],
+                    "dynamic": False,
+                },
+            }
+        )
+    elif method == "init":
+        send({"jsonrpc": "2.0", "id": request_id, "result": {}})
+        if URL:
+            log(f"lsps2 hook: forwarding htlc_accepted to {URL}")
+        else:
+            log("lsps2 hook: LSPS2_HOOK_URL not set; HTLCs continue untouched", "unusual")
+    elif method == "htlc_accepted":
+        # Held HTLCs can take minutes; each one gets its own thread.
+        threading.Thread(
+            target=handle_htlc, args=(request_id, request.get("params") or {}), daemon=True
+        ).start()
+    elif request_id is not None:
+        send(
+            {
+                "jsonrpc": "2.0",
+                "id": request_id,
+                "error": {"code": -32601, "message": f"unknown method {method}"},
+            }
+        )
+
+
+def main():
+    decoder = json.JSONDecoder()
+    buffer = ""
+    while True:
+        chunk = sys.stdin.readline()
+        if not chunk:
+            return
+        buffer += chunk
+        while True:
+            buffer = buffer.lstrip()
+            if not buffer:
+                break
+            try:
+                request, end = decoder.raw_decode(buffer)
+            except ValueError:
+                break
+            buffer = buffer[end:]
+            handle(request)
+
+
+if __name__ == "__main__":
+    main()
@@ -1,9 +1,23 @@
+use crate::core::api_key::ApiKeyStore;
+use crate::core::auth_session::{AuthSessionStore, PendingChallenge};
 use crate::core::cli::Args;
-use crate::core::recent_request::entry::RecentRequestEntry;
+use crate::core::config;
+use crate::core::invoice_events::InvoiceSettledEvent;
+use crate::core::lsps::lsps1::Lsps1Service;
+use crate::core::lsps::lsps2::Lsps2Service;
+use crate::core::recent_request::{self, entry::RecentRequestEntry};
+use crate::core::runtime_config::{ConfigChange, RuntimeConfig};
+use crate::core::shutdown::Shutdown;
+use crate::core::spending_limits::SpendingLedger;
+use crate::core::trusted_proxies::TrustedProxies;
+use crate::core::webhook::dispatcher::WebhookDispatcher;
+use crate::openapi::ApiDoc;
 
+use arc_swap::ArcSwap;
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, broadcast};
+use utoipa::OpenApi;
 
 use crate::core::bitcoin_rpc_connector::BitcoinRPCConnector;
 use crate::core::lightning_rpc_connector::LightningRPCConnector;
@@ -22,9 +36,41 @@ pub struct Context {
     // Set of active channel request keys (k1) for LNURL-channel callbacks
     pub channel_keys_set: Mutex<HashSet<String>>,
 
-    // LNURL-auth: pending k1 challenges and completed auth (k1 -> pubkey hex)
-    pub auth_pending_keys_set: Mutex<HashSet<String>>,
-    pub auth_completed: Mutex<HashMap<String, String>>,
+    // LNURL-auth: pending k1 challenges and completed auth (k1 -> completion unix time); both
+    // are pruned once expired (CHALLENGE_TTL_SECS and the session TTL) when new entries arrive
+    pub auth_pending_keys: Mutex<HashMap<String, PendingChallenge>>,
+    pub auth_completed: Mutex<HashMap<String, u64>>,
+
+    // Sessions issued after a successful LNURL-auth login
+    pub auth_sessions: AuthSessionStore,
+
+    // Hashed API keys and the scope each route requires
+    pub api_keys: ApiKeyStore,
+
+    // Proxies allowed to report the client address in forwarding headers
+    pub trusted_proxies: TrustedProxies,
+
+    // Fan-out of settled invoices for the /events and /ws subscribers
+    pub invoice_events: broadcast::Sender<InvoiceSettledEvent>,
+
+    // Outgoing webhook queue (no-op when no endpoints are configured)
+    pub webhooks: WebhookDispatcher,
+
+    // Withdraw limits, spending policy and rate limits; swapped by reload_runtime_config
+    pub runtime: ArcSwap<RuntimeConfig>,
+    reload_lock: std::sync::Mutex<()>,
+
+    // Recent withdrawals, channel fundings and payments checked against the spending limits
+    pub spending: SpendingLedger,
+
+    // LSPS1 channel orders (idle unless SERVER_LSPS1_ENABLED)
+    pub lsps1: Lsps1Service,
+
+    // LSPS2 buys and the HTLCs held for them (idle unless SERVER_LSPS2_ENABLED)
+    pub lsps2: Lsps2Service,
+
+    // Draining state after SIGTERM/SIGINT
+    pub shutdown: Shutdown,
 }
 
 impl Context {
@@ -45,6 +91,75 @@ impl Context {
             );
         }
 
+        let webhooks = WebhookDispatcher::new(&args);
+
+        let auth_sessions = match AuthSessionStore::new(&args) {
+            Ok(store) => store,
+            Err(e) => {
+                tracing::error!("Invalid LNURL-auth configuration: {:#}", e);
+                std::process::exit(2);
+            }
+        };
+
+        let api_keys = match ApiKeyStore::from_args(&args, &ApiDoc::openapi()) {
+            Ok(store) => store,
+            Err(e) => {
+                tracing::error!("Invalid API key configuration: {:#}", e);
+                std::process::exit(2);
+            }
+        };
+
+        if !auth_sessions.admin_required() && !api_keys.is_enabled() {
+            tracing::warn!(
+                "No admin linking keys or API keys configured; administrative routes will reject every request with 401"
+            );
+        }
+
+        let trusted_proxies = match TrustedProxies::from_args(&args) {
+            Ok(proxies) => proxies,
+            Err(e) => {
+                tracing::error!("Invalid trusted proxies: {:#}", e);
+                std::process::exit(2);
+            }
+        };
+
+        if let Some(url) = &args.public_base_url {
+            if let Err(e) = crate::core::utils::parse_public_base_url(url) {
+                tracing::error!("Invalid public base URL: {:#}", e);
+                std::process::exit(2);
+            }
+        } else {
+            tracing::info!(
+                "SERVER_PUBLIC_BASE_URL not set; callback URLs are derived from request headers"
+            );
+        }
+
+        let runtime = match RuntimeConfig::from_args(&args) {
+            Ok(runtime) => runtime,
+            Err(e) => {
+                tracing::error!("Invalid policy configuration: {:#}", e);
+                std::process::exit(2);
+            }
+        };
+
+        if runtime.spending_policy.limits.is_empty() {
+            tracing::warn!(
+                "No spending limits configured; withdraw and channel callbacks are not capped"
+            );
+        }
+        let spending = SpendingLedger::new(&args);
+        let lsps1 = Lsps1Service::new(&args);
+        let lsps2 = match Lsps2Service::new(&args) {
+            Ok(lsps2) => lsps2,
+            Err(e) => {
+                tracing::error!("Invalid LSPS2 configuration: {:#}", e);
+                std::process::exit(2);
+            }
+        };
+
+        let recent_requests =
+            recent_request::store::load(&args.data_dir.join(recent_request::store::STORE_FILE));
+
         let sock = args.rpc_sockpath.as_ref().expect("rpc_sockpath required");
         let cln_client = LightningRPCConnector::connect_unix(sock).await;
 
@@ -54,11 +169,22 @@ impl Context {
                     args,
                     btc_client: bitcoin,
                     cln_client: Mutex::new(cln_client),
-                    recent_requests: Mutex::new(VecDeque::new()),
+                    recent_requests: Mutex::new(recent_requests),
                     withdrawal_keys_set: Mutex::new(HashSet::new()),
                     channel_keys_set: Mutex::new(HashSet::new()),
-                    auth_pending_keys_set: Mutex::new(HashSet::new()),
+                    auth_pending_keys: Mutex::new(HashMap::new()),
                     auth_completed: Mutex::new(HashMap::new()),
+                    auth_sessions,
+                    api_keys,
+                    trusted_proxies,
+                    invoice_events: broadcast::channel(256).0,
+                    webhooks,
+                    runtime: ArcSwap::from_pointee(runtime),
+                    reload_lock: std::sync::Mutex::new(()),
+                    spending,
+                    lsps1,
+                    lsps2,
+                    shutdown: Shutdown::default(),
                 });
 
                 tracing::info!(
@@ -74,4 +200,58 @@ impl Context {
             }
         }
     }
+
+    /// Opens a connection of its own for CLN calls that can block for a long time (`pay`,
+    /// `waitanyinvoice`, ...), so they don't hold `cln_client` and stall every other request.
+    pub async fn dedicated_cln_client(&self) -> anyhow::Result<LightningRPCConnector> {
+        let sock = self
+            .args
+            .rpc_sockpath
+            .as_ref()
+            .expect("rpc_sockpath required");
+        LightningRPCConnector::connect_unix(sock).await
+    }
+
+    /// Re-reads the reloadable settings (see `core::runtime_config`) and swaps in the new
+    /// runtime config. Invalid settings leave the current one in place.
+    pub fn reload_runtime_config(&self) -> anyhow::Result<Vec<ConfigChange>> {
+        let _guard = self.reload_lock.lock().unwrap_or_else(|e| e.into_inner());
+
+        let args = config::reload_args(&self.args)?;
+        let (next, changes) = self.runtime.load().reload(&args)?;
+
+        for change in &changes {
+            tracing::info!(
+                "Config reload: {} changed from {} to {}",
+                change.setting,
+                change.old,
+                change.new
+            );
+        }
+        if changes.is_empty() {
+            tracing::info!("Config reload: no changes");
+        } else {
+            tracing::info!("Config version {} ({})", next.version, next.hash);
+            self.runtime.store(Arc::new(next));
+        }
+
+        Ok(changes)
+    }
+
+    /// Persists in-memory state on shutdown. The invoice pay index is already written after
+    /// every settlement.
+    pub async fn flush(&self) {
+        self.webhooks.flush().await;
+
+        let path = self.args.data_dir.join(recent_request::store::STORE_FILE);
+        let recent_requests = self.recent_requests.lock().await;
+        match recent_request::store::save(&path, &recent_requests).await {
+            Ok(()) => tracing::info!(
+                "Shutdown: saved {} recent requests to {:?}",
+                recent_requests.len(),
+                path
+            ),
+            Err(e) => tracing::error!("Could not save recent requests to {:?}: {:#}", path, e),
+        }
+    }
 }
@@ -0,0 +1,63 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::{Request, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::{
+    context::Context,
+    core::{api_key::API_KEY_HEADER, utils::json_error as reject},
+};
+
+// Middleware enforcing the API key scopes declared in each route's `#[utoipa::path]`.
+// A verified key is stored in the request extensions for the handlers.
+
+pub async fn middleware(
+    State(state): State<Arc<Context>>,
+    mut req: Request<axum::body::Body>,
+    next: Next,
+) -> Response {
+    let Some(requirement) = state
+        .api_keys
+        .routes()
+        .lookup(req.method(), req.uri().path())
+    else {
+        return next.run(req).await;
+    };
+
+    let raw = req
+        .headers()
+        .get(API_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim);
+
+    match raw {
+        Some(raw) => {
+            let Some(key) = state.api_keys.verify(raw) else {
+                return reject(StatusCode::UNAUTHORIZED, "invalid API key");
+            };
+
+            if !key.allows(requirement.scope) {
+                return reject(
+                    StatusCode::FORBIDDEN,
+                    format!("API key lacks the {} scope", requirement.scope),
+                );
+            }
+
+            tracing::debug!("API key {} granted {}", key.name, requirement.scope);
+            let key = key.clone();
+            req.extensions_mut().insert(key);
+            next.run(req).await
+        }
+        // Admin routes also accept an LNURL-auth admin session, checked by the handler.
+        None if requirement.admin_session => next.run(req).await,
+        None if state.api_keys.is_enabled() => reject(
+            StatusCode::UNAUTHORIZED,
+            format!("API key with the {} scope required", requirement.scope),
+        ),
+        None => next.run(req).await,
+    }
+}
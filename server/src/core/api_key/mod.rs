@@ -0,0 +1,109 @@
+pub mod middleware;
+pub mod scope;
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use anyhow::{Context as AnyhowContext, bail};
+use sha2::{Digest, Sha256};
+
+use crate::core::api_key::scope::{RouteScopes, Scope};
+use crate::core::cli::Args;
+
+/// Header carrying the API key.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// A configured API key. Only the SHA256 hash of the key is kept.
+#[derive(Debug, Clone)]
+pub struct ApiKey {
+    pub name: String,
+    pub scopes: Vec<Scope>,
+}
+
+impl ApiKey {
+    pub fn allows(&self, scope: Scope) -> bool {
+        self.scopes
+            .iter()
+            .any(|s| *s == scope || *s == Scope::Admin)
+    }
+
+    /// Parses a `<name>:<sha256-hex>:<scope>+<scope>` entry.
+    fn parse(entry: &str) -> anyhow::Result<([u8; 32], Self)> {
+        let mut parts = entry.splitn(3, ':');
+        let (Some(name), Some(hash), Some(scopes)) = (parts.next(), parts.next(), parts.next())
+        else {
+            bail!("expected <name>:<sha256-hex>:<scopes>");
+        };
+
+        if name.is_empty() {
+            bail!("empty API key name");
+        }
+
+        let hash = <[u8; 32]>::try_from(hex::decode(hash)?.as_slice())
+            .map_err(|_| anyhow::anyhow!("API key hash must be 32 bytes of hex"))?;
+        let scopes = scopes
+            .split('+')
+            .map(Scope::from_str)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok((
+            hash,
+            Self {
+                name: name.to_string(),
+                scopes,
+            },
+        ))
+    }
+}
+
+/// Whether one of the `SERVER_API_KEYS` entries grants `scope`. Unparsable entries are skipped;
+/// `ApiKeyStore::from_args` rejects them.
+pub fn any_key_allows(args: &Args, scope: Scope) -> bool {
+    args.api_keys
+        .iter()
+        .filter_map(|entry| ApiKey::parse(entry).ok())
+        .any(|(_, key)| key.allows(scope))
+}
+
+pub struct ApiKeyStore {
+    keys: HashMap<[u8; 32], ApiKey>,
+    routes: RouteScopes,
+}
+
+impl ApiKeyStore {
+    pub fn from_args(args: &Args, openapi: &utoipa::openapi::OpenApi) -> anyhow::Result<Self> {
+        let mut keys = HashMap::new();
+        for entry in &args.api_keys {
+            let (hash, key) = ApiKey::parse(entry).with_context(|| {
+                format!(
+                    "invalid API key entry: {}",
+                    entry.split(':').next().unwrap_or_default()
+                )
+            })?;
+
+            if keys.insert(hash, key).is_some() {
+                bail!("duplicate API key hash");
+            }
+        }
+
+        Ok(Self {
+            keys,
+            routes: RouteScopes::from_openapi(openapi),
+        })
+    }
+
+    /// Whether any API key is configured. Routes that only accept API keys are open otherwise.
+    pub fn is_enabled(&self) -> bool {
+        !self.keys.is_empty()
+    }
+
+    /// Returns the key whose hash matches `raw`.
+    pub fn verify(&self, raw: &str) -> Option<&ApiKey> {
+        let hash: [u8; 32] = Sha256::digest(raw.as_bytes()).into();
+        self.keys.get(&hash)
+    }
+
+    pub fn routes(&self) -> &RouteScopes {
+        &self.routes
+    }
+}
@@ -0,0 +1,319 @@
+use std::fmt;
+use std::str::FromStr;
+
+use axum::http::Method;
+use serde_json::Value;
+
+/// Permission carried by an API key. `admin` implies every other scope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Scope {
+    ReadHealth,
+    ReadMetrics,
+    ReadPolicy,
+    LnurlWithdrawIssue,
+    LnurlChannelIssue,
+    InvoicesRead,
+    InvoicesWrite,
+    PaymentsSend,
+    PaymentsRead,
+    ChannelsRead,
+    ChannelsManage,
+    PeersRead,
+    PeersManage,
+    Lsps1Refunds,
+    Lsps2Hook,
+    Admin,
+}
+
+impl Scope {
+    const ALL: [Scope; 16] = [
+        Scope::ReadHealth,
+        Scope::ReadMetrics,
+        Scope::ReadPolicy,
+        Scope::LnurlWithdrawIssue,
+        Scope::LnurlChannelIssue,
+        Scope::InvoicesRead,
+        Scope::InvoicesWrite,
+        Scope::PaymentsSend,
+        Scope::PaymentsRead,
+        Scope::ChannelsRead,
+        Scope::ChannelsManage,
+        Scope::PeersRead,
+        Scope::PeersManage,
+        Scope::Lsps1Refunds,
+        Scope::Lsps2Hook,
+        Scope::Admin,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::ReadHealth => "read:health",
+            Scope::ReadMetrics => "read:metrics",
+            Scope::ReadPolicy => "read:policy",
+            Scope::LnurlWithdrawIssue => "lnurl:withdraw:issue",
+            Scope::LnurlChannelIssue => "lnurl:channel:issue",
+            Scope::InvoicesRead => "invoices:read",
+            Scope::InvoicesWrite => "invoices:write",
+            Scope::PaymentsSend => "payments:send",
+            Scope::PaymentsRead => "payments:read",
+            Scope::ChannelsRead => "channels:read",
+            Scope::ChannelsManage => "channels:manage",
+            Scope::PeersRead => "peers:read",
+            Scope::PeersManage => "peers:manage",
+            Scope::Lsps1Refunds => "lsps1:refunds",
+            Scope::Lsps2Hook => "lsps2:hook",
+            Scope::Admin => "admin",
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Scope {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Scope::ALL
+            .into_iter()
+            .find(|scope| scope.as_str() == s)
+            .ok_or_else(|| anyhow::anyhow!("unknown API key scope: {s}"))
+    }
+}
+
+/// Authorization required by a route, taken from the `security(...)` of its `#[utoipa::path]`.
+#[derive(Debug, Clone, Copy)]
+pub struct RouteRequirement {
+    /// Scope an API key needs to call the route.
+    pub scope: Scope,
+    /// Whether an admin LNURL-auth session is accepted instead of an API key.
+    pub admin_session: bool,
+}
+
+struct RouteEntry {
+    method: Method,
+    segments: Vec<String>,
+    requirement: RouteRequirement,
+}
+
+impl RouteEntry {
+    /// Number of literal segments matched, or `None` if the path does not match.
+    fn matches(&self, method: &Method, path: &str) -> Option<usize> {
+        if *method != self.method {
+            return None;
+        }
+
+        let parts: Vec<&str> = path.trim_matches('/').split('/').collect();
+        if parts.len() != self.segments.len() {
+            return None;
+        }
+
+        let mut literals = 0;
+        for (segment, part) in self.segments.iter().zip(parts) {
+            if segment.starts_with('{') {
+                continue;
+            }
+            if segment != part {
+                return None;
+            }
+            literals += 1;
+        }
+
+        Some(literals)
+    }
+}
+
+/// Per-route scope requirements, built once from the OpenAPI document so the routes and the
+/// documentation can't drift apart.
+pub struct RouteScopes {
+    entries: Vec<RouteEntry>,
+}
+
+impl RouteScopes {
+    pub fn from_openapi(openapi: &utoipa::openapi::OpenApi) -> Self {
+        let doc = serde_json::to_value(openapi).expect("serializable OpenAPI document");
+        let mut entries = Vec::new();
+
+        let paths = doc["paths"].as_object().cloned().unwrap_or_default();
+        for (path, item) in paths {
+            let Some(operations) = item.as_object() else {
+                continue;
+            };
+
+            for (method, operation) in operations {
+                let Ok(method) = Method::from_str(&method.to_ascii_uppercase()) else {
+                    continue;
+                };
+
+                if let Some(requirement) = Self::requirement(&operation["security"]) {
+                    entries.push(RouteEntry {
+                        method,
+                        segments: path
+                            .trim_matches('/')
+                            .split('/')
+                            .map(String::from)
+                            .collect(),
+                        requirement,
+                    });
+                }
+            }
+        }
+
+        Self { entries }
+    }
+
+    fn requirement(security: &Value) -> Option<RouteRequirement> {
+        let alternatives = security.as_array()?;
+
+        let scope = alternatives.iter().find_map(|alt| {
+            let scope = alt["api_key"].as_array()?.first()?.as_str()?;
+            Some(Scope::from_str(scope).expect("valid scope in #[utoipa::path] security"))
+        })?;
+        let admin_session = alternatives
+            .iter()
+            .any(|alt| alt.get("admin_session").is_some());
+
+        Some(RouteRequirement {
+            scope,
+            admin_session,
+        })
+    }
+
+    /// Looks up the requirement of a route; the most specific template wins
+    /// (e.g. `/payments/keysend` over `/payments/{payment_hash}`).
+    pub fn lookup(&self, method: &Method, path: &str) -> Option<RouteRequirement> {
+        self.entries
+            .iter()
+            .filter_map(|e| e.matches(method, path).map(|score| (score, e.requirement)))
+            .max_by_key(|(score, _)| *score)
+            .map(|(_, requirement)| requirement)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use utoipa::OpenApi;
+
+    use super::*;
+    use crate::openapi::ApiDoc;
+
+    /// Routes wallets, LSP clients and session holders call without an API key or admin session.
+    const OPEN_ROUTES: [(&str, &str); 9] = [
+        ("GET", "/auth/session"),
+        ("DELETE", "/auth/session"),
+        ("GET", "/callbacks/issue-withdraw"),
+        ("GET", "/callbacks/lnurl-auth"),
+        ("GET", "/callbacks/open-channel"),
+        ("GET", "/lnurl-auth-request"),
+        ("GET", "/lnurl-auth-status/{k1}"),
+        ("GET", "/lnurl-auth-status/{k1}/events"),
+        ("POST", "/lsps"),
+    ];
+
+    fn routes() -> RouteScopes {
+        RouteScopes::from_openapi(&ApiDoc::openapi())
+    }
+
+    #[test]
+    fn scopes_round_trip() {
+        for scope in Scope::ALL {
+            assert_eq!(Scope::from_str(scope.as_str()).unwrap(), scope);
+        }
+        assert!(Scope::from_str("invoices").is_err());
+        assert!(Scope::from_str("Admin").is_err());
+    }
+
+    #[test]
+    fn only_known_routes_are_open() {
+        let doc = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let routes = routes();
+
+        let mut open = Vec::new();
+        for (path, item) in doc["paths"].as_object().unwrap() {
+            for method in item.as_object().unwrap().keys() {
+                let method = Method::from_str(&method.to_ascii_uppercase()).unwrap();
+                // Templates match themselves, as `{k1}` is taken for a parameter.
+                if routes.lookup(&method, path).is_none() {
+                    open.push((method.to_string(), path.clone()));
+                }
+            }
+        }
+        open.sort();
+
+        let mut expected: Vec<(String, String)> = OPEN_ROUTES
+            .iter()
+            .map(|(method, path)| (method.to_string(), path.to_string()))
+            .collect();
+        expected.sort();
+
+        assert_eq!(
+            open, expected,
+            "a route without `security(...)` is reachable without credentials"
+        );
+    }
+
+    #[test]
+    fn looks_up_the_scope_of_a_route() {
+        let routes = routes();
+        let cases = [
+            (Method::GET, "/invoices", Some((Scope::InvoicesRead, true))),
+            (
+                Method::POST,
+                "/invoices",
+                Some((Scope::InvoicesWrite, true)),
+            ),
+            (Method::GET, "/invoices/", Some((Scope::InvoicesRead, true))),
+            (
+                Method::GET,
+                "/invoices/some-label",
+                Some((Scope::InvoicesRead, true)),
+            ),
+            (
+                Method::DELETE,
+                "/invoices/some-label",
+                Some((Scope::Admin, true)),
+            ),
+            (Method::GET, "/offers", Some((Scope::InvoicesRead, true))),
+            (Method::POST, "/offers", Some((Scope::InvoicesWrite, true))),
+            // Literal segments win over parameters.
+            (
+                Method::POST,
+                "/offers/pay",
+                Some((Scope::PaymentsSend, true)),
+            ),
+            (
+                Method::POST,
+                "/offers/abc/disable",
+                Some((Scope::Admin, true)),
+            ),
+            (
+                Method::POST,
+                "/payments/keysend",
+                Some((Scope::PaymentsSend, true)),
+            ),
+            (
+                Method::GET,
+                "/payments/keysend",
+                Some((Scope::PaymentsRead, true)),
+            ),
+            // Service routes don't take admin sessions.
+            (Method::GET, "/metrics", Some((Scope::ReadMetrics, false))),
+            (Method::POST, "/lsps2/htlc", Some((Scope::Lsps2Hook, false))),
+            (Method::PUT, "/invoices", None),
+            (Method::GET, "/invoices/a/b", None),
+            (Method::POST, "/lsps", None),
+            (Method::GET, "/callbacks/open-channel", None),
+            (Method::GET, "/unknown", None),
+        ];
+
+        for (method, path, expected) in cases {
+            let requirement = routes
+                .lookup(&method, path)
+                .map(|r| (r.scope, r.admin_session));
+            assert_eq!(requirement, expected, "{method} {path}");
+        }
+    }
+}
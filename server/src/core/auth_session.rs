@@ -0,0 +1,238 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use anyhow::Context as AnyhowContext;
+use axum::http::{HeaderMap, header};
+use secp256k1::PublicKey;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Mutex, broadcast};
+
+use crate::core::cli::Args;
+use crate::core::utils;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LnUrlAuthRequestAction {
+    Register,
+    Login,
+    Link,
+    Auth,
+}
+
+/// How long a `/lnurl-auth-request` challenge can be signed, in seconds.
+pub const CHALLENGE_TTL_SECS: u64 = 600;
+
+/// How long a ticket for `/events` or `/ws` can be redeemed, in seconds.
+pub const STREAM_TICKET_TTL_SECS: u64 = 60;
+
+/// An LNURL-auth challenge waiting for the wallet's signature.
+#[derive(Debug, Clone, Copy)]
+pub struct PendingChallenge {
+    /// Action requested when the challenge was issued.
+    pub action: Option<LnUrlAuthRequestAction>,
+    /// Unix timestamp (seconds) when the challenge was issued.
+    pub created_at: u64,
+}
+
+impl PendingChallenge {
+    pub fn new(action: Option<LnUrlAuthRequestAction>) -> Self {
+        Self {
+            action,
+            created_at: utils::unix_time_ms() / 1000,
+        }
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        now >= self.created_at + CHALLENGE_TTL_SECS
+    }
+}
+
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct AuthSession {
+    /// Opaque bearer token, sent as `Authorization: Bearer <token>`.
+    pub token: String,
+    /// LNURL-auth linking key (compressed secp256k1 public key hex).
+    pub linking_key: String,
+    /// Action requested when the challenge was issued.
+    pub action: Option<LnUrlAuthRequestAction>,
+    /// Whether the linking key is allowed to use administrative routes.
+    pub admin: bool,
+    /// Unix timestamp (seconds) when the session was issued.
+    pub created_at: u64,
+    /// Unix timestamp (seconds) after which the session is no longer valid.
+    pub expires_at: u64,
+}
+
+impl AuthSession {
+    fn is_expired(&self, now: u64) -> bool {
+        now >= self.expires_at
+    }
+}
+
+/// One-time ticket opening an `/events` or `/ws` stream, for browsers that can't set headers on
+/// `EventSource` or `WebSocket`.
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct StreamTicket {
+    /// Opaque ticket, sent as `?ticket=<ticket>`.
+    pub ticket: String,
+    /// Unix timestamp (seconds) after which the ticket is no longer accepted.
+    pub expires_at: u64,
+}
+
+/// Server-side store of sessions issued after a successful LNURL-auth login.
+///
+/// A session is bound to the `k1` it was issued for until a status endpoint hands it out;
+/// each session is handed out only once, so a leaked `k1` cannot be replayed for a token.
+pub struct AuthSessionStore {
+    ttl_secs: u64,
+    admin_keys: HashSet<String>,
+    sessions: Mutex<HashMap<String, AuthSession>>,
+    unclaimed: Mutex<HashMap<String, String>>,
+    issued: broadcast::Sender<String>,
+    // Stream ticket -> expiry (unix seconds)
+    tickets: Mutex<HashMap<String, u64>>,
+}
+
+impl AuthSessionStore {
+    pub fn new(args: &Args) -> anyhow::Result<Self> {
+        let admin_keys = args
+            .admin_linking_keys
+            .iter()
+            .map(|k| {
+                PublicKey::from_str(k)
+                    .map(|pk| pk.to_string())
+                    .with_context(|| format!("invalid admin linking key: {k}"))
+            })
+            .collect::<anyhow::Result<HashSet<_>>>()?;
+
+        Ok(Self {
+            ttl_secs: args.auth_session_ttl_secs,
+            admin_keys,
+            sessions: Mutex::new(HashMap::new()),
+            unclaimed: Mutex::new(HashMap::new()),
+            issued: broadcast::channel(64).0,
+            tickets: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Whether admin sessions are configured. Without them (and without API keys)
+    /// administrative routes reject every request.
+    pub fn admin_required(&self) -> bool {
+        !self.admin_keys.is_empty()
+    }
+
+    /// Lifetime of issued sessions, in seconds.
+    pub fn ttl_secs(&self) -> u64 {
+        self.ttl_secs
+    }
+
+    pub fn is_admin(&self, linking_key: &str) -> bool {
+        self.admin_keys.contains(&linking_key.to_ascii_lowercase())
+    }
+
+    /// Issues a session for the linking key that signed `k1`.
+    pub async fn issue(
+        &self,
+        k1: String,
+        linking_key: String,
+        action: Option<LnUrlAuthRequestAction>,
+    ) -> AuthSession {
+        let now = utils::unix_time_ms() / 1000;
+        let session = AuthSession {
+            token: utils::gen_k1_as_string(),
+            admin: self.is_admin(&linking_key),
+            linking_key,
+            action,
+            created_at: now,
+            expires_at: now + self.ttl_secs,
+        };
+
+        let mut sessions = self.sessions.lock().await;
+        sessions.retain(|_, s| !s.is_expired(now));
+        sessions.insert(session.token.clone(), session.clone());
+
+        // Sessions nobody claimed go along with the expired ones.
+        let mut unclaimed = self.unclaimed.lock().await;
+        unclaimed.retain(|_, token| sessions.contains_key(token));
+        unclaimed.insert(k1.clone(), session.token.clone());
+        drop(unclaimed);
+        drop(sessions);
+
+        // Nobody may be waiting on the SSE status endpoint; that's fine.
+        let _ = self.issued.send(k1);
+
+        session
+    }
+
+    /// Hands out the session issued for `k1`. Returns `None` if it was already claimed,
+    /// has expired, or was never issued.
+    pub async fn claim(&self, k1: &str) -> Option<AuthSession> {
+        let token = self.unclaimed.lock().await.remove(k1)?;
+        self.get(&token).await
+    }
+
+    /// Subscribes to the `k1`s of newly issued sessions.
+    pub fn subscribe(&self) -> broadcast::Receiver<String> {
+        self.issued.subscribe()
+    }
+
+    /// Looks up a valid session by token, dropping it if it has expired.
+    pub async fn get(&self, token: &str) -> Option<AuthSession> {
+        let now = utils::unix_time_ms() / 1000;
+        let mut sessions = self.sessions.lock().await;
+
+        match sessions.get(token) {
+            Some(s) if s.is_expired(now) => {
+                sessions.remove(token);
+                None
+            }
+            Some(s) => Some(s.clone()),
+            None => None,
+        }
+    }
+
+    /// Issues a stream ticket valid for `STREAM_TICKET_TTL_SECS`.
+    pub async fn issue_ticket(&self) -> StreamTicket {
+        let now = utils::unix_time_ms() / 1000;
+        let ticket = StreamTicket {
+            ticket: utils::gen_k1_as_string(),
+            expires_at: now + STREAM_TICKET_TTL_SECS,
+        };
+
+        let mut tickets = self.tickets.lock().await;
+        tickets.retain(|_, expires_at| now < *expires_at);
+        tickets.insert(ticket.ticket.clone(), ticket.expires_at);
+
+        ticket
+    }
+
+    /// Consumes a stream ticket. Returns whether it was valid.
+    pub async fn redeem_ticket(&self, ticket: &str) -> bool {
+        let now = utils::unix_time_ms() / 1000;
+        self.tickets
+            .lock()
+            .await
+            .remove(ticket)
+            .is_some_and(|expires_at| now < expires_at)
+    }
+
+    /// Revokes a session. Returns the session if it was still valid.
+    pub async fn revoke(&self, token: &str) -> Option<AuthSession> {
+        let now = utils::unix_time_ms() / 1000;
+        self.sessions
+            .lock()
+            .await
+            .remove(token)
+            .filter(|s| !s.is_expired(now))
+    }
+}
+
+/// Extracts the token of an `Authorization: Bearer <token>` header.
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+}
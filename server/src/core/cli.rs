@@ -1,18 +1,34 @@
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches, Parser};
+use serde::Serialize;
+use std::collections::HashSet;
 use std::path::PathBuf;
 
-#[derive(Parser, Clone)]
+use crate::core::config::{self, Command};
+
+#[derive(Parser, Clone, Serialize)]
 #[command(
     name = "CoreLightning REST Server",
     version = "0.1.0",
     about = "Expose CoreLightning functionality over REST API"
 )]
 pub struct Args {
+    #[command(subcommand)]
+    #[serde(skip)]
+    pub command: Option<Command>,
+
+    #[arg(
+        long,
+        global = true,
+        env = "SERVER_CONFIG",
+        help = "TOML or YAML configuration file; environment variables and flags override its values"
+    )]
+    #[serde(skip)]
+    pub config: Option<PathBuf>,
+
     #[arg(
         long,
         env = "SERVER_CLN_RPC_PATH",
-        help = "Path to the CoreLightning RPC socket",
-        required = true
+        help = "Path to the CoreLightning RPC socket"
     )]
     pub rpc_sockpath: Option<PathBuf>,
 
@@ -41,6 +57,173 @@ pub struct Args {
     )]
     pub max_withdrawable_msat: u64,
 
+    #[arg(
+        long,
+        env = "SERVER_WITHDRAW_ALLOW_ALL",
+        help = "Accept amount=all in the withdraw callback to sweep the confirmed on-chain balance",
+        default_value = "false"
+    )]
+    pub withdraw_allow_all: bool,
+
+    #[arg(
+        long,
+        env = "SERVER_CHANNEL_MIN_SAT",
+        help = "Smallest channel opened by the channel callback, in satoshis; also the size of LUD-02 channels that don't ask for an amount",
+        default_value = "20000"
+    )]
+    pub channel_min_sat: u64,
+
+    #[arg(
+        long,
+        env = "SERVER_CHANNEL_MAX_SAT",
+        help = "Largest channel opened by the channel callback, in satoshis",
+        default_value = "1000000"
+    )]
+    pub channel_max_sat: u64,
+
+    #[arg(
+        long,
+        env = "SERVER_CHANNEL_PUSH_MSAT",
+        help = "Amount pushed to the wallet's side of every channel opened by the channel callback, in millisatoshis",
+        default_value = "0"
+    )]
+    pub channel_push_msat: u64,
+
+    #[arg(
+        long,
+        env = "SERVER_LSPS1_ENABLED",
+        help = "Sell inbound channels through LSPS1 on POST /lsps",
+        default_value = "false"
+    )]
+    pub lsps1_enabled: bool,
+
+    #[arg(
+        long,
+        env = "SERVER_LSPS1_FEE_BASE_SAT",
+        help = "Fixed part of the LSPS1 channel fee, in satoshis",
+        default_value = "1000"
+    )]
+    pub lsps1_fee_base_sat: u64,
+
+    #[arg(
+        long,
+        env = "SERVER_LSPS1_FEE_PPM",
+        help = "Proportional part of the LSPS1 channel fee, in parts per million of the LSP balance",
+        default_value = "10000"
+    )]
+    pub lsps1_fee_ppm: u64,
+
+    #[arg(
+        long,
+        env = "SERVER_LSPS1_MAX_CLIENT_BALANCE_SAT",
+        help = "Largest client balance an LSPS1 order can buy, in satoshis (0 disables it)",
+        default_value = "0"
+    )]
+    pub lsps1_max_client_balance_sat: u64,
+
+    #[arg(
+        long,
+        env = "SERVER_LSPS1_MAX_CHANNEL_EXPIRY_BLOCKS",
+        help = "Longest time, in blocks, LSPS1 channels are guaranteed to stay open",
+        default_value = "13140"
+    )]
+    pub lsps1_max_channel_expiry_blocks: u32,
+
+    #[arg(
+        long,
+        env = "SERVER_LSPS1_ORDER_EXPIRY_SECS",
+        help = "Seconds an LSPS1 order can be paid for",
+        default_value = "3600"
+    )]
+    pub lsps1_order_expiry_secs: u64,
+
+    #[arg(
+        long,
+        env = "SERVER_LSPS1_MAX_OPEN_ORDERS",
+        help = "Most unpaid LSPS1 orders at a time; lsps1.create_order is refused beyond it",
+        default_value = "100"
+    )]
+    pub lsps1_max_open_orders: usize,
+
+    #[arg(
+        long,
+        env = "SERVER_LSPS2_ENABLED",
+        help = "Sell just-in-time channels through LSPS2 on POST /lsps",
+        default_value = "false"
+    )]
+    pub lsps2_enabled: bool,
+
+    #[arg(
+        long,
+        env = "SERVER_LSPS2_PROMISE_SECRET",
+        help = "HMAC key signing the LSPS2 opening fee parameters (required with LSPS2)"
+    )]
+    pub lsps2_promise_secret: Option<String>,
+
+    #[arg(
+        long,
+        env = "SERVER_LSPS2_MIN_FEE_MSAT",
+        help = "Minimum LSPS2 opening fee, in millisatoshis",
+        default_value = "2000000"
+    )]
+    pub lsps2_min_fee_msat: u64,
+
+    #[arg(
+        long,
+        env = "SERVER_LSPS2_FEE_PPM",
+        help = "LSPS2 opening fee, in parts per million of the payment",
+        default_value = "10000"
+    )]
+    pub lsps2_fee_ppm: u32,
+
+    #[arg(
+        long,
+        env = "SERVER_LSPS2_MIN_PAYMENT_MSAT",
+        help = "Smallest payment that can open an LSPS2 channel, in millisatoshis",
+        default_value = "10000000"
+    )]
+    pub lsps2_min_payment_msat: u64,
+
+    #[arg(
+        long,
+        env = "SERVER_LSPS2_MAX_PAYMENT_MSAT",
+        help = "Largest payment that can open an LSPS2 channel, in millisatoshis",
+        default_value = "400000000"
+    )]
+    pub lsps2_max_payment_msat: u64,
+
+    #[arg(
+        long,
+        env = "SERVER_LSPS2_FEE_VALID_SECS",
+        help = "Seconds LSPS2 opening fee parameters can be used to buy a channel",
+        default_value = "3600"
+    )]
+    pub lsps2_fee_valid_secs: u64,
+
+    #[arg(
+        long,
+        env = "SERVER_LSPS2_MIN_LIFETIME",
+        help = "Blocks an LSPS2 channel is kept open at least",
+        default_value = "4320"
+    )]
+    pub lsps2_min_lifetime: u32,
+
+    #[arg(
+        long,
+        env = "SERVER_LSPS2_MAX_CLIENT_TO_SELF_DELAY",
+        help = "Largest to_self_delay the client may ask for on LSPS2 channels, in blocks",
+        default_value = "2016"
+    )]
+    pub lsps2_max_client_to_self_delay: u32,
+
+    #[arg(
+        long,
+        env = "SERVER_LSPS2_CLTV_EXPIRY_DELTA",
+        help = "cltv_expiry_delta clients put in the route hint of LSPS2 invoices",
+        default_value = "144"
+    )]
+    pub lsps2_cltv_expiry_delta: u32,
+
     #[arg(
         long,
         env = "SERVER_BTC_RPC_URL",
@@ -62,26 +245,282 @@ pub struct Args {
         help = "Bitcoin Core JSON-RPC password"
     )]
     pub btc_rpc_password: Option<String>,
+
+    #[arg(
+        long,
+        env = "SERVER_DATA_DIR",
+        help = "Directory where the server persists its state",
+        default_value = ".data"
+    )]
+    pub data_dir: PathBuf,
+
+    #[arg(
+        long,
+        env = "SERVER_WEBHOOK_URLS",
+        help = "Comma-separated list of webhook endpoints notified of gateway events",
+        value_delimiter = ','
+    )]
+    pub webhook_urls: Vec<String>,
+
+    #[arg(
+        long,
+        env = "SERVER_WEBHOOK_SECRET",
+        help = "Shared secret used to sign webhook payloads (HMAC-SHA256)"
+    )]
+    pub webhook_secret: Option<String>,
+
+    #[arg(
+        long,
+        env = "SERVER_WEBHOOK_MAX_ATTEMPTS",
+        help = "Delivery attempts before a webhook is marked as failed",
+        default_value = "8"
+    )]
+    pub webhook_max_attempts: u32,
+
+    #[arg(
+        long,
+        env = "SERVER_WEBHOOK_MAX_QUEUE",
+        help = "Deliveries kept in the webhook queue; the oldest failed, then pending ones are dropped beyond it",
+        default_value = "10000"
+    )]
+    pub webhook_max_queue: usize,
+
+    #[arg(
+        long,
+        env = "SERVER_PAYMENT_MAX_MSAT",
+        help = "Maximum amount of a single outgoing payment in millisatoshis",
+        default_value = "100000"
+    )]
+    pub payment_max_msat: u64,
+
+    #[arg(
+        long,
+        env = "SERVER_PAYMENT_MAX_FEE_PPM",
+        help = "Maximum routing fee of an outgoing payment, in parts per million of its amount",
+        default_value = "10000"
+    )]
+    pub payment_max_fee_ppm: u64,
+
+    #[arg(
+        long,
+        env = "SERVER_PAYMENT_MAX_FEE_MSAT",
+        help = "Maximum absolute routing fee of an outgoing payment in millisatoshis"
+    )]
+    pub payment_max_fee_msat: Option<u64>,
+
+    #[arg(
+        long,
+        env = "SERVER_PAYMENT_ALLOWED_DESTINATIONS",
+        help = "Comma-separated node pubkeys outgoing payments are restricted to (default: any)",
+        value_delimiter = ','
+    )]
+    pub payment_allowed_destinations: Vec<String>,
+
+    #[arg(
+        long,
+        env = "SERVER_SPENDING_LIMITS",
+        help = "Comma-separated spending ceilings as <flow>:<window>=<msat> (flow: withdraw, channel, lightning or total; window: request, hour or day)",
+        value_delimiter = ','
+    )]
+    pub spending_limits: Vec<String>,
+
+    #[arg(
+        long,
+        env = "SERVER_AUTH_SESSION_TTL_SECS",
+        help = "Lifetime of sessions issued after an LNURL-auth login, in seconds",
+        default_value = "86400"
+    )]
+    pub auth_session_ttl_secs: u64,
+
+    #[arg(
+        long,
+        env = "SERVER_ADMIN_LINKING_KEYS",
+        help = "Comma-separated LNURL-auth linking keys granted admin sessions (without them or API keys, admin routes are disabled)",
+        value_delimiter = ','
+    )]
+    pub admin_linking_keys: Vec<String>,
+
+    #[arg(
+        long,
+        env = "SERVER_API_KEYS",
+        help = "Comma-separated API keys as <name>:<sha256-hex>:<scope>+<scope>",
+        value_delimiter = ','
+    )]
+    pub api_keys: Vec<String>,
+
+    #[arg(
+        long,
+        env = "SERVER_REQUIRE_RUNE",
+        help = "Require a CLN rune (X-CLN-Rune header) for every call that changes node state",
+        default_value = "false"
+    )]
+    pub require_rune: bool,
+
+    #[arg(
+        long,
+        env = "SERVER_TLS_CERT",
+        help = "PEM certificate chain; serve HTTPS directly when set together with --tls-key"
+    )]
+    pub tls_cert: Option<PathBuf>,
+
+    #[arg(
+        long,
+        env = "SERVER_TLS_KEY",
+        help = "PEM private key of the TLS certificate"
+    )]
+    pub tls_key: Option<PathBuf>,
+
+    #[arg(
+        long,
+        env = "SERVER_TLS_CLIENT_CA",
+        help = "PEM CA bundle; admin routes then require a client certificate signed by it (mTLS)"
+    )]
+    pub tls_client_ca: Option<PathBuf>,
+
+    #[arg(
+        long,
+        env = "SERVER_TLS_RELOAD_INTERVAL_SECS",
+        help = "How often to check the TLS files for changes (e.g. certbot renewals)",
+        default_value = "60"
+    )]
+    pub tls_reload_interval_secs: u64,
+
+    #[arg(
+        long,
+        env = "SERVER_HTTP_REDIRECT_PORT",
+        help = "With TLS enabled, also listen for plain HTTP on this port and redirect to HTTPS"
+    )]
+    pub http_redirect_port: Option<u16>,
+
+    #[arg(
+        long,
+        env = "SERVER_ONION_LISTEN",
+        help = "Extra plain HTTP listener for a Tor hidden service: unix:<path>, <ip>:<port> or <port>"
+    )]
+    pub onion_listen: Option<String>,
+
+    #[arg(
+        long,
+        env = "SERVER_ONION_ADDRESS",
+        help = "Onion host name of the hidden service, used in callback URLs for onion requests"
+    )]
+    pub onion_address: Option<String>,
+
+    #[arg(
+        long,
+        env = "SERVER_ONION_VIRTUAL_PORT",
+        help = "Virtual port of the hidden service (HiddenServicePort)",
+        default_value = "80"
+    )]
+    pub onion_virtual_port: u16,
+
+    #[arg(
+        long,
+        env = "SERVER_PUBLIC_BASE_URL",
+        help = "Public URL of the gateway used in callback URLs (e.g. https://ln.example.com); overrides request headers"
+    )]
+    pub public_base_url: Option<String>,
+
+    #[arg(
+        long,
+        env = "SERVER_CORS_ALLOWED_ORIGINS",
+        help = "Comma-separated origins allowed to call the API from a browser (e.g. https://ui.example.com), or \"*\"",
+        value_delimiter = ','
+    )]
+    pub cors_allowed_origins: Vec<String>,
+
+    #[arg(
+        long,
+        env = "SERVER_CORS_ALLOWED_METHODS",
+        help = "Comma-separated HTTP methods allowed for cross-origin requests, or \"*\"",
+        value_delimiter = ',',
+        default_value = "GET,POST,DELETE,OPTIONS"
+    )]
+    pub cors_allowed_methods: Vec<String>,
+
+    #[arg(
+        long,
+        env = "SERVER_CORS_ALLOWED_HEADERS",
+        help = "Comma-separated request headers allowed for cross-origin requests, or \"*\"",
+        value_delimiter = ',',
+        default_value = "content-type,authorization,x-api-key,x-cln-rune"
+    )]
+    pub cors_allowed_headers: Vec<String>,
+
+    #[arg(
+        long,
+        env = "SERVER_CORS_ALLOW_CREDENTIALS",
+        help = "Allow credentialed cross-origin requests (requires explicit origins)",
+        default_value = "false"
+    )]
+    pub cors_allow_credentials: bool,
+
+    #[arg(
+        long,
+        env = "SERVER_TRUSTED_PROXIES",
+        help = "Comma-separated proxy addresses or CIDR networks whose Forwarded / X-Forwarded-* / X-Real-IP headers are trusted",
+        value_delimiter = ',',
+        default_value = "127.0.0.1/32,::1/128"
+    )]
+    pub trusted_proxies: Vec<String>,
+
+    #[arg(
+        long,
+        env = "SERVER_RATE_LIMIT_DEFAULT",
+        help = "Default per-client rate limit as <requests>/<seconds>, or \"off\"",
+        default_value = "300/60"
+    )]
+    pub rate_limit_default: String,
+
+    #[arg(
+        long,
+        env = "SERVER_RATE_LIMITS",
+        help = "Comma-separated per-route rate limits as <path-prefix>=<requests>/<seconds>",
+        value_delimiter = ',',
+        default_value = "/withdraw-request=10/60,/channel-request=10/60,/lnurl-auth-request=30/60,/callbacks/=20/60"
+    )]
+    pub rate_limits: Vec<String>,
+
+    #[arg(
+        long,
+        env = "SERVER_SHUTDOWN_TIMEOUT_SECS",
+        help = "Seconds to wait for in-flight requests after SIGTERM/SIGINT before exiting",
+        default_value = "30"
+    )]
+    pub shutdown_timeout_secs: u64,
+
+    /// Reloadable settings given on the command line or in the process environment; a policy
+    /// reload keeps their values.
+    #[arg(skip)]
+    #[serde(skip)]
+    pub pinned_settings: Vec<String>,
 }
 
+// Backwards-compat: old LNS_* env vars and the SERVER_* names they map to.
+pub(crate) const LEGACY_ENV: [(&str, &str); 7] = [
+    ("LNS_CL_RPC_PATH", "SERVER_CLN_RPC_PATH"),
+    ("LNS_PORT", "SERVER_PORT"),
+    ("LNS_MIN_WITHDRAWABLE_MSAT", "SERVER_MIN_WITHDRAWABLE_MSAT"),
+    ("LNS_MAX_WITHDRAWABLE_MSAT", "SERVER_MAX_WITHDRAWABLE_MSAT"),
+    ("LNS_BTC_RPC_URL", "SERVER_BTC_RPC_URL"),
+    ("LNS_BTC_RPC_USER", "SERVER_BTC_RPC_USER"),
+    ("LNS_BTC_RPC_PASSWORD", "SERVER_BTC_RPC_PASSWORD"),
+];
+
 impl Args {
     pub fn new() -> Self {
+        // Variables set before .env is loaded can't change while we run, unlike .env and the
+        // config file, which are re-read on a policy reload.
+        let process_env: HashSet<String> = std::env::vars_os()
+            .filter_map(|(name, _)| name.into_string().ok())
+            .collect();
+
         // Load variables from a .env file if present before parsing CLI args
         let _ = dotenvy::dotenv();
 
         // Backwards-compat: map old LNS_* env vars to the new SERVER_* names.
         // This keeps existing deployments working while we migrate docs/config.
-        let mappings = [
-            ("LNS_CL_RPC_PATH", "SERVER_CLN_RPC_PATH"),
-            ("LNS_PORT", "SERVER_PORT"),
-            ("LNS_MIN_WITHDRAWABLE_MSAT", "SERVER_MIN_WITHDRAWABLE_MSAT"),
-            ("LNS_MAX_WITHDRAWABLE_MSAT", "SERVER_MAX_WITHDRAWABLE_MSAT"),
-            ("LNS_BTC_RPC_URL", "SERVER_BTC_RPC_URL"),
-            ("LNS_BTC_RPC_USER", "SERVER_BTC_RPC_USER"),
-            ("LNS_BTC_RPC_PASSWORD", "SERVER_BTC_RPC_PASSWORD"),
-        ];
-
-        for (old_name, new_name) in mappings {
+        for (old_name, new_name) in LEGACY_ENV {
             let has_new = std::env::var_os(new_name)
                 .and_then(|v| (!v.to_string_lossy().trim().is_empty()).then_some(v))
                 .is_some();
@@ -98,7 +537,36 @@ impl Args {
             }
         }
 
-        let mut args = Args::parse();
+        // The config file comes below env and CLI, so it only fills variables still unset.
+        if let Some(path) = config::config_path()
+            && let Err(e) = config::apply_file(&path)
+        {
+            eprintln!("Invalid configuration: {:#}", e);
+            std::process::exit(2);
+        }
+
+        let matches = Args::command().get_matches();
+        let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+        args.normalize();
+        args.pinned_settings = config::pinned_settings(&matches, &process_env);
+
+        // `config` subcommands report problems themselves instead of refusing to start.
+        if args.command.is_none() {
+            let errors = config::check_args(&args);
+            for e in &errors {
+                eprintln!("Invalid configuration: {:#}", e);
+            }
+            if !errors.is_empty() {
+                std::process::exit(2);
+            }
+        }
+
+        args
+    }
+
+    /// Cleans up values as parsed by clap.
+    pub(crate) fn normalize(&mut self) {
+        let args = self;
 
         // `VAR=` can produce empty values; treat these as "unset" where that makes sense.
         args.rpc_sockpath = args.rpc_sockpath.take().and_then(|p| {
@@ -106,13 +574,6 @@ impl Args {
             (!s.trim().is_empty()).then_some(p)
         });
 
-        if args.rpc_sockpath.is_none() {
-            eprintln!(
-                "Missing CoreLightning RPC socket path: set --rpc-sockpath or SERVER_CLN_RPC_PATH"
-            );
-            std::process::exit(2);
-        }
-
         // dotenv + clap treat `VAR=` as "present but empty", which becomes `Some("")` for
         // `Option<String>`. For RPC auth we want empty strings to behave like "not set".
         let user = args.btc_rpc_user.take().filter(|v| !v.trim().is_empty());
@@ -127,7 +588,91 @@ impl Args {
             _ => (None, None),
         };
 
-        args
+        args.webhook_urls = args
+            .webhook_urls
+            .drain(..)
+            .map(|u| u.trim().to_string())
+            .filter(|u| !u.is_empty())
+            .collect();
+        args.webhook_secret = args.webhook_secret.take().filter(|v| !v.trim().is_empty());
+
+        args.payment_allowed_destinations = args
+            .payment_allowed_destinations
+            .drain(..)
+            .map(|d| d.trim().to_string())
+            .filter(|d| !d.is_empty())
+            .collect();
+
+        args.spending_limits = args
+            .spending_limits
+            .drain(..)
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty())
+            .collect();
+
+        args.admin_linking_keys = args
+            .admin_linking_keys
+            .drain(..)
+            .map(|k| k.trim().to_ascii_lowercase())
+            .filter(|k| !k.is_empty())
+            .collect();
+
+        args.api_keys = args
+            .api_keys
+            .drain(..)
+            .map(|k| k.trim().to_string())
+            .filter(|k| !k.is_empty())
+            .collect();
+
+        // Empty TLS paths mean "plain HTTP".
+        for path in [
+            &mut args.tls_cert,
+            &mut args.tls_key,
+            &mut args.tls_client_ca,
+        ] {
+            *path = path
+                .take()
+                .filter(|p| !p.to_string_lossy().trim().is_empty());
+        }
+
+        args.onion_listen = args.onion_listen.take().filter(|v| !v.trim().is_empty());
+        args.onion_address = args
+            .onion_address
+            .take()
+            .map(|v| v.trim().to_ascii_lowercase())
+            .filter(|v| !v.is_empty());
+
+        args.public_base_url = args
+            .public_base_url
+            .take()
+            .map(|u| u.trim().trim_end_matches('/').to_string())
+            .filter(|u| !u.is_empty());
+
+        for list in [
+            &mut args.cors_allowed_origins,
+            &mut args.cors_allowed_methods,
+            &mut args.cors_allowed_headers,
+        ] {
+            *list = list
+                .drain(..)
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+                .collect();
+        }
+
+        args.trusted_proxies = args
+            .trusted_proxies
+            .drain(..)
+            .map(|p| p.trim().to_string())
+            .filter(|p| !p.is_empty())
+            .collect();
+
+        args.rate_limits = args
+            .rate_limits
+            .drain(..)
+            .map(|r| r.trim().to_string())
+            .filter(|r| !r.is_empty())
+            .collect();
     }
 }
 
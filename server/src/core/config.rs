@@ -0,0 +1,410 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context as AnyhowContext, bail};
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Subcommand, ValueEnum};
+use serde_json::Value;
+use utoipa::OpenApi;
+
+use crate::core::api_key::ApiKeyStore;
+use crate::core::auth_session::AuthSessionStore;
+use crate::core::cli::{Args, LEGACY_ENV};
+use crate::core::lsps::lsps2::Lsps2Policy;
+use crate::core::onion::OnionService;
+use crate::core::rate_limit::RateLimiter;
+use crate::core::runtime_config::{self, RELOADABLE_SETTINGS};
+use crate::core::spending_policy::SpendingPolicy;
+use crate::core::tls::TlsConfig;
+use crate::core::trusted_proxies::TrustedProxies;
+use crate::core::{cors, utils};
+use crate::openapi::ApiDoc;
+
+// Settings replaced by a placeholder when the configuration is printed.
+const SECRET_SETTINGS: [&str; 3] = ["btc_rpc_password", "webhook_secret", "lsps2_promise_secret"];
+
+/// Settings holding URLs (or lists of them) that may carry `user:password@` credentials.
+const URL_SETTINGS: [&str; 2] = ["btc_rpc_url", "webhook_urls"];
+
+#[derive(Subcommand, Clone, Debug)]
+pub enum Command {
+    /// Inspect the server configuration.
+    #[command(subcommand)]
+    Config(ConfigCommand),
+}
+
+#[derive(Subcommand, Clone, Debug)]
+pub enum ConfigCommand {
+    /// Validate the configuration and exit.
+    Check,
+    /// Print the configuration file, or with --effective the merged configuration.
+    Print {
+        /// Print the configuration merged from file, environment and CLI.
+        #[arg(long)]
+        effective: bool,
+
+        /// Output format.
+        #[arg(long, value_enum, default_value_t = OutputFormat::Toml)]
+        format: OutputFormat,
+    },
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+pub enum OutputFormat {
+    Toml,
+    Yaml,
+}
+
+/// Path passed with `--config` or `SERVER_CONFIG`. Looked up before clap parses the arguments,
+/// since the file provides defaults for them.
+pub fn config_path() -> Option<PathBuf> {
+    let mut argv = std::env::args_os().skip(1);
+
+    while let Some(arg) = argv.next() {
+        if arg == "--config" {
+            return argv.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.to_str().and_then(|a| a.strip_prefix("--config=")) {
+            return Some(PathBuf::from(path));
+        }
+    }
+
+    std::env::var_os("SERVER_CONFIG")
+        .filter(|v| !v.to_string_lossy().trim().is_empty())
+        .map(PathBuf::from)
+}
+
+/// Reads a TOML or YAML configuration file. Keys are the setting names (`min_withdrawable_msat`
+/// or `min-withdrawable-msat`); lists may be written as arrays.
+pub fn read_file(path: &Path) -> anyhow::Result<BTreeMap<String, Value>> {
+    let raw = std::fs::read_to_string(path)
+        .with_context(|| format!("reading config file {}", path.display()))?;
+
+    let table: BTreeMap<String, Value> = match path.extension().and_then(|e| e.to_str()) {
+        Some("toml") => toml::from_str(&raw)
+            .with_context(|| format!("parsing TOML config {}", path.display()))?,
+        Some("yaml" | "yml") => serde_yaml::from_str(&raw)
+            .with_context(|| format!("parsing YAML config {}", path.display()))?,
+        _ => bail!(
+            "unsupported config file {} (expected .toml, .yaml or .yml)",
+            path.display()
+        ),
+    };
+
+    Ok(table
+        .into_iter()
+        .map(|(key, value)| (key.replace('-', "_"), value))
+        .collect())
+}
+
+/// Layers the file under the environment: each setting is exported as its `SERVER_*` variable
+/// unless that variable is already set, so env and CLI values keep precedence.
+pub fn apply_file(path: &Path) -> anyhow::Result<()> {
+    let command = Args::command();
+
+    for (key, value) in read_file(path)? {
+        let Some(arg) = command.get_arguments().find(|a| a.get_id().as_str() == key) else {
+            bail!("unknown setting in {}: {}", path.display(), key);
+        };
+        let Some(env) = arg.get_env() else {
+            bail!("{} can't be set in a config file", key);
+        };
+
+        let value = match value {
+            Value::Null => continue,
+            Value::Array(items) if arg.get_value_delimiter().is_some() => items
+                .iter()
+                .map(|item| scalar(&key, item))
+                .collect::<anyhow::Result<Vec<_>>>()?
+                .join(","),
+            value => scalar(&key, &value)?,
+        };
+
+        let is_set = std::env::var_os(env).is_some_and(|v| !v.to_string_lossy().trim().is_empty());
+        if !is_set {
+            // SAFETY: we set vars only during early startup, before spawning threads.
+            unsafe { std::env::set_var(env, OsString::from(value)) };
+        }
+    }
+
+    Ok(())
+}
+
+fn scalar(key: &str, value: &Value) -> anyhow::Result<String> {
+    match value {
+        Value::String(s) => Ok(s.clone()),
+        Value::Number(n) => Ok(n.to_string()),
+        Value::Bool(b) => Ok(b.to_string()),
+        _ => bail!("{} must be a string, number or boolean", key),
+    }
+}
+
+/// Setting value as a command-line string; lists are joined with commas.
+fn to_arg_value(key: &str, value: &Value) -> anyhow::Result<Option<String>> {
+    match value {
+        Value::Null => Ok(None),
+        Value::Array(items) => Ok(Some(
+            items
+                .iter()
+                .map(|item| scalar(key, item))
+                .collect::<anyhow::Result<Vec<_>>>()?
+                .join(","),
+        )),
+        value => scalar(key, value).map(Some),
+    }
+}
+
+/// Reloadable settings set on the command line or in the process environment. Those sources
+/// can't change at runtime, so reloads keep their values.
+pub fn pinned_settings(matches: &ArgMatches, process_env: &HashSet<String>) -> Vec<String> {
+    let command = Args::command();
+
+    RELOADABLE_SETTINGS
+        .iter()
+        .filter(|key| {
+            let Some(arg) = command
+                .get_arguments()
+                .find(|a| a.get_id().as_str() == **key)
+            else {
+                return false;
+            };
+
+            let from_env = arg.get_env().and_then(|e| e.to_str()).is_some_and(|env| {
+                process_env.contains(env)
+                    || LEGACY_ENV
+                        .iter()
+                        .any(|(old, new)| *new == env && process_env.contains(*old))
+            });
+
+            matches!(matches.value_source(key), Some(ValueSource::CommandLine))
+                || (matches!(matches.value_source(key), Some(ValueSource::EnvVariable)) && from_env)
+        })
+        .map(|key| key.to_string())
+        .collect()
+}
+
+/// Re-reads the reloadable settings from `.env` and the config file (in that order of
+/// precedence, falling back to the defaults). Pinned settings keep their current values.
+pub fn reload_args(current: &Args) -> anyhow::Result<Args> {
+    let file = match config_path() {
+        Some(path) => read_file(&path)?,
+        None => BTreeMap::new(),
+    };
+    let dotenv: HashMap<String, String> = dotenvy::dotenv_iter()
+        .map(|vars| vars.filter_map(Result::ok).collect())
+        .unwrap_or_default();
+    let Value::Object(current_values) = serde_json::to_value(current)? else {
+        bail!("arguments don't serialize to an object");
+    };
+
+    let command = Args::command();
+    let mut argv = vec![OsString::from(command.get_name())];
+
+    for key in RELOADABLE_SETTINGS {
+        let arg = command
+            .get_arguments()
+            .find(|a| a.get_id().as_str() == key)
+            .expect("reloadable setting is an argument");
+        let env = arg.get_env().and_then(|e| e.to_str()).unwrap_or_default();
+        let legacy = LEGACY_ENV
+            .iter()
+            .find(|(_, new)| *new == env)
+            .map(|(old, _)| *old);
+
+        let value = if current.pinned_settings.iter().any(|p| p == key) {
+            to_arg_value(key, current_values.get(key).unwrap_or(&Value::Null))?
+        } else if let Some(value) = dotenv
+            .get(env)
+            .or_else(|| legacy.and_then(|old| dotenv.get(old)))
+            .filter(|v| !v.trim().is_empty())
+        {
+            Some(value.clone())
+        } else {
+            to_arg_value(key, file.get(key).unwrap_or(&Value::Null))?
+        };
+
+        if let (Some(value), Some(long)) = (value, arg.get_long()) {
+            argv.push(OsString::from(format!("--{long}={value}")));
+        }
+    }
+
+    // The exported variables are stale now, so only the arguments built above count.
+    let matches = command
+        .mut_args(|a| a.env(None::<&'static str>))
+        .try_get_matches_from(argv)?;
+    let mut fresh = Args::from_arg_matches(&matches)?;
+    fresh.normalize();
+
+    Ok(fresh)
+}
+
+/// Checks that only need the parsed arguments; run on every startup.
+pub fn check_args(args: &Args) -> Vec<anyhow::Error> {
+    let mut errors = Vec::new();
+
+    match &args.rpc_sockpath {
+        None => errors.push(anyhow::anyhow!(
+            "Missing CoreLightning RPC socket path: set --rpc-sockpath or SERVER_CLN_RPC_PATH"
+        )),
+        Some(path) if !path.exists() => errors.push(anyhow::anyhow!(
+            "CoreLightning RPC socket {} does not exist",
+            path.display()
+        )),
+        Some(_) => {}
+    }
+
+    if args.min_withdrawable_msat > args.max_withdrawable_msat {
+        errors.push(anyhow::anyhow!(
+            "min_withdrawable_msat ({}) exceeds max_withdrawable_msat ({})",
+            args.min_withdrawable_msat,
+            args.max_withdrawable_msat
+        ));
+    }
+
+    if let Err(e) = runtime_config::check_channel_bounds(args) {
+        errors.push(e);
+    }
+
+    for (name, url) in std::iter::once(("btc_rpc_url", &args.btc_rpc_url))
+        .chain(args.webhook_urls.iter().map(|u| ("webhook_urls", u)))
+    {
+        match reqwest::Url::parse(url) {
+            Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => {}
+            Ok(_) => errors.push(anyhow::anyhow!("{name}: {url} is not an http(s) URL")),
+            Err(e) => errors.push(anyhow::anyhow!("{name}: invalid URL {url}: {e}")),
+        }
+    }
+
+    if let Some(url) = &args.public_base_url
+        && let Err(e) = utils::parse_public_base_url(url)
+    {
+        errors.push(e);
+    }
+
+    errors
+}
+
+/// Every startup check, including the ones normally done when the components are built.
+fn check_all(args: &Args) -> Vec<anyhow::Error> {
+    let mut errors = check_args(args);
+
+    let components = [
+        TrustedProxies::from_args(args).map(drop),
+        RateLimiter::from_args(args).map(drop),
+        SpendingPolicy::from_args(args).map(drop),
+        AuthSessionStore::new(args).map(drop),
+        ApiKeyStore::from_args(args, &ApiDoc::openapi()).map(drop),
+        cors::layer(args).map(drop),
+        TlsConfig::from_args(args).map(drop),
+        OnionService::from_args(args).map(drop),
+        Lsps2Policy::from_args(args).map(drop),
+    ];
+    errors.extend(components.into_iter().filter_map(Result::err));
+
+    errors
+}
+
+/// Runs a `config` subcommand and returns the process exit code.
+pub fn run(command: &ConfigCommand, args: &Args) -> i32 {
+    match command {
+        ConfigCommand::Check => {
+            let errors = check_all(args);
+            if errors.is_empty() {
+                println!("Configuration OK");
+                return 0;
+            }
+
+            for e in &errors {
+                eprintln!("error: {:#}", e);
+            }
+            2
+        }
+        ConfigCommand::Print { effective, format } => {
+            let settings = if *effective {
+                effective_settings(args)
+            } else {
+                match config_path().map(|p| read_file(&p)) {
+                    Some(Ok(settings)) => settings,
+                    Some(Err(e)) => {
+                        eprintln!("error: {:#}", e);
+                        return 2;
+                    }
+                    None => {
+                        eprintln!("error: no config file (set --config or SERVER_CONFIG)");
+                        return 2;
+                    }
+                }
+            };
+
+            match render(redact(settings), *format) {
+                Ok(out) => {
+                    print!("{out}");
+                    0
+                }
+                Err(e) => {
+                    eprintln!("error: {:#}", e);
+                    1
+                }
+            }
+        }
+    }
+}
+
+fn effective_settings(args: &Args) -> BTreeMap<String, Value> {
+    let Ok(Value::Object(map)) = serde_json::to_value(args) else {
+        return BTreeMap::new();
+    };
+
+    // TOML has no null; unset options are left out.
+    map.into_iter().filter(|(_, v)| !v.is_null()).collect()
+}
+
+fn redact(mut settings: BTreeMap<String, Value>) -> BTreeMap<String, Value> {
+    for key in SECRET_SETTINGS {
+        if let Some(value) = settings.get_mut(key) {
+            *value = Value::String("<redacted>".to_string());
+        }
+    }
+
+    for key in URL_SETTINGS {
+        match settings.get_mut(key) {
+            // A file may list URLs as one comma-separated string.
+            Some(Value::String(urls)) => {
+                *urls = urls
+                    .split(',')
+                    .map(redact_userinfo)
+                    .collect::<Vec<_>>()
+                    .join(",");
+            }
+            Some(Value::Array(urls)) => {
+                for url in urls {
+                    if let Value::String(s) = url {
+                        *s = redact_userinfo(s);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    settings
+}
+
+/// Replaces the `user:password` part of a URL's authority.
+fn redact_userinfo(url: &str) -> String {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return url.to_string();
+    };
+    let authority_end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    match rest[..authority_end].rfind('@') {
+        Some(at) => format!("{scheme}://<redacted>@{}", &rest[at + 1..]),
+        None => url.to_string(),
+    }
+}
+
+fn render(settings: BTreeMap<String, Value>, format: OutputFormat) -> anyhow::Result<String> {
+    Ok(match format {
+        OutputFormat::Toml => toml::to_string(&settings)?,
+        OutputFormat::Yaml => serde_yaml::to_string(&settings)?,
+    })
+}
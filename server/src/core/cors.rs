@@ -0,0 +1,87 @@
+use std::str::FromStr;
+
+use anyhow::{Context as AnyhowContext, bail};
+use axum::http::{HeaderName, HeaderValue, Method, header};
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, Any, CorsLayer};
+
+use crate::core::cli::Args;
+
+/// Builds the CORS layer from `SERVER_CORS_*`.
+///
+/// Without configured origins, debug builds allow any origin for local frontends and release
+/// builds send no CORS headers (same-origin deployments behind nginx don't need them).
+pub fn layer(args: &Args) -> anyhow::Result<CorsLayer> {
+    if args.cors_allowed_origins.is_empty() {
+        if args.cors_allow_credentials {
+            bail!("--cors-allow-credentials requires --cors-allowed-origins");
+        }
+
+        return Ok(if cfg!(debug_assertions) {
+            CorsLayer::new()
+                .allow_origin(Any)
+                .allow_methods([Method::GET, Method::POST, Method::DELETE, Method::OPTIONS])
+                .allow_headers(Any)
+                .expose_headers([header::RETRY_AFTER])
+        } else {
+            CorsLayer::new()
+        });
+    }
+
+    let any_origin = args.cors_allowed_origins.iter().any(|o| o == "*");
+    let any_method = args.cors_allowed_methods.iter().any(|m| m == "*");
+    let any_header = args.cors_allowed_headers.iter().any(|h| h == "*");
+
+    // Browsers reject wildcards on credentialed requests, and tower-http refuses to build them.
+    if args.cors_allow_credentials && (any_origin || any_method || any_header) {
+        bail!("--cors-allow-credentials can't be combined with \"*\" origins, methods or headers");
+    }
+
+    let origins = if any_origin {
+        AllowOrigin::any()
+    } else {
+        let origins = args
+            .cors_allowed_origins
+            .iter()
+            .map(|o| {
+                let origin = o.trim_end_matches('/');
+                if !(origin.starts_with("http://") || origin.starts_with("https://")) {
+                    bail!("CORS origin must start with http:// or https://: {o}");
+                }
+                HeaderValue::from_str(origin).with_context(|| format!("invalid CORS origin: {o}"))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        AllowOrigin::list(origins)
+    };
+
+    let methods = if any_method {
+        AllowMethods::any()
+    } else {
+        let methods = args
+            .cors_allowed_methods
+            .iter()
+            .map(|m| {
+                Method::from_str(&m.to_ascii_uppercase())
+                    .with_context(|| format!("invalid CORS method: {m}"))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        AllowMethods::list(methods)
+    };
+
+    let headers = if any_header {
+        AllowHeaders::any()
+    } else {
+        let headers = args
+            .cors_allowed_headers
+            .iter()
+            .map(|h| HeaderName::from_str(h).with_context(|| format!("invalid CORS header: {h}")))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        AllowHeaders::list(headers)
+    };
+
+    Ok(CorsLayer::new()
+        .allow_origin(origins)
+        .allow_methods(methods)
+        .allow_headers(headers)
+        .allow_credentials(args.cors_allow_credentials)
+        .expose_headers([header::RETRY_AFTER]))
+}
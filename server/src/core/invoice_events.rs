@@ -0,0 +1,147 @@
+use std::io::ErrorKind;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use cln_rpc::model::responses::WaitanyinvoiceResponse;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::context::Context;
+use crate::core::lightning_rpc_connector::LightningRPCConnector;
+use crate::core::utils;
+use crate::core::webhook::event::WebhookEvent;
+
+const PAY_INDEX_FILE: &str = "last_pay_index";
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+const PAY_INDEX_SCAN_PAGE: u64 = 100;
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct InvoiceSettledEvent {
+    /// Invoice label.
+    pub label: String,
+    /// Payment hash (hex).
+    pub payment_hash: String,
+    /// BOLT11 payment request (absent for BOLT12 invoices).
+    pub bolt11: Option<String>,
+    /// Invoice description (absent when only its hash was committed).
+    pub description: Option<String>,
+    /// Requested amount in millisatoshis (absent for "any amount" invoices).
+    pub amount_msat: Option<u64>,
+    /// Amount actually received in millisatoshis.
+    pub amount_received_msat: Option<u64>,
+    /// Unix timestamp (seconds) when the invoice was paid.
+    pub paid_at: Option<u64>,
+    /// Monotonic index of the payment among all paid invoices.
+    pub pay_index: u64,
+    /// Payment preimage (hex).
+    pub payment_preimage: Option<String>,
+}
+
+impl InvoiceSettledEvent {
+    fn new(pay_index: u64, res: WaitanyinvoiceResponse) -> Self {
+        Self {
+            label: res.label,
+            payment_hash: res.payment_hash.to_string(),
+            bolt11: res.bolt11,
+            description: res.description,
+            amount_msat: res.amount_msat.map(|a| a.msat()),
+            amount_received_msat: res.amount_received_msat.map(|a| a.msat()),
+            paid_at: res.paid_at,
+            pay_index,
+            payment_preimage: res.payment_preimage.map(|p| hex::encode(p.to_vec())),
+        }
+    }
+}
+
+/// Spawns the background task that follows CLN `waitanyinvoice` and broadcasts every
+/// settled invoice on `Context.invoice_events`.
+///
+/// The last processed `pay_index` is persisted in the data directory, so settlements that
+/// happen while the gateway is down are replayed on the next start.
+pub fn spawn_listener(ctx: Arc<Context>) {
+    tokio::spawn(async move {
+        let path = ctx.args.data_dir.join(PAY_INDEX_FILE);
+        loop {
+            if let Err(e) = listen(&ctx, &path).await {
+                tracing::warn!(
+                    "Invoice listener error, retrying in {:?}: {:#}",
+                    RETRY_DELAY,
+                    e
+                );
+            }
+            tokio::time::sleep(RETRY_DELAY).await;
+        }
+    });
+}
+
+async fn listen(ctx: &Context, path: &Path) -> anyhow::Result<()> {
+    // `waitanyinvoice` blocks, so the listener needs its own connection instead of the shared one.
+    let mut rpc = ctx.dedicated_cln_client().await?;
+
+    let mut last_pay_index = match read_pay_index(path).await? {
+        Some(index) => index,
+        None => {
+            // First start: don't replay the whole invoice history, begin from the latest payment.
+            let index = latest_pay_index(&mut rpc).await?;
+            write_pay_index(path, index).await?;
+            index
+        }
+    };
+
+    tracing::info!("Listening for invoice settlements after pay_index {last_pay_index}");
+
+    loop {
+        let res = rpc.waitanyinvoice(Some(last_pay_index)).await?;
+        let Some(pay_index) = res.pay_index else {
+            anyhow::bail!(
+                "waitanyinvoice returned invoice {} without pay_index",
+                res.label
+            );
+        };
+
+        let event = InvoiceSettledEvent::new(pay_index, res);
+        tracing::info!(label = %event.label, pay_index, "Invoice settled");
+
+        ctx.webhooks
+            .enqueue(WebhookEvent::InvoiceSettled(event.clone()))
+            .await;
+
+        // Sending fails only when nobody is subscribed, which is fine.
+        let _ = ctx.invoice_events.send(event);
+
+        write_pay_index(path, pay_index).await?;
+        last_pay_index = pay_index;
+    }
+}
+
+// Paid invoices are not updated again, so the latest payment has the highest `updated_index`
+// of all paid invoices. Scanning back from the current index a page at a time avoids listing
+// the whole invoice history.
+async fn latest_pay_index(rpc: &mut LightningRPCConnector) -> anyhow::Result<u64> {
+    let mut end = rpc.invoices_updated_index().await?;
+    while end > 0 {
+        let start = end.saturating_sub(PAY_INDEX_SCAN_PAGE - 1).max(1);
+        let res = rpc
+            .listinvoices_updated(start, (end - start + 1) as u32)
+            .await?;
+        if let Some(index) = res.invoices.iter().filter_map(|i| i.pay_index).max() {
+            return Ok(index);
+        }
+        end = start - 1;
+    }
+    Ok(0)
+}
+
+async fn read_pay_index(path: &Path) -> anyhow::Result<Option<u64>> {
+    match tokio::fs::read_to_string(path).await {
+        Ok(s) => Ok(Some(s.trim().parse()?)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+async fn write_pay_index(path: &Path, pay_index: u64) -> anyhow::Result<()> {
+    utils::write_file_atomic(path, pay_index.to_string().as_bytes()).await?;
+    Ok(())
+}
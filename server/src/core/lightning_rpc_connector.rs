@@ -1,8 +1,57 @@
 use std::path::Path;
 
-use cln_rpc::ClnRpc;
 use cln_rpc::model::{requests as clnreq, responses as clnresp};
-use cln_rpc::primitives::{Amount, AmountOrAll, PublicKey};
+use cln_rpc::primitives::{Amount, AmountOrAll, AmountOrAny, PublicKey, Sha256, TlvStream};
+use cln_rpc::{ClnRpc, RpcError};
+
+/// `checkrune` error codes meaning the rune itself was rejected (CLN `RUNE_NOT_AUTHORIZED`,
+/// `RUNE_NOT_PERMITTED` and `RUNE_BLACKLISTED`), as opposed to the call failing.
+const RUNE_REJECTION_CODES: [i32; 3] = [1501, 1502, 1503];
+
+/// `pay`/`keysend` error codes after which no part of the payment is in flight: invalid
+/// parameters, invoice already paid, destination failure, no route, too expensive, expired
+/// invoice, retries exhausted, invalid invoice request and declined preapprovals.
+const PAY_DEFINITIVE_FAILURE_CODES: [i32; 10] =
+    [-32602, 201, 203, 205, 206, 207, 210, 212, 213, 214];
+
+/// Whether a failed `pay`/`keysend` is known not to have sent anything. Other failures, such as a
+/// dropped connection or `PAY_IN_PROGRESS`, leave the payment's outcome open.
+pub fn is_definitive_pay_failure(e: &anyhow::Error) -> bool {
+    matches!(
+        e.downcast_ref::<RpcError>(),
+        Some(RpcError { code: Some(code), .. }) if PAY_DEFINITIVE_FAILURE_CODES.contains(code)
+    )
+}
+
+/// Announced addresses tried by `connect_via_gossip`. Each failure can take CLN's connect timeout,
+/// and a node may announce any number of addresses.
+const MAX_GOSSIP_CONNECT_ATTEMPTS: usize = 3;
+
+/// `FUNDING_BROADCAST_FAIL`: the funding transaction was built and signed, and may still reach
+/// the mempool.
+const FUNDCHANNEL_BROADCAST_FAILED: i32 = 303;
+
+/// Whether a failed `fundchannel` is known not to have funded anything, i.e. CLN rejected it
+/// before broadcasting. A dropped connection leaves the channel's fate open.
+pub fn is_definitive_fundchannel_failure(e: &anyhow::Error) -> bool {
+    matches!(
+        e.downcast_ref::<RpcError>(),
+        Some(RpcError { code: Some(code), .. }) if *code != FUNDCHANNEL_BROADCAST_FAILED
+    )
+}
+
+/// `withdraw` error codes raised before a transaction is signed: invalid parameters, funds
+/// insufficient (`FUND_CANNOT_AFFORD`) and an output below the dust limit (`FUND_OUTPUT_IS_DUST`).
+const WITHDRAW_DEFINITIVE_FAILURE_CODES: [i32; 3] = [-32602, 301, 302];
+
+/// Whether a failed `withdraw` is known not to have spent anything. Other failures, such as a
+/// broadcast error or a dropped connection, may leave the transaction in the mempool.
+pub fn is_definitive_withdraw_failure(e: &anyhow::Error) -> bool {
+    matches!(
+        e.downcast_ref::<RpcError>(),
+        Some(RpcError { code: Some(code), .. }) if WITHDRAW_DEFINITIVE_FAILURE_CODES.contains(code)
+    )
+}
 
 pub struct LightningRPCConnector {
     rpc: ClnRpc,
@@ -31,6 +80,8 @@ impl LightningRPCConnector {
         remote_id: PublicKey,
         amount_sat: u64,
         announce: Option<bool>,
+        push_msat: Option<u64>,
+        mindepth: Option<u32>,
     ) -> anyhow::Result<clnresp::FundchannelResponse> {
         let req = clnreq::FundchannelRequest {
             id: remote_id,
@@ -39,8 +90,8 @@ impl LightningRPCConnector {
             feerate: None,
             minconf: None,
             utxos: None,
-            mindepth: None,
-            push_msat: None,
+            mindepth,
+            push_msat: push_msat.map(Amount::from_msat),
             close_to: None,
             request_amt: None,
             reserve: None,
@@ -52,14 +103,190 @@ impl LightningRPCConnector {
         Ok(res)
     }
 
+    pub async fn listpeers(
+        &mut self,
+        id: Option<PublicKey>,
+    ) -> anyhow::Result<clnresp::ListpeersResponse> {
+        let req = clnreq::ListpeersRequest { id, level: None };
+
+        let res = self.rpc.call_typed(&req).await?;
+        Ok(res)
+    }
+
+    pub async fn listpeerchannels(
+        &mut self,
+        id: Option<PublicKey>,
+    ) -> anyhow::Result<clnresp::ListpeerchannelsResponse> {
+        let req = clnreq::ListpeerchannelsRequest { id };
+
+        let res = self.rpc.call_typed(&req).await?;
+        Ok(res)
+    }
+
+    /// Closes a channel (by channel id, short channel id or peer id), mutually unless the peer
+    /// doesn't cooperate within `unilateraltimeout` seconds.
+    pub async fn close(
+        &mut self,
+        id: String,
+        unilateraltimeout: Option<u32>,
+        destination: Option<String>,
+    ) -> anyhow::Result<clnresp::CloseResponse> {
+        let req = clnreq::CloseRequest {
+            id,
+            unilateraltimeout,
+            destination,
+            fee_negotiation_step: None,
+            wrong_funding: None,
+            force_lease_closed: None,
+            feerange: None,
+        };
+
+        let res = self.rpc.call_typed(&req).await?;
+        Ok(res)
+    }
+
+    /// Updates the fees and HTLC limits of a channel, of all channels with a peer, or of every
+    /// channel (`all`). Unset values are left unchanged.
+    pub async fn setchannel(
+        &mut self,
+        id: String,
+        feebase_msat: Option<u64>,
+        feeppm: Option<u32>,
+        htlcmin_msat: Option<u64>,
+        htlcmax_msat: Option<u64>,
+    ) -> anyhow::Result<clnresp::SetchannelResponse> {
+        let req = clnreq::SetchannelRequest {
+            id,
+            feebase: feebase_msat.map(Amount::from_msat),
+            feeppm,
+            htlcmin: htlcmin_msat.map(Amount::from_msat),
+            htlcmax: htlcmax_msat.map(Amount::from_msat),
+            enforcedelay: None,
+            ignorefeelimits: None,
+        };
+
+        let res = self.rpc.call_typed(&req).await?;
+        Ok(res)
+    }
+
+    pub async fn listnodes(
+        &mut self,
+        id: Option<PublicKey>,
+    ) -> anyhow::Result<clnresp::ListnodesResponse> {
+        let req = clnreq::ListnodesRequest { id };
+
+        let res = self.rpc.call_typed(&req).await?;
+        Ok(res)
+    }
+
+    /// Connects to `id` unless it already is, using the addresses CLN knows from the gossip.
+    pub async fn ensure_connected(&mut self, id: PublicKey) -> anyhow::Result<()> {
+        let peers = self.listpeers(Some(id)).await?;
+        if !peers.peers.iter().any(|p| p.connected) && self.connect_via_gossip(id).await?.is_none()
+        {
+            anyhow::bail!("no address of {} known from the gossip", id);
+        }
+        Ok(())
+    }
+
+    /// Tries the addresses `id` announced in the gossip, clearnet first, until one connects.
+    /// At most `MAX_GOSSIP_CONNECT_ATTEMPTS` are tried, and Tor addresses only when CLN has a
+    /// proxy. `None` when the gossip has no address for it.
+    pub async fn connect_via_gossip(
+        &mut self,
+        id: PublicKey,
+    ) -> anyhow::Result<Option<clnresp::ConnectResponse>> {
+        let nodes = self.listnodes(Some(id)).await?;
+        let mut addresses: Vec<(String, u16, bool)> = nodes
+            .nodes
+            .into_iter()
+            .flat_map(|node| node.addresses.unwrap_or_default())
+            .filter_map(|a| {
+                let tor = matches!(
+                    a.item_type,
+                    clnresp::ListnodesNodesAddressesType::TORV2
+                        | clnresp::ListnodesNodesAddressesType::TORV3
+                );
+                a.address.map(|address| (address, a.port, tor))
+            })
+            .collect();
+
+        if addresses.iter().any(|(_, _, tor)| *tor) && self.has_proxy().await == Some(false) {
+            addresses.retain(|(_, _, tor)| !tor);
+            if addresses.is_empty() {
+                anyhow::bail!("{} only announces Tor addresses and CLN has no proxy", id);
+            }
+        }
+        // A stable sort keeps the announced order within clearnet and Tor.
+        addresses.sort_by_key(|(_, _, tor)| *tor);
+
+        let mut last_error = None;
+        for (host, port, _) in addresses.into_iter().take(MAX_GOSSIP_CONNECT_ATTEMPTS) {
+            match self.connect(id, Some(host.clone()), Some(port)).await {
+                Ok(res) => return Ok(Some(res)),
+                Err(e) => {
+                    tracing::debug!("Connecting to {}@{}:{} failed: {}", id, host, port, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        match last_error {
+            Some(e) => Err(e),
+            None => Ok(None),
+        }
+    }
+
+    /// Whether CLN has a `proxy` to reach Tor addresses; `None` when `listconfigs` fails.
+    pub async fn has_proxy(&mut self) -> Option<bool> {
+        let res: serde_json::Value = self
+            .rpc
+            .call_raw("listconfigs", &serde_json::json!({ "config": "proxy" }))
+            .await
+            .ok()?;
+        Some(
+            res["configs"]["proxy"]["value_str"]
+                .as_str()
+                .is_some_and(|proxy| !proxy.is_empty()),
+        )
+    }
+
+    /// Connects to a peer, letting CLN look up its address in the gossip when none is given.
+    pub async fn connect(
+        &mut self,
+        id: PublicKey,
+        host: Option<String>,
+        port: Option<u16>,
+    ) -> anyhow::Result<clnresp::ConnectResponse> {
+        let req = clnreq::ConnectRequest {
+            id: id.to_string(),
+            host,
+            port,
+        };
+
+        let res = self.rpc.call_typed(&req).await?;
+        Ok(res)
+    }
+
+    /// Disconnects a peer; `force` also drops it while channels with it are active.
+    pub async fn disconnect(
+        &mut self,
+        id: PublicKey,
+        force: Option<bool>,
+    ) -> anyhow::Result<clnresp::DisconnectResponse> {
+        let req = clnreq::DisconnectRequest { id, force };
+
+        let res = self.rpc.call_typed(&req).await?;
+        Ok(res)
+    }
+
     pub async fn withdraw(
         &mut self,
         destination: String,
-        amount_sat: u64,
+        satoshi: AmountOrAll,
     ) -> anyhow::Result<clnresp::WithdrawResponse> {
         let req = clnreq::WithdrawRequest {
             destination,
-            satoshi: AmountOrAll::Amount(Amount::from_sat(amount_sat)),
+            satoshi,
             feerate: None,
             minconf: None,
             utxos: None,
@@ -68,4 +295,324 @@ impl LightningRPCConnector {
         let res: clnresp::WithdrawResponse = self.rpc.call_typed(&req).await?;
         Ok(res)
     }
+
+    pub async fn listfunds(&mut self) -> anyhow::Result<clnresp::ListfundsResponse> {
+        let res = self
+            .rpc
+            .call_typed(&clnreq::ListfundsRequest { spent: None })
+            .await?;
+        Ok(res)
+    }
+
+    pub async fn newaddr(&mut self) -> anyhow::Result<clnresp::NewaddrResponse> {
+        let req = clnreq::NewaddrRequest {
+            addresstype: Some(clnreq::NewaddrAddresstype::BECH32),
+        };
+
+        let res = self.rpc.call_typed(&req).await?;
+        Ok(res)
+    }
+
+    pub async fn invoice(
+        &mut self,
+        amount_msat: Option<u64>,
+        label: String,
+        description: String,
+        expiry: Option<u64>,
+        deschashonly: Option<bool>,
+    ) -> anyhow::Result<clnresp::InvoiceResponse> {
+        let amount_msat = match amount_msat {
+            Some(msat) => AmountOrAny::Amount(Amount::from_msat(msat)),
+            None => AmountOrAny::Any,
+        };
+
+        let req = clnreq::InvoiceRequest {
+            amount_msat,
+            label,
+            description,
+            expiry,
+            deschashonly,
+            cltv: None,
+            preimage: None,
+            exposeprivatechannels: None,
+            fallbacks: None,
+        };
+
+        let res = self.rpc.call_typed(&req).await?;
+        Ok(res)
+    }
+
+    pub async fn listinvoices(
+        &mut self,
+        label: Option<String>,
+        start: Option<u64>,
+        limit: Option<u32>,
+    ) -> anyhow::Result<clnresp::ListinvoicesResponse> {
+        // Pagination in CLN requires an explicit index; we always page by creation order.
+        let index =
+            (start.is_some() || limit.is_some()).then_some(clnreq::ListinvoicesIndex::CREATED);
+
+        let req = clnreq::ListinvoicesRequest {
+            label,
+            index,
+            start,
+            limit,
+            invstring: None,
+            offer_id: None,
+            payment_hash: None,
+        };
+
+        let res = self.rpc.call_typed(&req).await?;
+        Ok(res)
+    }
+
+    /// Lists the invoices whose `updated_index` is in `start..start + limit` (paid, expired or
+    /// otherwise changed since creation).
+    pub async fn listinvoices_updated(
+        &mut self,
+        start: u64,
+        limit: u32,
+    ) -> anyhow::Result<clnresp::ListinvoicesResponse> {
+        let req = clnreq::ListinvoicesRequest {
+            label: None,
+            index: Some(clnreq::ListinvoicesIndex::UPDATED),
+            start: Some(start),
+            limit: Some(limit),
+            invstring: None,
+            offer_id: None,
+            payment_hash: None,
+        };
+
+        let res = self.rpc.call_typed(&req).await?;
+        Ok(res)
+    }
+
+    /// Current `updated` index of the invoices subsystem (`wait` returns at once for 0).
+    pub async fn invoices_updated_index(&mut self) -> anyhow::Result<u64> {
+        let req = clnreq::WaitRequest {
+            subsystem: clnreq::WaitSubsystem::INVOICES,
+            indexname: clnreq::WaitIndexname::UPDATED,
+            nextvalue: 0,
+        };
+
+        let res = self.rpc.call_typed(&req).await?;
+        Ok(res.updated.unwrap_or(0))
+    }
+
+    pub async fn delinvoice(
+        &mut self,
+        label: String,
+        status: clnreq::DelinvoiceStatus,
+    ) -> anyhow::Result<clnresp::DelinvoiceResponse> {
+        let req = clnreq::DelinvoiceRequest {
+            label,
+            status,
+            desconly: None,
+        };
+
+        let res = self.rpc.call_typed(&req).await?;
+        Ok(res)
+    }
+
+    /// Blocks until an invoice with a `pay_index` greater than `lastpay_index` is paid.
+    pub async fn waitanyinvoice(
+        &mut self,
+        lastpay_index: Option<u64>,
+    ) -> anyhow::Result<clnresp::WaitanyinvoiceResponse> {
+        let req = clnreq::WaitanyinvoiceRequest {
+            lastpay_index,
+            timeout: None,
+        };
+
+        let res = self.rpc.call_typed(&req).await?;
+        Ok(res)
+    }
+
+    pub async fn decode(&mut self, string: String) -> anyhow::Result<clnresp::DecodeResponse> {
+        let res = self
+            .rpc
+            .call_typed(&clnreq::DecodeRequest { string })
+            .await?;
+        Ok(res)
+    }
+
+    pub async fn pay(
+        &mut self,
+        bolt11: String,
+        amount_msat: Option<u64>,
+        maxfee_msat: Option<u64>,
+    ) -> anyhow::Result<clnresp::PayResponse> {
+        let req = clnreq::PayRequest {
+            bolt11,
+            amount_msat: amount_msat.map(Amount::from_msat),
+            maxfee: maxfee_msat.map(Amount::from_msat),
+            description: None,
+            exemptfee: None,
+            label: None,
+            localinvreqid: None,
+            maxdelay: None,
+            maxfeepercent: None,
+            partial_msat: None,
+            retry_for: None,
+            riskfactor: None,
+            exclude: None,
+        };
+
+        let res = self.rpc.call_typed(&req).await?;
+        Ok(res)
+    }
+
+    pub async fn listpays(
+        &mut self,
+        payment_hash: Option<Sha256>,
+    ) -> anyhow::Result<clnresp::ListpaysResponse> {
+        let req = clnreq::ListpaysRequest {
+            payment_hash,
+            bolt11: None,
+            index: None,
+            limit: None,
+            start: None,
+            status: None,
+        };
+
+        let res = self.rpc.call_typed(&req).await?;
+        Ok(res)
+    }
+
+    pub async fn keysend(
+        &mut self,
+        destination: PublicKey,
+        amount_msat: u64,
+        maxfee_msat: Option<u64>,
+        extratlvs: Option<TlvStream>,
+    ) -> anyhow::Result<clnresp::KeysendResponse> {
+        let req = clnreq::KeysendRequest {
+            destination,
+            amount_msat: Amount::from_msat(amount_msat),
+            maxfee: maxfee_msat.map(Amount::from_msat),
+            extratlvs,
+            exemptfee: None,
+            label: None,
+            maxdelay: None,
+            maxfeepercent: None,
+            retry_for: None,
+            routehints: None,
+        };
+
+        let res = self.rpc.call_typed(&req).await?;
+        Ok(res)
+    }
+
+    pub async fn offer(
+        &mut self,
+        amount_msat: Option<u64>,
+        description: Option<String>,
+        issuer: Option<String>,
+        quantity_max: Option<u64>,
+        recurrence: Option<String>,
+        single_use: Option<bool>,
+    ) -> anyhow::Result<clnresp::OfferResponse> {
+        let amount = match amount_msat {
+            Some(msat) => format!("{}msat", msat),
+            None => "any".to_string(),
+        };
+
+        let req = clnreq::OfferRequest {
+            amount,
+            description,
+            issuer,
+            quantity_max,
+            recurrence,
+            single_use,
+            absolute_expiry: None,
+            label: None,
+            recurrence_base: None,
+            recurrence_limit: None,
+            recurrence_paywindow: None,
+            recurrence_start_any_period: None,
+        };
+
+        let res = self.rpc.call_typed(&req).await?;
+        Ok(res)
+    }
+
+    pub async fn listoffers(
+        &mut self,
+        offer_id: Option<Sha256>,
+        active_only: Option<bool>,
+    ) -> anyhow::Result<clnresp::ListoffersResponse> {
+        let req = clnreq::ListoffersRequest {
+            offer_id,
+            active_only,
+        };
+
+        let res = self.rpc.call_typed(&req).await?;
+        Ok(res)
+    }
+
+    pub async fn disableoffer(
+        &mut self,
+        offer_id: Sha256,
+    ) -> anyhow::Result<clnresp::DisableofferResponse> {
+        let res = self
+            .rpc
+            .call_typed(&clnreq::DisableofferRequest { offer_id })
+            .await?;
+        Ok(res)
+    }
+
+    pub async fn fetchinvoice(
+        &mut self,
+        offer: String,
+        amount_msat: Option<u64>,
+        quantity: Option<u64>,
+        payer_note: Option<String>,
+    ) -> anyhow::Result<clnresp::FetchinvoiceResponse> {
+        let req = clnreq::FetchinvoiceRequest {
+            offer,
+            amount_msat: amount_msat.map(Amount::from_msat),
+            quantity,
+            payer_note,
+            bip353: None,
+            payer_metadata: None,
+            recurrence_counter: None,
+            recurrence_label: None,
+            recurrence_start: None,
+            timeout: None,
+        };
+
+        let res = self.rpc.call_typed(&req).await?;
+        Ok(res)
+    }
+
+    /// Asks CLN whether `rune` allows calling `method` with the named `params`.
+    /// Returns `Ok(Err(reason))` when the rune is rejected.
+    pub async fn checkrune(
+        &mut self,
+        rune: String,
+        method: &str,
+        params: serde_json::Value,
+    ) -> anyhow::Result<Result<(), String>> {
+        let req = serde_json::json!({
+            "rune": rune,
+            "method": method,
+            "params": params,
+        });
+
+        // CLN reports most failed restrictions as an RPC error rather than `valid: false`.
+        match self
+            .rpc
+            .call_raw::<clnresp::CheckruneResponse, _>("checkrune", &req)
+            .await
+        {
+            Ok(res) if res.valid => Ok(Ok(())),
+            Ok(_) => Ok(Err("rune is not valid".to_string())),
+            Err(RpcError {
+                code: Some(code),
+                message,
+                ..
+            }) if RUNE_REJECTION_CODES.contains(&code) => Ok(Err(message)),
+            Err(e) => Err(e.into()),
+        }
+    }
 }
@@ -0,0 +1,1018 @@
+//! LSPS1 (bLIP-51): clients buy a channel from the node, pay for it with a BOLT11 invoice or
+//! on-chain, and the node funds the channel once the payment is seen.
+
+use std::collections::HashSet;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use bitcoin::Address;
+use cln_rpc::model::responses::{ListfundsOutputsStatus, ListinvoicesInvoicesStatus};
+use cln_rpc::primitives::{ChannelSide, PublicKey};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Mutex, Notify, broadcast::error::RecvError};
+use tokio::task::JoinHandle;
+
+use crate::context::Context;
+use crate::core::cli::Args;
+use crate::core::lightning_rpc_connector::is_definitive_fundchannel_failure;
+use crate::core::lsps::{LspsError, amount_string};
+use crate::core::runtime_config::RuntimeConfig;
+use crate::core::spending_limits::SpendFlow;
+use crate::core::utils;
+use crate::core::webhook::event::WebhookEvent;
+
+const STORE_FILE: &str = "lsps1_orders.json";
+const INVOICE_LABEL_PREFIX: &str = "lsps1-";
+const WORKER_INTERVAL: Duration = Duration::from_secs(15);
+/// Order invoices are seen settling through `invoice_events`; listing them one by one is only a
+/// fallback, done this often.
+const INVOICE_POLL_INTERVAL_MS: u64 = 5 * 60 * 1_000;
+/// How long orders that expired unpaid are kept, so that clients can still look them up.
+const UNPAID_RETENTION_MS: u64 = 7 * 24 * 60 * 60 * 1_000;
+/// How long funding is retried after payment (e.g. while the client is offline).
+const FUNDING_RETRY_MS: u64 = 24 * 60 * 60 * 1_000;
+const BLOCK_INTERVAL_MS: u64 = 10 * 60 * 1_000;
+
+const MIN_REQUIRED_CHANNEL_CONFIRMATIONS: u16 = 1;
+const MIN_FUNDING_CONFIRMS_WITHIN_BLOCKS: u16 = 6;
+const MIN_ONCHAIN_PAYMENT_CONFIRMATIONS: u16 = 1;
+
+/// `create_order` asked for something outside `get_info`.
+const OPTION_MISMATCH: i32 = 100;
+/// `get_order` for an unknown order.
+const NOT_FOUND: i32 = 101;
+/// Implementation-defined JSON-RPC server error: `SERVER_LSPS1_MAX_OPEN_ORDERS` is reached.
+const TOO_MANY_ORDERS: i32 = -32000;
+
+/// Result of `lsps1.get_info`.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct Lsps1Options {
+    pub min_required_channel_confirmations: u16,
+    pub min_funding_confirms_within_blocks: u16,
+    pub supports_zero_channel_reserve: bool,
+    pub max_channel_expiry_blocks: u32,
+    #[serde(with = "amount_string")]
+    #[schema(value_type = String)]
+    pub min_initial_client_balance_sat: u64,
+    #[serde(with = "amount_string")]
+    #[schema(value_type = String)]
+    pub max_initial_client_balance_sat: u64,
+    #[serde(with = "amount_string")]
+    #[schema(value_type = String)]
+    pub min_initial_lsp_balance_sat: u64,
+    #[serde(with = "amount_string")]
+    #[schema(value_type = String)]
+    pub max_initial_lsp_balance_sat: u64,
+    #[serde(with = "amount_string")]
+    #[schema(value_type = String)]
+    pub min_channel_balance_sat: u64,
+    #[serde(with = "amount_string")]
+    #[schema(value_type = String)]
+    pub max_channel_balance_sat: u64,
+}
+
+/// Params of `lsps1.create_order`.
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct CreateOrderParams {
+    /// Node id of the client. Over BOLT8 this comes from the transport; over HTTP it is required.
+    pub public_key: String,
+    #[serde(with = "amount_string")]
+    #[schema(value_type = String)]
+    pub lsp_balance_sat: u64,
+    #[serde(with = "amount_string", default)]
+    #[schema(value_type = String)]
+    pub client_balance_sat: u64,
+    pub required_channel_confirmations: u16,
+    pub funding_confirms_within_blocks: u16,
+    pub channel_expiry_blocks: u32,
+    #[serde(default)]
+    pub token: Option<String>,
+    pub refund_onchain_address: Option<String>,
+    pub announce_channel: bool,
+}
+
+/// Params of `lsps1.get_order`.
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct GetOrderParams {
+    pub order_id: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OrderState {
+    /// Waiting for payment or for the channel to be funded.
+    Created,
+    /// The channel was funded.
+    Completed,
+    /// The order expired unpaid, or the channel could not be funded.
+    Failed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PaymentState {
+    ExpectPayment,
+    Hold,
+    Paid,
+    Refunded,
+}
+
+/// Order as returned by `lsps1.create_order` and `lsps1.get_order`.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct Lsps1Order {
+    pub order_id: String,
+    #[serde(with = "amount_string")]
+    #[schema(value_type = String)]
+    pub lsp_balance_sat: u64,
+    #[serde(with = "amount_string")]
+    #[schema(value_type = String)]
+    pub client_balance_sat: u64,
+    pub required_channel_confirmations: u16,
+    pub funding_confirms_within_blocks: u16,
+    pub channel_expiry_blocks: u32,
+    pub token: String,
+    /// RFC 3339 timestamp.
+    pub created_at: String,
+    pub announce_channel: bool,
+    pub order_state: OrderState,
+    pub payment: Lsps1Payment,
+    pub channel: Option<Lsps1Channel>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct Lsps1Payment {
+    pub bolt11: Lsps1Bolt11Payment,
+    /// Absent when the node could not provide an address.
+    pub onchain: Option<Lsps1OnchainPayment>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct Lsps1Bolt11Payment {
+    pub state: PaymentState,
+    pub expires_at: String,
+    #[serde(with = "amount_string")]
+    #[schema(value_type = String)]
+    pub fee_total_sat: u64,
+    #[serde(with = "amount_string")]
+    #[schema(value_type = String)]
+    pub order_total_sat: u64,
+    pub invoice: String,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct Lsps1OnchainPayment {
+    pub state: PaymentState,
+    pub expires_at: String,
+    #[serde(with = "amount_string")]
+    #[schema(value_type = String)]
+    pub fee_total_sat: u64,
+    #[serde(with = "amount_string")]
+    #[schema(value_type = String)]
+    pub order_total_sat: u64,
+    pub address: String,
+    /// Always null: unconfirmed payments are not accepted.
+    pub min_fee_for_0conf: Option<u64>,
+    pub min_onchain_payment_confirmations: u16,
+    pub refund_onchain_address: Option<String>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct Lsps1Channel {
+    pub funded_at: String,
+    /// `<txid>:<vout>` of the funding output.
+    pub funding_outpoint: String,
+    /// Estimate based on `channel_expiry_blocks`.
+    pub expires_at: String,
+}
+
+/// Paid order that failed: the node owes the client its payment back. Neither payment method
+/// can be refunded automatically (a BOLT11 payment has no return path, an on-chain refund needs an
+/// operator's fee decision), so these are listed until marked refunded.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct Lsps1Refund {
+    pub order_id: String,
+    pub client_node_id: String,
+    /// `bolt11` or `onchain`.
+    pub paid_via: String,
+    /// What the client paid, in satoshis.
+    pub amount_sat: u64,
+    /// Refund address the client gave with the order, if any.
+    pub refund_onchain_address: Option<String>,
+    pub paid_at: String,
+    /// Why the order failed.
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ChannelRecord {
+    funded_at_ms: u64,
+    channel_id: String,
+    txid: String,
+    outnum: u32,
+}
+
+/// Persisted order, including what the client doesn't see.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct OrderRecord {
+    order_id: String,
+    client_node_id: String,
+    lsp_balance_sat: u64,
+    client_balance_sat: u64,
+    required_channel_confirmations: u16,
+    funding_confirms_within_blocks: u16,
+    channel_expiry_blocks: u32,
+    token: String,
+    refund_onchain_address: Option<String>,
+    announce_channel: bool,
+    created_at_ms: u64,
+    expires_at_ms: u64,
+    fee_total_sat: u64,
+    order_total_sat: u64,
+    state: OrderState,
+    invoice_label: String,
+    invoice: String,
+    bolt11_state: PaymentState,
+    onchain_address: Option<String>,
+    onchain_state: PaymentState,
+    paid_at_ms: Option<u64>,
+    /// Set right before `fundchannel` and cleared once its outcome is known. An order that still
+    /// has it (the server stopped mid-call, or the call's outcome was lost) is reconciled against
+    /// `listpeerchannels` before it is funded again.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    funding_started_at_ms: Option<u64>,
+    channel: Option<ChannelRecord>,
+    /// Last funding error, or why the order failed.
+    error: Option<String>,
+}
+
+impl OrderRecord {
+    fn is_paid(&self) -> bool {
+        self.paid_at_ms.is_some()
+    }
+
+    fn needs_refund(&self) -> bool {
+        self.state == OrderState::Failed
+            && self.is_paid()
+            && self.bolt11_state != PaymentState::Refunded
+            && self.onchain_state != PaymentState::Refunded
+    }
+
+    fn refund(&self) -> Lsps1Refund {
+        Lsps1Refund {
+            order_id: self.order_id.clone(),
+            client_node_id: self.client_node_id.clone(),
+            paid_via: if self.onchain_state == PaymentState::Paid {
+                "onchain"
+            } else {
+                "bolt11"
+            }
+            .to_string(),
+            amount_sat: self.order_total_sat,
+            refund_onchain_address: self.refund_onchain_address.clone(),
+            paid_at: utils::rfc3339(self.paid_at_ms.unwrap_or_default()),
+            reason: self.error.clone(),
+        }
+    }
+
+    fn view(&self) -> Lsps1Order {
+        let expires_at = utils::rfc3339(self.expires_at_ms);
+
+        Lsps1Order {
+            order_id: self.order_id.clone(),
+            lsp_balance_sat: self.lsp_balance_sat,
+            client_balance_sat: self.client_balance_sat,
+            required_channel_confirmations: self.required_channel_confirmations,
+            funding_confirms_within_blocks: self.funding_confirms_within_blocks,
+            channel_expiry_blocks: self.channel_expiry_blocks,
+            token: self.token.clone(),
+            created_at: utils::rfc3339(self.created_at_ms),
+            announce_channel: self.announce_channel,
+            order_state: self.state,
+            payment: Lsps1Payment {
+                bolt11: Lsps1Bolt11Payment {
+                    state: self.bolt11_state,
+                    expires_at: expires_at.clone(),
+                    fee_total_sat: self.fee_total_sat,
+                    order_total_sat: self.order_total_sat,
+                    invoice: self.invoice.clone(),
+                },
+                onchain: self
+                    .onchain_address
+                    .as_ref()
+                    .map(|address| Lsps1OnchainPayment {
+                        state: self.onchain_state,
+                        expires_at,
+                        fee_total_sat: self.fee_total_sat,
+                        order_total_sat: self.order_total_sat,
+                        address: address.clone(),
+                        min_fee_for_0conf: None,
+                        min_onchain_payment_confirmations: MIN_ONCHAIN_PAYMENT_CONFIRMATIONS,
+                        refund_onchain_address: self.refund_onchain_address.clone(),
+                    }),
+            },
+            channel: self.channel.as_ref().map(|c| Lsps1Channel {
+                funded_at: utils::rfc3339(c.funded_at_ms),
+                funding_outpoint: format!("{}:{}", c.txid, c.outnum),
+                expires_at: utils::rfc3339(
+                    c.funded_at_ms + u64::from(self.channel_expiry_blocks) * BLOCK_INTERVAL_MS,
+                ),
+            }),
+        }
+    }
+}
+
+fn option_mismatch(property: &str, message: impl Into<String>) -> LspsError {
+    LspsError {
+        code: OPTION_MISMATCH,
+        message: "Option mismatch".to_string(),
+        data: Some(serde_json::json!({ "property": property, "message": message.into() })),
+    }
+}
+
+/// Orders, persisted in the data directory, and the worker that settles them.
+pub struct Lsps1Service {
+    store_path: PathBuf,
+    orders: Mutex<Vec<OrderRecord>>,
+    /// Labels of order invoices that settled since the last run.
+    settled: std::sync::Mutex<HashSet<String>>,
+    last_invoice_poll_ms: AtomicU64,
+    wake: Notify,
+}
+
+impl Lsps1Service {
+    pub fn new(args: &Args) -> Self {
+        let store_path = args.data_dir.join(STORE_FILE);
+        let orders = match std::fs::read(&store_path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring unreadable LSPS1 orders {:?}: {}", store_path, e);
+                Vec::new()
+            }),
+            Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(e) => {
+                tracing::warn!("Could not read LSPS1 orders {:?}: {}", store_path, e);
+                Vec::new()
+            }
+        };
+
+        Self {
+            store_path,
+            orders: Mutex::new(orders),
+            settled: Default::default(),
+            last_invoice_poll_ms: AtomicU64::new(0),
+            wake: Notify::new(),
+        }
+    }
+
+    /// LSP balances follow the channel bounds of the channel callback.
+    pub fn options(args: &Args, runtime: &RuntimeConfig) -> Lsps1Options {
+        Lsps1Options {
+            min_required_channel_confirmations: MIN_REQUIRED_CHANNEL_CONFIRMATIONS,
+            min_funding_confirms_within_blocks: MIN_FUNDING_CONFIRMS_WITHIN_BLOCKS,
+            supports_zero_channel_reserve: false,
+            max_channel_expiry_blocks: args.lsps1_max_channel_expiry_blocks,
+            min_initial_client_balance_sat: 0,
+            max_initial_client_balance_sat: args.lsps1_max_client_balance_sat,
+            min_initial_lsp_balance_sat: runtime.channel_min_sat,
+            max_initial_lsp_balance_sat: runtime.channel_max_sat,
+            min_channel_balance_sat: runtime.channel_min_sat,
+            max_channel_balance_sat: runtime.channel_max_sat,
+        }
+    }
+
+    fn check_params(options: &Lsps1Options, params: &CreateOrderParams) -> Result<(), LspsError> {
+        if params.lsp_balance_sat < options.min_initial_lsp_balance_sat
+            || params.lsp_balance_sat > options.max_initial_lsp_balance_sat
+        {
+            return Err(option_mismatch(
+                "lsp_balance_sat",
+                format!(
+                    "must be between {} and {}",
+                    options.min_initial_lsp_balance_sat, options.max_initial_lsp_balance_sat
+                ),
+            ));
+        }
+        if params.client_balance_sat > options.max_initial_client_balance_sat {
+            return Err(option_mismatch(
+                "client_balance_sat",
+                format!("must be at most {}", options.max_initial_client_balance_sat),
+            ));
+        }
+        let total = params
+            .lsp_balance_sat
+            .saturating_add(params.client_balance_sat);
+        if total > options.max_channel_balance_sat {
+            return Err(option_mismatch(
+                "client_balance_sat",
+                format!(
+                    "the channel of {} sat exceeds {}",
+                    total, options.max_channel_balance_sat
+                ),
+            ));
+        }
+        if params.required_channel_confirmations < options.min_required_channel_confirmations {
+            return Err(option_mismatch(
+                "required_channel_confirmations",
+                format!(
+                    "must be at least {}",
+                    options.min_required_channel_confirmations
+                ),
+            ));
+        }
+        if params.funding_confirms_within_blocks < options.min_funding_confirms_within_blocks {
+            return Err(option_mismatch(
+                "funding_confirms_within_blocks",
+                format!(
+                    "must be at least {}",
+                    options.min_funding_confirms_within_blocks
+                ),
+            ));
+        }
+        if params.channel_expiry_blocks > options.max_channel_expiry_blocks {
+            return Err(option_mismatch(
+                "channel_expiry_blocks",
+                format!("must be at most {}", options.max_channel_expiry_blocks),
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub async fn create_order(
+        &self,
+        ctx: &Context,
+        params: CreateOrderParams,
+    ) -> Result<Lsps1Order, LspsError> {
+        let client_node_id = PublicKey::from_str(params.public_key.trim())
+            .map_err(|e| LspsError::invalid_params("public_key", e.to_string()))?;
+        if let Some(address) = &params.refund_onchain_address
+            && let Err(e) = Address::from_str(address)
+        {
+            return Err(LspsError::invalid_params(
+                "refund_onchain_address",
+                e.to_string(),
+            ));
+        }
+
+        let runtime = ctx.runtime.load_full();
+        Self::check_params(&Self::options(&ctx.args, &runtime), &params)?;
+
+        let open = self
+            .orders
+            .lock()
+            .await
+            .iter()
+            .filter(|o| o.state == OrderState::Created && !o.is_paid())
+            .count();
+        if open >= ctx.args.lsps1_max_open_orders {
+            tracing::warn!(
+                "LSPS1: refusing an order, {} orders are awaiting payment",
+                open
+            );
+            return Err(LspsError::new(
+                TOO_MANY_ORDERS,
+                "Too many open orders, try again later",
+            ));
+        }
+
+        // The client pays the fee plus whatever is pushed to its side.
+        let fee_total_sat = ctx.args.lsps1_fee_base_sat.saturating_add(
+            params
+                .lsp_balance_sat
+                .saturating_mul(ctx.args.lsps1_fee_ppm)
+                / 1_000_000,
+        );
+        let order_total_sat = fee_total_sat.saturating_add(params.client_balance_sat);
+
+        let order_id = uuid::Uuid::new_v4().to_string();
+        let invoice_label = format!("{INVOICE_LABEL_PREFIX}{order_id}");
+        let now = utils::unix_time_ms();
+        let expiry_secs = ctx.args.lsps1_order_expiry_secs;
+
+        let (invoice, onchain_address) = {
+            let mut rpc = ctx.cln_client.lock().await;
+            let invoice = rpc
+                .invoice(
+                    Some(order_total_sat.saturating_mul(1_000)),
+                    invoice_label.clone(),
+                    format!("LSPS1 channel order {order_id}"),
+                    Some(expiry_secs),
+                    None,
+                )
+                .await
+                .map_err(|e| LspsError::internal(format!("could not create invoice: {e}")))?;
+
+            // An order can still be paid over Lightning without an address.
+            let address = match rpc.newaddr().await {
+                Ok(res) => res.bech32,
+                Err(e) => {
+                    tracing::warn!("LSPS1 order {} without on-chain address: {}", order_id, e);
+                    None
+                }
+            };
+
+            (invoice.bolt11, address)
+        };
+
+        let record = OrderRecord {
+            order_id,
+            client_node_id: client_node_id.to_string(),
+            lsp_balance_sat: params.lsp_balance_sat,
+            client_balance_sat: params.client_balance_sat,
+            required_channel_confirmations: params.required_channel_confirmations,
+            funding_confirms_within_blocks: params.funding_confirms_within_blocks,
+            channel_expiry_blocks: params.channel_expiry_blocks,
+            token: params.token.unwrap_or_default(),
+            refund_onchain_address: params.refund_onchain_address,
+            announce_channel: params.announce_channel,
+            created_at_ms: now,
+            expires_at_ms: now.saturating_add(expiry_secs.saturating_mul(1_000)),
+            fee_total_sat,
+            order_total_sat,
+            state: OrderState::Created,
+            invoice_label,
+            invoice,
+            bolt11_state: PaymentState::ExpectPayment,
+            onchain_address,
+            onchain_state: PaymentState::ExpectPayment,
+            paid_at_ms: None,
+            funding_started_at_ms: None,
+            channel: None,
+            error: None,
+        };
+
+        tracing::info!(
+            order_id = %record.order_id,
+            client = %record.client_node_id,
+            "LSPS1 order created for {} sat (total {} sat)",
+            record.lsp_balance_sat + record.client_balance_sat,
+            record.order_total_sat
+        );
+
+        let view = record.view();
+        let mut orders = self.orders.lock().await;
+        orders.push(record);
+        self.save(&orders).await;
+
+        Ok(view)
+    }
+
+    pub async fn get_order(&self, order_id: &str) -> Result<Lsps1Order, LspsError> {
+        let orders = self.orders.lock().await;
+        orders
+            .iter()
+            .find(|o| o.order_id == order_id)
+            .map(OrderRecord::view)
+            .ok_or_else(|| LspsError::new(NOT_FOUND, "Not found"))
+    }
+
+    /// Paid orders that failed and still have to be refunded by hand.
+    pub async fn refunds(&self) -> Vec<Lsps1Refund> {
+        let orders = self.orders.lock().await;
+        orders
+            .iter()
+            .filter(|o| o.needs_refund())
+            .map(OrderRecord::refund)
+            .collect()
+    }
+
+    /// Records that the payment of a failed order was refunded by hand. `None` when no order with
+    /// this id awaits a refund.
+    pub async fn mark_refunded(&self, order_id: &str) -> Option<Lsps1Order> {
+        let mut orders = self.orders.lock().await;
+        let order = orders
+            .iter_mut()
+            .find(|o| o.order_id == order_id && o.needs_refund())?;
+        if order.onchain_state == PaymentState::Paid {
+            order.onchain_state = PaymentState::Refunded;
+        } else {
+            order.bolt11_state = PaymentState::Refunded;
+        }
+        tracing::info!(order_id = %order.order_id, "LSPS1 order marked refunded");
+
+        let view = order.view();
+        self.save(&orders).await;
+        Some(view)
+    }
+
+    async fn save(&self, orders: &[OrderRecord]) {
+        let result = match serde_json::to_vec(orders) {
+            Ok(bytes) => utils::write_file_atomic(&self.store_path, &bytes).await,
+            Err(e) => Err(e.into()),
+        };
+
+        if let Err(e) = result {
+            tracing::error!(
+                "Could not persist LSPS1 orders {:?}: {}",
+                self.store_path,
+                e
+            );
+        }
+    }
+
+    async fn update(&self, record: OrderRecord) {
+        let mut orders = self.orders.lock().await;
+        if let Some(existing) = orders.iter_mut().find(|o| o.order_id == record.order_id) {
+            *existing = record;
+        }
+        self.save(&orders).await;
+    }
+
+    /// Checks the payments of open orders, funds the paid ones and fails the expired ones.
+    async fn process(&self, ctx: &Context) {
+        self.prune().await;
+
+        let pending: Vec<OrderRecord> = {
+            let orders = self.orders.lock().await;
+            orders
+                .iter()
+                .filter(|o| o.state == OrderState::Created)
+                .cloned()
+                .collect()
+        };
+        if pending.is_empty() {
+            return;
+        }
+
+        let onchain_received = self.onchain_received(ctx, &pending).await;
+        let settled = std::mem::take(&mut *self.settled.lock().unwrap_or_else(|e| e.into_inner()));
+        let now = utils::unix_time_ms();
+        let poll_invoices = now.saturating_sub(self.last_invoice_poll_ms.load(Ordering::Relaxed))
+            >= INVOICE_POLL_INTERVAL_MS;
+        if poll_invoices {
+            self.last_invoice_poll_ms.store(now, Ordering::Relaxed);
+        }
+
+        for mut order in pending {
+            let before = order.clone();
+            let now = utils::unix_time_ms();
+
+            if !order.is_paid() {
+                let settled = settled.contains(&order.invoice_label);
+                self.check_payment(
+                    ctx,
+                    &mut order,
+                    settled,
+                    poll_invoices,
+                    &onchain_received,
+                    now,
+                )
+                .await;
+            }
+
+            if !order.is_paid() {
+                if now > order.expires_at_ms {
+                    order.state = OrderState::Failed;
+                    order.error = Some("order expired unpaid".to_string());
+                    tracing::info!(order_id = %order.order_id, "LSPS1 order expired unpaid");
+                }
+            } else {
+                self.fund(ctx, &mut order, now).await;
+            }
+
+            if order != before {
+                self.update(order).await;
+            }
+        }
+    }
+
+    /// Drops orders that expired unpaid more than `UNPAID_RETENTION_MS` ago.
+    async fn prune(&self) {
+        let cutoff = utils::unix_time_ms().saturating_sub(UNPAID_RETENTION_MS);
+        let mut orders = self.orders.lock().await;
+        let before = orders.len();
+        orders.retain(|o| o.is_paid() || o.expires_at_ms > cutoff);
+        if orders.len() != before {
+            tracing::info!("LSPS1: pruned {} expired orders", before - orders.len());
+            self.save(&orders).await;
+        }
+    }
+
+    /// Confirmed amount received per order address, in satoshis.
+    async fn onchain_received(&self, ctx: &Context, pending: &[OrderRecord]) -> Vec<(String, u64)> {
+        let addresses: Vec<&String> = pending
+            .iter()
+            .filter(|o| !o.is_paid())
+            .filter_map(|o| o.onchain_address.as_ref())
+            .collect();
+        if addresses.is_empty() {
+            return Vec::new();
+        }
+
+        let funds = match ctx.cln_client.lock().await.listfunds().await {
+            Ok(funds) => funds,
+            Err(e) => {
+                tracing::warn!("LSPS1: could not list funds: {}", e);
+                return Vec::new();
+            }
+        };
+
+        addresses
+            .into_iter()
+            .map(|address| {
+                let sat = funds
+                    .outputs
+                    .iter()
+                    .filter(|o| matches!(o.status, ListfundsOutputsStatus::CONFIRMED))
+                    .filter(|o| o.address.as_ref() == Some(address))
+                    .map(|o| o.amount_msat.msat() / 1_000)
+                    .sum();
+                (address.clone(), sat)
+            })
+            .collect()
+    }
+
+    /// Marks the order paid when its invoice `settled`, or, when `poll_invoice`, CLN lists it as
+    /// paid, or its on-chain address received the order total.
+    async fn check_payment(
+        &self,
+        ctx: &Context,
+        order: &mut OrderRecord,
+        settled: bool,
+        poll_invoice: bool,
+        onchain_received: &[(String, u64)],
+        now: u64,
+    ) {
+        let paid = settled || (poll_invoice && self.invoice_paid(ctx, order).await);
+        if paid {
+            order.bolt11_state = PaymentState::Paid;
+            order.paid_at_ms = Some(now);
+            tracing::info!(order_id = %order.order_id, "LSPS1 order paid over Lightning");
+            return;
+        }
+
+        let received = order.onchain_address.as_ref().and_then(|address| {
+            onchain_received
+                .iter()
+                .find(|(a, _)| a == address)
+                .map(|(_, sat)| *sat)
+        });
+        if let Some(sat) = received
+            && sat >= order.order_total_sat
+        {
+            order.onchain_state = PaymentState::Paid;
+            order.paid_at_ms = Some(now);
+            tracing::info!(order_id = %order.order_id, "LSPS1 order paid on-chain ({} sat)", sat);
+        }
+    }
+
+    async fn invoice_paid(&self, ctx: &Context, order: &OrderRecord) -> bool {
+        let invoice = ctx
+            .cln_client
+            .lock()
+            .await
+            .listinvoices(Some(order.invoice_label.clone()), None, None)
+            .await;
+        match invoice {
+            Ok(res) => res
+                .invoices
+                .iter()
+                .any(|i| matches!(i.status, ListinvoicesInvoicesStatus::PAID)),
+            Err(e) => {
+                tracing::warn!(
+                    "LSPS1: could not check invoice of order {}: {}",
+                    order.order_id,
+                    e
+                );
+                false
+            }
+        }
+    }
+
+    /// Funds the channel of a paid order. Failures are retried on the next run until
+    /// `FUNDING_RETRY_MS` after payment.
+    async fn fund(&self, ctx: &Context, order: &mut OrderRecord, now: u64) {
+        let result = match self.reconcile_funding(ctx, order).await {
+            Ok(Some(channel)) => {
+                tracing::info!(
+                    order_id = %order.order_id,
+                    "LSPS1 found the channel of an interrupted funding"
+                );
+                Ok(channel)
+            }
+            Ok(None) => self.open_channel(ctx, order).await,
+            Err(e) => {
+                tracing::warn!(
+                    order_id = %order.order_id,
+                    "LSPS1 could not reconcile an interrupted funding: {}",
+                    e
+                );
+                return;
+            }
+        };
+
+        match result {
+            Ok(channel) => {
+                tracing::info!(
+                    order_id = %order.order_id,
+                    "LSPS1 channel {} funded in {}",
+                    channel.channel_id,
+                    channel.txid
+                );
+                ctx.webhooks
+                    .enqueue(WebhookEvent::Lsps1OrderCompleted {
+                        order_id: order.order_id.clone(),
+                        remote_id: order.client_node_id.clone(),
+                        channel_id: channel.channel_id.clone(),
+                        txid: channel.txid.clone(),
+                        lsp_balance_sat: order.lsp_balance_sat,
+                        client_balance_sat: order.client_balance_sat,
+                    })
+                    .await;
+                order.channel = Some(channel);
+                order.state = OrderState::Completed;
+                order.error = None;
+            }
+            Err(e) => {
+                let paid_at = order.paid_at_ms.unwrap_or(now);
+                if now.saturating_sub(paid_at) > FUNDING_RETRY_MS {
+                    tracing::error!(
+                        order_id = %order.order_id,
+                        "LSPS1 order failed, {} sat have to be refunded by hand (see GET /lsps1/refunds): {}",
+                        order.order_total_sat,
+                        e
+                    );
+                    order.state = OrderState::Failed;
+                    ctx.webhooks
+                        .enqueue(WebhookEvent::Lsps1OrderFailed {
+                            order_id: order.order_id.clone(),
+                            reason: e.clone(),
+                            refund_sat: order.order_total_sat,
+                        })
+                        .await;
+                } else {
+                    tracing::warn!(
+                        order_id = %order.order_id,
+                        "LSPS1 funding failed, retrying: {}",
+                        e
+                    );
+                }
+                order.error = Some(e);
+            }
+        }
+    }
+
+    /// Looks for the channel of a funding that was interrupted: a channel we opened to the
+    /// client with the order's capacity, not yet claimed by another order. Clears the marker when
+    /// there is none, so that the order is funded again.
+    async fn reconcile_funding(
+        &self,
+        ctx: &Context,
+        order: &mut OrderRecord,
+    ) -> Result<Option<ChannelRecord>, String> {
+        let Some(started_at) = order.funding_started_at_ms else {
+            return Ok(None);
+        };
+
+        let id = PublicKey::from_str(&order.client_node_id).map_err(|e| e.to_string())?;
+        let amount_msat = (order.lsp_balance_sat + order.client_balance_sat).saturating_mul(1_000);
+        let channels = ctx
+            .cln_client
+            .lock()
+            .await
+            .listpeerchannels(Some(id))
+            .await
+            .map_err(|e| e.to_string())?
+            .channels;
+
+        let claimed: Vec<String> = self
+            .orders
+            .lock()
+            .await
+            .iter()
+            .filter_map(|o| o.channel.as_ref().map(|c| c.txid.clone()))
+            .collect();
+
+        let channel = channels.into_iter().find(|c| {
+            matches!(c.opener, ChannelSide::LOCAL)
+                && c.total_msat.is_none_or(|total| total.msat() == amount_msat)
+                && c.funding_txid
+                    .as_ref()
+                    .is_some_and(|txid| !claimed.contains(txid))
+        });
+
+        order.funding_started_at_ms = None;
+        Ok(channel.map(|c| ChannelRecord {
+            funded_at_ms: started_at,
+            channel_id: c.channel_id.map(|id| id.to_string()).unwrap_or_default(),
+            txid: c.funding_txid.unwrap_or_default(),
+            outnum: c.funding_outnum.unwrap_or_default(),
+        }))
+    }
+
+    /// Connects and funds over a dedicated connection, so that the shared one isn't held while
+    /// the peer negotiates. The order is persisted with its funding marker before `fundchannel`.
+    async fn open_channel(
+        &self,
+        ctx: &Context,
+        order: &mut OrderRecord,
+    ) -> Result<ChannelRecord, String> {
+        let id = PublicKey::from_str(&order.client_node_id).map_err(|e| e.to_string())?;
+        let amount_sat = order.lsp_balance_sat + order.client_balance_sat;
+        let amount_msat = amount_sat.saturating_mul(1_000);
+        let push_msat =
+            (order.client_balance_sat > 0).then(|| order.client_balance_sat.saturating_mul(1_000));
+
+        let mut rpc = ctx
+            .dedicated_cln_client()
+            .await
+            .map_err(|e| format!("could not connect to CLN: {e}"))?;
+        rpc.ensure_connected(id)
+            .await
+            .map_err(|e| format!("could not connect to {}: {}", id, e))?;
+
+        let runtime = ctx.runtime.load_full();
+        let reservation = ctx
+            .spending
+            .reserve(
+                &runtime.spending_policy.limits,
+                SpendFlow::Channel,
+                amount_msat,
+            )
+            .await?;
+
+        order.funding_started_at_ms = Some(utils::unix_time_ms());
+        self.update(order.clone()).await;
+
+        let result = rpc
+            .fundchannel(
+                id,
+                amount_sat,
+                Some(order.announce_channel),
+                push_msat,
+                Some(u32::from(order.required_channel_confirmations)),
+            )
+            .await;
+
+        match result {
+            Ok(res) => {
+                ctx.spending.commit(reservation, amount_msat).await;
+                order.funding_started_at_ms = None;
+                Ok(ChannelRecord {
+                    funded_at_ms: utils::unix_time_ms(),
+                    channel_id: res.channel_id.to_string(),
+                    txid: res.txid,
+                    outnum: res.outnum,
+                })
+            }
+            Err(e) if is_definitive_fundchannel_failure(&e) => {
+                ctx.spending.release(reservation).await;
+                order.funding_started_at_ms = None;
+                Err(e.to_string())
+            }
+            Err(e) => {
+                // The channel may exist: count it as spent and keep the marker, the next run
+                // reconciles before funding again.
+                ctx.spending.commit(reservation, amount_msat).await;
+                Err(format!("funding outcome unknown: {e}"))
+            }
+        }
+    }
+}
+
+/// Spawns the worker that settles LSPS1 orders. It runs every `WORKER_INTERVAL`, and right away
+/// when an order invoice is paid. The returned task ends after the run in progress once shutdown
+/// is triggered.
+pub fn spawn_worker(ctx: Arc<Context>) -> Option<JoinHandle<()>> {
+    if !ctx.args.lsps1_enabled {
+        return None;
+    }
+
+    let mut invoices = ctx.invoice_events.subscribe();
+    let waker = ctx.clone();
+    tokio::spawn(async move {
+        loop {
+            match invoices.recv().await {
+                Ok(event) if event.label.starts_with(INVOICE_LABEL_PREFIX) => {
+                    waker
+                        .lsps1
+                        .settled
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .insert(event.label);
+                    waker.lsps1.wake.notify_one();
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+
+    Some(tokio::spawn(async move {
+        let shutdown = ctx.shutdown.wait();
+        tokio::pin!(shutdown);
+
+        loop {
+            ctx.lsps1.process(&ctx).await;
+
+            tokio::select! {
+                _ = &mut shutdown => break,
+                _ = tokio::time::sleep(WORKER_INTERVAL) => {}
+                _ = ctx.lsps1.wake.notified() => {}
+            }
+        }
+    }))
+}
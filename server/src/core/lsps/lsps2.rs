@@ -0,0 +1,1222 @@
+//! LSPS2 (bLIP-52): just-in-time channels. A client buys an intercept SCID to put in the route
+//! hint of its invoice. CLN hands HTLCs to that SCID to `POST /lsps2/htlc` through its
+//! `htlc_accepted` hook (see `deploy/cln/lsps2_hook.py`); the node then opens a zero-conf channel
+//! to the client and forwards the payment minus the opening fee.
+
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail};
+use cln_rpc::primitives::{ChannelState, PublicKey};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Deserializer, Serialize};
+use sha2::Sha256;
+use tokio::sync::{Mutex, oneshot};
+use tokio::time::Instant;
+
+use crate::context::Context;
+use crate::core::api_key::{self, scope::Scope};
+use crate::core::cli::Args;
+use crate::core::lightning_rpc_connector::is_definitive_fundchannel_failure;
+use crate::core::lsps::{LspsError, amount_string};
+use crate::core::spending_limits::SpendFlow;
+use crate::core::utils;
+use crate::core::webhook::event::WebhookEvent;
+
+const STORE_FILE: &str = "lsps2_buys.json";
+/// How long the parts of a multi-part payment are held, counted from the first one.
+const MPP_TIMEOUT: Duration = Duration::from_secs(90);
+const CHANNEL_READY_TIMEOUT: Duration = Duration::from_secs(60);
+const CHANNEL_READY_POLL: Duration = Duration::from_secs(1);
+
+/// `buy` with fee parameters that weren't issued by this node, or have expired.
+const INVALID_OPENING_FEE_PARAMS: i32 = 201;
+const PAYMENT_SIZE_TOO_SMALL: i32 = 202;
+const PAYMENT_SIZE_TOO_LARGE: i32 = 203;
+
+/// BOLT4 failure codes returned to the payer.
+const UNKNOWN_NEXT_PEER: &str = "400a";
+const TEMPORARY_NODE_FAILURE: &str = "2002";
+
+/// Entry of the `lsps2.get_info` fee menu. `promise` signs the other fields, so `buy` can
+/// check them without keeping the menus around.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct OpeningFeeParams {
+    #[serde(with = "amount_string")]
+    #[schema(value_type = String)]
+    pub min_fee_msat: u64,
+    /// Parts per million of the payment.
+    pub proportional: u32,
+    /// RFC 3339 timestamp.
+    pub valid_until: String,
+    pub min_lifetime: u32,
+    pub max_client_to_self_delay: u32,
+    #[serde(with = "amount_string")]
+    #[schema(value_type = String)]
+    pub min_payment_size_msat: u64,
+    #[serde(with = "amount_string")]
+    #[schema(value_type = String)]
+    pub max_payment_size_msat: u64,
+    pub promise: String,
+}
+
+/// Params of `lsps2.get_info`.
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct GetInfoParams {
+    /// Accepted and ignored: every client gets the same menu.
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+/// Result of `lsps2.get_info`.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct Lsps2Info {
+    pub opening_fee_params_menu: Vec<OpeningFeeParams>,
+}
+
+/// Params of `lsps2.buy`.
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct BuyParams {
+    /// Node id of the client. Over BOLT8 this comes from the transport; over HTTP it is required.
+    pub public_key: String,
+    pub opening_fee_params: OpeningFeeParams,
+    /// Set for "MPP mode"; without it the first HTLC is the whole payment.
+    #[serde(default, with = "amount_string::option")]
+    #[schema(value_type = Option<String>)]
+    pub payment_size_msat: Option<u64>,
+}
+
+/// Result of `lsps2.buy`.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct BuyResult {
+    /// SCID to use in the route hint of the invoice, as `<block>x<tx>x<output>`.
+    pub jit_channel_scid: String,
+    pub lsp_cltv_expiry_delta: u32,
+    pub client_trusts_lsp: bool,
+}
+
+/// Payload of CLN's `htlc_accepted` hook; only the fields used here.
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct HtlcAccepted {
+    pub onion: HtlcOnion,
+    #[serde(default)]
+    pub htlc: HtlcInfo,
+}
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct HtlcOnion {
+    /// Hex TLV payload of this hop.
+    pub payload: String,
+    /// Next channel; absent when this node is the final hop.
+    pub short_channel_id: Option<String>,
+    /// Amount to forward, as a number or an `<n>msat` string (older CLN).
+    #[serde(default, deserialize_with = "msat_opt")]
+    #[schema(value_type = Option<u64>)]
+    pub forward_msat: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize, utoipa::ToSchema)]
+pub struct HtlcInfo {
+    pub payment_hash: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HtlcResult {
+    Continue,
+    Fail,
+}
+
+/// Answer to the `htlc_accepted` hook, returned as is by the plugin.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct HtlcResolution {
+    pub result: HtlcResult,
+    /// Replacement hop payload, with `amt_to_forward` reduced by the opening fee.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload: Option<String>,
+    /// Channel id the HTLC is forwarded to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub forward_to: Option<String>,
+    /// Hex BOLT4 failure message.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure_message: Option<String>,
+}
+
+impl HtlcResolution {
+    pub fn resume() -> Self {
+        Self {
+            result: HtlcResult::Continue,
+            payload: None,
+            forward_to: None,
+            failure_message: None,
+        }
+    }
+
+    fn forward(channel_id: &str, payload: Option<String>) -> Self {
+        Self {
+            payload,
+            forward_to: Some(channel_id.to_string()),
+            ..Self::resume()
+        }
+    }
+
+    fn fail(code: &str) -> Self {
+        Self {
+            result: HtlcResult::Fail,
+            failure_message: Some(code.to_string()),
+            ..Self::resume()
+        }
+    }
+}
+
+fn msat_opt<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u64>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Msat {
+        Number(u64),
+        String(String),
+    }
+
+    match Option::<Msat>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(Msat::Number(msat)) => Ok(Some(msat)),
+        Some(Msat::String(s)) => s
+            .trim_end_matches("msat")
+            .parse()
+            .map(Some)
+            .map_err(|_| serde::de::Error::custom(format!("invalid msat amount: {s}"))),
+    }
+}
+
+/// Fee menu settings and the promise key; `None` from `from_args` when LSPS2 is disabled.
+#[derive(Debug, Clone)]
+pub struct Lsps2Policy {
+    secret: String,
+    min_fee_msat: u64,
+    fee_ppm: u32,
+    min_payment_msat: u64,
+    max_payment_msat: u64,
+    fee_valid_secs: u64,
+    min_lifetime: u32,
+    max_client_to_self_delay: u32,
+    cltv_expiry_delta: u32,
+}
+
+impl Lsps2Policy {
+    pub fn from_args(args: &Args) -> anyhow::Result<Option<Self>> {
+        if !args.lsps2_enabled {
+            return Ok(None);
+        }
+
+        let secret = args
+            .lsps2_promise_secret
+            .as_deref()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| anyhow!("SERVER_LSPS2_PROMISE_SECRET is required with LSPS2"))?;
+        if secret.len() < 16 {
+            bail!("SERVER_LSPS2_PROMISE_SECRET must be at least 16 characters");
+        }
+        if args.lsps2_min_payment_msat > args.lsps2_max_payment_msat {
+            bail!(
+                "SERVER_LSPS2_MIN_PAYMENT_MSAT ({}) is above SERVER_LSPS2_MAX_PAYMENT_MSAT ({})",
+                args.lsps2_min_payment_msat,
+                args.lsps2_max_payment_msat
+            );
+        }
+        if args.lsps2_min_fee_msat >= args.lsps2_min_payment_msat {
+            bail!(
+                "SERVER_LSPS2_MIN_FEE_MSAT ({}) must be below SERVER_LSPS2_MIN_PAYMENT_MSAT ({})",
+                args.lsps2_min_fee_msat,
+                args.lsps2_min_payment_msat
+            );
+        }
+        if args.lsps2_fee_valid_secs == 0 {
+            bail!("SERVER_LSPS2_FEE_VALID_SECS must be positive");
+        }
+        // The hook decides which HTLCs get forwarded, so it must never be reachable unauthenticated.
+        if !api_key::any_key_allows(args, Scope::Lsps2Hook) {
+            bail!("LSPS2 needs an API key with the lsps2:hook scope in SERVER_API_KEYS");
+        }
+
+        Ok(Some(Self {
+            secret: secret.to_string(),
+            min_fee_msat: args.lsps2_min_fee_msat,
+            fee_ppm: args.lsps2_fee_ppm,
+            min_payment_msat: args.lsps2_min_payment_msat,
+            max_payment_msat: args.lsps2_max_payment_msat,
+            fee_valid_secs: args.lsps2_fee_valid_secs,
+            min_lifetime: args.lsps2_min_lifetime,
+            max_client_to_self_delay: args.lsps2_max_client_to_self_delay,
+            cltv_expiry_delta: args.lsps2_cltv_expiry_delta,
+        }))
+    }
+
+    fn mac(&self, params: &OpeningFeeParams) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.secret.as_bytes())
+            .expect("HMAC accepts any key length");
+        mac.update(
+            format!(
+                "{}|{}|{}|{}|{}|{}|{}",
+                params.min_fee_msat,
+                params.proportional,
+                params.valid_until,
+                params.min_lifetime,
+                params.max_client_to_self_delay,
+                params.min_payment_size_msat,
+                params.max_payment_size_msat
+            )
+            .as_bytes(),
+        );
+        mac
+    }
+
+    fn fee_params(&self, now_ms: u64) -> OpeningFeeParams {
+        let mut params = OpeningFeeParams {
+            min_fee_msat: self.min_fee_msat,
+            proportional: self.fee_ppm,
+            valid_until: utils::rfc3339(
+                now_ms.saturating_add(self.fee_valid_secs.saturating_mul(1_000)),
+            ),
+            min_lifetime: self.min_lifetime,
+            max_client_to_self_delay: self.max_client_to_self_delay,
+            min_payment_size_msat: self.min_payment_msat,
+            max_payment_size_msat: self.max_payment_msat,
+            promise: String::new(),
+        };
+        params.promise = hex::encode(self.mac(&params).finalize().into_bytes());
+        params
+    }
+
+    /// Checks the promise and the expiry. `rfc3339` has a fixed width, so timestamps compare
+    /// as strings.
+    fn verify(&self, params: &OpeningFeeParams, now_ms: u64) -> Result<(), LspsError> {
+        let promise = hex::decode(&params.promise).unwrap_or_default();
+        if self.mac(params).verify_slice(&promise).is_err() {
+            return Err(LspsError::new(
+                INVALID_OPENING_FEE_PARAMS,
+                "opening_fee_params were not issued by this LSP",
+            ));
+        }
+        if params.valid_until <= utils::rfc3339(now_ms) {
+            return Err(LspsError::new(
+                INVALID_OPENING_FEE_PARAMS,
+                "opening_fee_params have expired",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// `max(min_fee_msat, ceil(payment * proportional / 1e6))`, `None` on overflow.
+pub fn opening_fee_msat(params: &OpeningFeeParams, payment_msat: u64) -> Option<u64> {
+    let proportional =
+        (u128::from(payment_msat) * u128::from(params.proportional)).div_ceil(1_000_000);
+    u64::try_from(proportional)
+        .ok()
+        .map(|fee| fee.max(params.min_fee_msat))
+}
+
+/// Size bounds of the fee menu, and an opening fee below the payment.
+fn check_payment_size(params: &OpeningFeeParams, payment_msat: u64) -> Result<u64, LspsError> {
+    if payment_msat < params.min_payment_size_msat {
+        return Err(LspsError::new(
+            PAYMENT_SIZE_TOO_SMALL,
+            format!(
+                "payment must be at least {} msat",
+                params.min_payment_size_msat
+            ),
+        ));
+    }
+    if payment_msat > params.max_payment_size_msat {
+        return Err(LspsError::new(
+            PAYMENT_SIZE_TOO_LARGE,
+            format!(
+                "payment must be at most {} msat",
+                params.max_payment_size_msat
+            ),
+        ));
+    }
+
+    match opening_fee_msat(params, payment_msat) {
+        Some(fee) if fee < payment_msat => Ok(fee),
+        Some(fee) => Err(LspsError::new(
+            PAYMENT_SIZE_TOO_SMALL,
+            format!("the opening fee of {fee} msat exceeds the payment"),
+        )),
+        None => Err(LspsError::new(
+            PAYMENT_SIZE_TOO_LARGE,
+            "opening fee overflow",
+        )),
+    }
+}
+
+/// Splits `fee` over the parts in proportion to their amounts; `None` if a part can't cover
+/// its share.
+pub fn split_fee(fee: u64, amounts: &[u64]) -> Option<Vec<u64>> {
+    let total: u128 = amounts.iter().map(|a| u128::from(*a)).sum();
+    if total == 0 {
+        return None;
+    }
+
+    let mut shares: Vec<u64> = amounts
+        .iter()
+        .map(|a| (u128::from(fee) * u128::from(*a) / total) as u64)
+        .collect();
+    let rest = fee - shares.iter().sum::<u64>();
+    shares[0] += rest;
+
+    shares
+        .iter()
+        .zip(amounts)
+        .all(|(share, amount)| share < amount)
+        .then_some(shares)
+}
+
+fn read_bigsize(bytes: &[u8]) -> Option<(u64, usize)> {
+    let (&first, rest) = bytes.split_first()?;
+    let (len, min) = match first {
+        0xfd => (2, 0xfd),
+        0xfe => (4, 0x1_0000),
+        0xff => (8, 0x1_0000_0000),
+        n => return Some((u64::from(n), 1)),
+    };
+    let value = rest
+        .get(..len)?
+        .iter()
+        .fold(0u64, |acc, b| (acc << 8) | u64::from(*b));
+
+    // Non-minimal encodings are invalid.
+    (value >= min).then_some((value, 1 + len))
+}
+
+fn write_bigsize(value: u64, out: &mut Vec<u8>) {
+    match value {
+        0..=0xfc => out.push(value as u8),
+        0xfd..=0xffff => {
+            out.push(0xfd);
+            out.extend_from_slice(&(value as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(0xfe);
+            out.extend_from_slice(&(value as u32).to_be_bytes());
+        }
+        _ => {
+            out.push(0xff);
+            out.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+}
+
+fn parse_tlv_stream(mut bytes: &[u8]) -> Option<Vec<(u64, Vec<u8>)>> {
+    let mut records = Vec::new();
+    while !bytes.is_empty() {
+        let (kind, n) = read_bigsize(bytes)?;
+        bytes = &bytes[n..];
+        let (len, n) = read_bigsize(bytes)?;
+        bytes = &bytes[n..];
+        let len = usize::try_from(len).ok()?;
+        records.push((kind, bytes.get(..len)?.to_vec()));
+        bytes = &bytes[len..];
+    }
+
+    Some(records)
+}
+
+/// Replaces `amt_to_forward` (TLV type 2) in a hop payload. CLN versions differ in whether
+/// the payload carries its length prefix, so the result keeps the form of the input.
+pub fn rewrite_amt_to_forward(payload_hex: &str, amount_msat: u64) -> Option<String> {
+    let bytes = hex::decode(payload_hex).ok()?;
+
+    let prefixed = read_bigsize(&bytes).and_then(|(len, n)| {
+        let records = (usize::try_from(len).ok()? == bytes.len() - n)
+            .then(|| parse_tlv_stream(&bytes[n..]))??;
+        Some(records)
+    });
+    let (mut records, has_prefix) = match prefixed {
+        Some(records) => (records, true),
+        None => (parse_tlv_stream(&bytes)?, false),
+    };
+
+    let amount = amount_msat.to_be_bytes();
+    let leading_zeros = amount.iter().take_while(|b| **b == 0).count();
+    let record = records.iter_mut().find(|(kind, _)| *kind == 2)?;
+    record.1 = amount[leading_zeros..].to_vec();
+
+    let mut stream = Vec::new();
+    for (kind, value) in &records {
+        write_bigsize(*kind, &mut stream);
+        write_bigsize(value.len() as u64, &mut stream);
+        stream.extend_from_slice(value);
+    }
+
+    if !has_prefix {
+        return Some(hex::encode(stream));
+    }
+    let mut out = Vec::with_capacity(stream.len() + 3);
+    write_bigsize(stream.len() as u64, &mut out);
+    out.extend_from_slice(&stream);
+    Some(hex::encode(out))
+}
+
+/// Random intercept SCID in the current block, so it never matches a real channel of the node.
+fn intercept_scid(blockheight: u32) -> String {
+    let random = uuid::Uuid::new_v4();
+    let bytes = random.as_bytes();
+    let tx = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+    let output = u16::from_be_bytes([bytes[3], bytes[4]]);
+    format!("{blockheight}x{tx}x{output}")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum BuyState {
+    /// Waiting for the payment.
+    Pending,
+    /// The channel is open; later HTLCs are forwarded to it without a fee.
+    Completed,
+    /// The channel could not be opened; HTLCs are failed.
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct BuyRecord {
+    scid: String,
+    client_node_id: String,
+    params: OpeningFeeParams,
+    payment_size_msat: Option<u64>,
+    created_at_ms: u64,
+    state: BuyState,
+    channel_id: Option<String>,
+    txid: Option<String>,
+    opening_fee_msat: Option<u64>,
+    error: Option<String>,
+}
+
+struct Part {
+    forward_msat: u64,
+    payload: String,
+    reply: oneshot::Sender<HtlcResolution>,
+}
+
+/// HTLCs of one payment (SCID and payment hash) held until they add up to the payment.
+struct PartSet {
+    parts: Vec<Part>,
+    deadline: Instant,
+    opening: bool,
+}
+
+/// Buys, persisted in the data directory, and the HTLCs held for them.
+pub struct Lsps2Service {
+    policy: Option<Lsps2Policy>,
+    store_path: PathBuf,
+    buys: Mutex<Vec<BuyRecord>>,
+    held: Mutex<HashMap<(String, String), PartSet>>,
+}
+
+/// The node calls LSPS2 makes. `Context` implements them over CLN; tests use a fake node.
+pub trait Lsps2Node {
+    fn blockheight(&self) -> impl Future<Output = anyhow::Result<u32>> + Send;
+
+    /// Connects to the peer unless it is already connected.
+    fn connect(&self, id: PublicKey) -> impl Future<Output = anyhow::Result<()>> + Send;
+
+    /// Funds an unannounced zero-conf channel; returns its channel id and funding txid.
+    fn fund_zero_conf(
+        &self,
+        id: PublicKey,
+        amount_sat: u64,
+    ) -> impl Future<Output = anyhow::Result<(String, String)>> + Send;
+
+    /// Whether the channel is in `CHANNELD_NORMAL`, i.e. can carry HTLCs.
+    fn channel_ready(
+        &self,
+        id: PublicKey,
+        channel_id: &str,
+    ) -> impl Future<Output = anyhow::Result<bool>> + Send;
+}
+
+/// Connecting and funding go over a dedicated connection: the peer can take a while to answer,
+/// and the shared one is needed to forward other payments meanwhile.
+impl Lsps2Node for Context {
+    async fn blockheight(&self) -> anyhow::Result<u32> {
+        Ok(self.cln_client.lock().await.getinfo().await?.blockheight)
+    }
+
+    async fn connect(&self, id: PublicKey) -> anyhow::Result<()> {
+        self.dedicated_cln_client()
+            .await?
+            .ensure_connected(id)
+            .await
+    }
+
+    async fn fund_zero_conf(
+        &self,
+        id: PublicKey,
+        amount_sat: u64,
+    ) -> anyhow::Result<(String, String)> {
+        let res = self
+            .dedicated_cln_client()
+            .await?
+            .fundchannel(id, amount_sat, Some(false), None, Some(0))
+            .await?;
+        Ok((res.channel_id.to_string(), res.txid))
+    }
+
+    async fn channel_ready(&self, id: PublicKey, channel_id: &str) -> anyhow::Result<bool> {
+        let res = self
+            .cln_client
+            .lock()
+            .await
+            .listpeerchannels(Some(id))
+            .await?;
+        Ok(res.channels.iter().any(|c| {
+            c.state == ChannelState::CHANNELD_NORMAL
+                && c.channel_id.is_some_and(|c| c.to_string() == channel_id)
+        }))
+    }
+}
+
+impl Lsps2Service {
+    pub fn new(args: &Args) -> anyhow::Result<Self> {
+        let policy = Lsps2Policy::from_args(args)?;
+
+        let store_path = args.data_dir.join(STORE_FILE);
+        let buys = match std::fs::read(&store_path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring unreadable LSPS2 buys {:?}: {}", store_path, e);
+                Vec::new()
+            }),
+            Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(e) => {
+                tracing::warn!("Could not read LSPS2 buys {:?}: {}", store_path, e);
+                Vec::new()
+            }
+        };
+
+        Ok(Self {
+            policy,
+            store_path,
+            buys: Mutex::new(buys),
+            held: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.policy.is_some()
+    }
+
+    fn policy(&self) -> Result<&Lsps2Policy, LspsError> {
+        self.policy
+            .as_ref()
+            .ok_or_else(|| LspsError::internal("LSPS2 is disabled"))
+    }
+
+    pub fn get_info(&self) -> Result<Lsps2Info, LspsError> {
+        let policy = self.policy()?;
+        Ok(Lsps2Info {
+            opening_fee_params_menu: vec![policy.fee_params(utils::unix_time_ms())],
+        })
+    }
+
+    pub async fn buy(
+        &self,
+        node: &impl Lsps2Node,
+        params: BuyParams,
+    ) -> Result<BuyResult, LspsError> {
+        let policy = self.policy()?;
+        let client_node_id = PublicKey::from_str(params.public_key.trim())
+            .map_err(|e| LspsError::invalid_params("public_key", e.to_string()))?;
+
+        policy.verify(&params.opening_fee_params, utils::unix_time_ms())?;
+        if let Some(size) = params.payment_size_msat {
+            check_payment_size(&params.opening_fee_params, size)?;
+        }
+
+        let blockheight = node
+            .blockheight()
+            .await
+            .map_err(|e| LspsError::internal(format!("could not get block height: {e}")))?;
+
+        let mut buys = self.buys.lock().await;
+        let scid = loop {
+            let scid = intercept_scid(blockheight);
+            if !buys.iter().any(|b| b.scid == scid) {
+                break scid;
+            }
+        };
+
+        tracing::info!(
+            scid = %scid,
+            client = %client_node_id,
+            "LSPS2 buy, payment size {}",
+            params
+                .payment_size_msat
+                .map_or("unset".to_string(), |size| format!("{size} msat"))
+        );
+
+        buys.push(BuyRecord {
+            scid: scid.clone(),
+            client_node_id: client_node_id.to_string(),
+            params: params.opening_fee_params,
+            payment_size_msat: params.payment_size_msat,
+            created_at_ms: utils::unix_time_ms(),
+            state: BuyState::Pending,
+            channel_id: None,
+            txid: None,
+            opening_fee_msat: None,
+            error: None,
+        });
+        self.save(&buys).await;
+
+        Ok(BuyResult {
+            jit_channel_scid: scid,
+            lsp_cltv_expiry_delta: policy.cltv_expiry_delta,
+            client_trusts_lsp: false,
+        })
+    }
+
+    async fn save(&self, buys: &[BuyRecord]) {
+        let result = match serde_json::to_vec(buys) {
+            Ok(bytes) => utils::write_file_atomic(&self.store_path, &bytes).await,
+            Err(e) => Err(e.into()),
+        };
+
+        if let Err(e) = result {
+            tracing::error!("Could not persist LSPS2 buys {:?}: {}", self.store_path, e);
+        }
+    }
+
+    async fn update(&self, record: BuyRecord) {
+        let mut buys = self.buys.lock().await;
+        if let Some(existing) = buys.iter_mut().find(|b| b.scid == record.scid) {
+            *existing = record;
+        }
+        self.save(&buys).await;
+    }
+
+    async fn fail_buy(&self, mut buy: BuyRecord, error: String) {
+        tracing::warn!(scid = %buy.scid, "LSPS2 buy failed: {}", error);
+        buy.state = BuyState::Failed;
+        buy.error = Some(error);
+        self.update(buy).await;
+    }
+
+    /// Resolves an HTLC passed on by the `htlc_accepted` hook. HTLCs to other channels continue
+    /// untouched; HTLCs to an intercept SCID are held until the payment is complete.
+    pub async fn handle_htlc(&self, ctx: &Context, htlc: HtlcAccepted) -> HtlcResolution {
+        let Some(scid) = htlc.onion.short_channel_id.as_deref() else {
+            return HtlcResolution::resume();
+        };
+        let Some(buy) = self
+            .buys
+            .lock()
+            .await
+            .iter()
+            .find(|b| b.scid == scid)
+            .cloned()
+        else {
+            return HtlcResolution::resume();
+        };
+
+        match (buy.state, &buy.channel_id) {
+            (BuyState::Completed, Some(channel_id)) => {
+                return HtlcResolution::forward(channel_id, None);
+            }
+            (BuyState::Pending, _) => {}
+            _ => return HtlcResolution::fail(UNKNOWN_NEXT_PEER),
+        }
+
+        let Some(forward_msat) = htlc.onion.forward_msat else {
+            tracing::warn!(scid = %scid, "LSPS2 HTLC without forward amount");
+            return HtlcResolution::fail(TEMPORARY_NODE_FAILURE);
+        };
+
+        let payment_hash = htlc.htlc.payment_hash.unwrap_or_default();
+        tracing::info!(
+            scid = %scid,
+            payment_hash = %payment_hash,
+            "LSPS2 HTLC of {} msat",
+            forward_msat
+        );
+
+        let (reply, response) = oneshot::channel();
+        let part = Part {
+            forward_msat,
+            payload: htlc.onion.payload,
+            reply,
+        };
+
+        let key = (scid.to_string(), payment_hash);
+        let (ready, deadline) = {
+            let mut held = self.held.lock().await;
+            // One channel per buy: a second payment completing while the first opens the channel
+            // is failed, and forwarded once the payer retries.
+            let opening = held
+                .iter()
+                .any(|((s, hash), set)| s == scid && *hash != key.1 && set.opening);
+            let set = held.entry(key.clone()).or_insert_with(|| PartSet {
+                parts: Vec::new(),
+                deadline: Instant::now() + MPP_TIMEOUT,
+                opening: false,
+            });
+            if set.opening || opening {
+                return HtlcResolution::fail(TEMPORARY_NODE_FAILURE);
+            }
+            if set.parts.is_empty()
+                && buy.params.valid_until <= utils::rfc3339(utils::unix_time_ms())
+            {
+                held.remove(&key);
+                drop(held);
+                self.fail_buy(buy, "opening_fee_params expired before the payment".into())
+                    .await;
+                return HtlcResolution::fail(UNKNOWN_NEXT_PEER);
+            }
+
+            set.parts.push(part);
+            // Without a payment size, the first HTLC is the whole payment.
+            let target = buy.payment_size_msat.unwrap_or(forward_msat);
+            let received: u64 = set.parts.iter().map(|p| p.forward_msat).sum();
+            if received >= target {
+                set.opening = true;
+                (Some(std::mem::take(&mut set.parts)), set.deadline)
+            } else {
+                (None, set.deadline)
+            }
+        };
+
+        if let Some(parts) = ready {
+            self.open_and_forward(ctx, buy, parts).await;
+            self.held.lock().await.remove(&key);
+        }
+
+        let mut response = response;
+        let resolution = match tokio::time::timeout_at(deadline, &mut response).await {
+            Ok(resolution) => resolution,
+            Err(_) => {
+                // Still answered by the channel opening if it started in the meantime.
+                self.expire(&key).await;
+                response.await
+            }
+        };
+
+        resolution.unwrap_or_else(|_| HtlcResolution::fail(TEMPORARY_NODE_FAILURE))
+    }
+
+    /// Fails the held parts of an incomplete payment after `MPP_TIMEOUT`.
+    async fn expire(&self, key: &(String, String)) {
+        let mut held = self.held.lock().await;
+        if held.get(key).is_some_and(|set| !set.opening)
+            && let Some(set) = held.remove(key)
+        {
+            tracing::info!(
+                scid = %key.0,
+                payment_hash = %key.1,
+                "LSPS2 payment incomplete after {:?}",
+                MPP_TIMEOUT
+            );
+            for part in set.parts {
+                let _ = part
+                    .reply
+                    .send(HtlcResolution::fail(TEMPORARY_NODE_FAILURE));
+            }
+        }
+    }
+
+    /// Opens the channel for a complete payment and answers every held part.
+    async fn open_and_forward(&self, ctx: &Context, mut buy: BuyRecord, parts: Vec<Part>) {
+        let amounts: Vec<u64> = parts.iter().map(|p| p.forward_msat).collect();
+        let payment_msat = buy.payment_size_msat.unwrap_or(amounts[0]);
+
+        let checked = check_payment_size(&buy.params, payment_msat)
+            .map_err(|e| e.message)
+            .and_then(|fee| {
+                split_fee(fee, &amounts)
+                    .map(|shares| (fee, shares))
+                    .ok_or_else(|| "the HTLCs can't cover the opening fee".to_string())
+            })
+            .and_then(|(fee, shares)| {
+                parts
+                    .iter()
+                    .zip(shares)
+                    .map(|(part, share)| {
+                        rewrite_amt_to_forward(&part.payload, part.forward_msat - share)
+                    })
+                    .collect::<Option<Vec<String>>>()
+                    .map(|payloads| (fee, payloads))
+                    .ok_or_else(|| "unreadable onion payload".to_string())
+            });
+        let (fee, payloads) = match checked {
+            Ok(checked) => checked,
+            Err(e) => {
+                self.fail_buy(buy, e).await;
+                for part in parts {
+                    let _ = part.reply.send(HtlcResolution::fail(UNKNOWN_NEXT_PEER));
+                }
+                return;
+            }
+        };
+
+        let received: u64 = amounts.iter().sum();
+        match self.open_channel(ctx, &mut buy, payment_msat).await {
+            Ok(channel_id) => {
+                tracing::info!(
+                    scid = %buy.scid,
+                    "LSPS2 channel {} ready, forwarding {} msat minus {} msat fee",
+                    channel_id,
+                    received,
+                    fee
+                );
+                buy.state = BuyState::Completed;
+                buy.opening_fee_msat = Some(fee);
+                buy.error = None;
+                self.update(buy.clone()).await;
+
+                for (part, payload) in parts.into_iter().zip(payloads) {
+                    let _ = part
+                        .reply
+                        .send(HtlcResolution::forward(&channel_id, Some(payload)));
+                }
+                // Parts of other payments to the SCID go to the channel as they are, like the
+                // HTLCs that arrive from now on.
+                let others: Vec<PartSet> = {
+                    let mut held = self.held.lock().await;
+                    let keys: Vec<(String, String)> = held
+                        .iter()
+                        .filter(|((scid, _), set)| *scid == buy.scid && !set.opening)
+                        .map(|(key, _)| key.clone())
+                        .collect();
+                    keys.iter().filter_map(|key| held.remove(key)).collect()
+                };
+                for part in others.into_iter().flat_map(|set| set.parts) {
+                    let _ = part.reply.send(HtlcResolution::forward(&channel_id, None));
+                }
+
+                ctx.webhooks
+                    .enqueue(WebhookEvent::Lsps2ChannelOpened {
+                        scid: buy.scid,
+                        remote_id: buy.client_node_id,
+                        channel_id,
+                        txid: buy.txid.unwrap_or_default(),
+                        payment_msat: received,
+                        opening_fee_msat: fee,
+                    })
+                    .await;
+            }
+            Err(e) => {
+                for part in parts {
+                    let _ = part.reply.send(HtlcResolution::fail(UNKNOWN_NEXT_PEER));
+                }
+                self.fail_buy(buy, e).await;
+            }
+        }
+    }
+
+    /// Funds a zero-conf channel to the client and waits until it can carry HTLCs. The channel
+    /// is twice the payment, within the LNURL-channel size bounds.
+    async fn open_channel(
+        &self,
+        ctx: &Context,
+        buy: &mut BuyRecord,
+        payment_msat: u64,
+    ) -> Result<String, String> {
+        let id = PublicKey::from_str(&buy.client_node_id).map_err(|e| e.to_string())?;
+
+        let runtime = ctx.runtime.load_full();
+        let payment_sat = payment_msat.div_ceil(1_000);
+        let amount_sat = runtime
+            .channel_min_sat
+            .max(payment_sat.saturating_mul(2))
+            .min(runtime.channel_max_sat);
+        if amount_sat <= payment_sat {
+            return Err(format!(
+                "a {} sat payment does not fit the maximum channel size of {} sat",
+                payment_sat, runtime.channel_max_sat
+            ));
+        }
+        let amount_msat = amount_sat.saturating_mul(1_000);
+
+        ctx.connect(id)
+            .await
+            .map_err(|e| format!("could not connect to {}: {}", id, e))?;
+
+        let reservation = ctx
+            .spending
+            .reserve(
+                &runtime.spending_policy.limits,
+                SpendFlow::Channel,
+                amount_msat,
+            )
+            .await?;
+
+        let channel_id = match ctx.fund_zero_conf(id, amount_sat).await {
+            Ok((channel_id, txid)) => {
+                ctx.spending.commit(reservation, amount_msat).await;
+                buy.txid = Some(txid);
+                channel_id
+            }
+            Err(e) if is_definitive_fundchannel_failure(&e) => {
+                ctx.spending.release(reservation).await;
+                return Err(e.to_string());
+            }
+            Err(e) => {
+                // The channel may exist: count it as spent.
+                ctx.spending.commit(reservation, amount_msat).await;
+                return Err(format!("funding outcome unknown: {e}"));
+            }
+        };
+        buy.channel_id = Some(channel_id.clone());
+
+        let deadline = Instant::now() + CHANNEL_READY_TIMEOUT;
+        loop {
+            match ctx.channel_ready(id, &channel_id).await {
+                Ok(true) => return Ok(channel_id),
+                Ok(false) => {}
+                Err(e) => tracing::warn!("LSPS2: could not list channels of {}: {}", id, e),
+            }
+
+            if Instant::now() >= deadline {
+                return Err(format!(
+                    "channel {} not ready after {:?}",
+                    channel_id, CHANNEL_READY_TIMEOUT
+                ));
+            }
+            tokio::time::sleep(CHANNEL_READY_POLL).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    const SECRET: &str = "0123456789abcdef";
+    const CLIENT: &str = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+
+    fn fee_params(min_fee_msat: u64, proportional: u32) -> OpeningFeeParams {
+        OpeningFeeParams {
+            min_fee_msat,
+            proportional,
+            valid_until: String::new(),
+            min_lifetime: 0,
+            max_client_to_self_delay: 0,
+            min_payment_size_msat: 0,
+            max_payment_size_msat: u64::MAX,
+            promise: String::new(),
+        }
+    }
+
+    fn args(data_dir: &std::path::Path) -> Args {
+        let hash = hex::encode(<sha2::Sha256 as sha2::Digest>::digest(b"hook"));
+        Args::parse_from([
+            "ln-server".to_string(),
+            "--lsps2-enabled".to_string(),
+            format!("--lsps2-promise-secret={SECRET}"),
+            format!("--api-keys=cln:{hash}:lsps2:hook"),
+            format!("--data-dir={}", data_dir.display()),
+        ])
+    }
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("lsps2-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    struct FakeNode;
+
+    impl Lsps2Node for FakeNode {
+        async fn blockheight(&self) -> anyhow::Result<u32> {
+            Ok(800_000)
+        }
+
+        async fn connect(&self, _id: PublicKey) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        async fn fund_zero_conf(
+            &self,
+            _id: PublicKey,
+            _amount_sat: u64,
+        ) -> anyhow::Result<(String, String)> {
+            Ok(("cd".repeat(32), "ab".repeat(32)))
+        }
+
+        async fn channel_ready(&self, _id: PublicKey, _channel_id: &str) -> anyhow::Result<bool> {
+            Ok(true)
+        }
+    }
+
+    #[test]
+    fn opening_fee_takes_the_larger_of_minimum_and_proportional() {
+        let params = fee_params(2_000, 10_000);
+
+        for (payment_msat, fee_msat) in [
+            (100_000, Some(2_000)),
+            (1_000_000, Some(10_000)),
+            // 1% of 1_000_001 msat rounds up.
+            (1_000_001, Some(10_001)),
+            (0, Some(2_000)),
+        ] {
+            assert_eq!(opening_fee_msat(&params, payment_msat), fee_msat);
+        }
+
+        assert_eq!(opening_fee_msat(&fee_params(0, u32::MAX), u64::MAX), None);
+    }
+
+    #[test]
+    fn split_fee_is_proportional_and_sums_to_the_fee() {
+        assert_eq!(split_fee(100, &[1_000]), Some(vec![100]));
+        assert_eq!(split_fee(100, &[3_000, 1_000]), Some(vec![75, 25]));
+        // The rounding rest goes to the first part.
+        assert_eq!(split_fee(10, &[1_000, 1_000, 1_000]), Some(vec![4, 3, 3]));
+        // A part can't give its whole amount to the fee.
+        assert_eq!(split_fee(100, &[50, 50]), None);
+        assert_eq!(split_fee(100, &[]), None);
+        assert_eq!(split_fee(100, &[0]), None);
+    }
+
+    #[test]
+    fn read_bigsize_rejects_non_minimal_and_truncated_encodings() {
+        for (bytes, expected) in [
+            (&[0x00][..], Some((0, 1))),
+            (&[0xfc][..], Some((0xfc, 1))),
+            (&[0xfd, 0x00, 0xfd][..], Some((0xfd, 3))),
+            (&[0xfd, 0xff, 0xff][..], Some((0xffff, 3))),
+            (&[0xfe, 0x00, 0x01, 0x00, 0x00][..], Some((0x1_0000, 5))),
+            (
+                &[0xff, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00][..],
+                Some((0x1_0000_0000, 9)),
+            ),
+            (&[0xfd, 0x00, 0xfc][..], None),
+            (&[0xfe, 0x00, 0x00, 0xff, 0xff][..], None),
+            (&[0xfd, 0x01][..], None),
+            (&[][..], None),
+        ] {
+            assert_eq!(read_bigsize(bytes), expected, "{bytes:02x?}");
+        }
+    }
+
+    #[test]
+    fn rewrite_amt_to_forward_keeps_the_payload_form() {
+        // amt_to_forward 1000 msat, outgoing_cltv_value 100000.
+        let stream = "020203e804030186a0";
+        let rewritten = "0202038404030186a0";
+
+        assert_eq!(
+            rewrite_amt_to_forward(stream, 900).as_deref(),
+            Some(rewritten)
+        );
+        assert_eq!(
+            rewrite_amt_to_forward(&format!("09{stream}"), 900),
+            Some(format!("09{rewritten}"))
+        );
+        // Amounts are minimally encoded, so the length may change.
+        assert_eq!(
+            rewrite_amt_to_forward(stream, 0x01_0000).as_deref(),
+            Some("020301000004030186a0")
+        );
+
+        assert_eq!(rewrite_amt_to_forward("04030186a0", 900), None);
+        assert_eq!(rewrite_amt_to_forward("0205", 900), None);
+        assert_eq!(rewrite_amt_to_forward("zz", 900), None);
+    }
+
+    #[test]
+    fn verify_accepts_only_unexpired_params_signed_with_the_secret() {
+        let dir = temp_dir();
+        let policy = Lsps2Policy::from_args(&args(&dir)).unwrap().unwrap();
+        let now = utils::unix_time_ms();
+        let params = policy.fee_params(now);
+
+        assert!(policy.verify(&params, now).is_ok());
+
+        let mut tampered = params.clone();
+        tampered.proportional += 1;
+        assert_eq!(
+            policy.verify(&tampered, now).unwrap_err().code,
+            INVALID_OPENING_FEE_PARAMS
+        );
+
+        let mut unsigned = params.clone();
+        unsigned.promise = "00".repeat(32);
+        assert!(policy.verify(&unsigned, now).is_err());
+
+        let other = Lsps2Policy {
+            secret: "fedcba9876543210".to_string(),
+            ..policy.clone()
+        };
+        assert!(other.verify(&params, now).is_err());
+
+        let expired = now + policy.fee_valid_secs * 1_000 + 1;
+        assert!(policy.verify(&params, expired).is_err());
+    }
+
+    #[test]
+    fn from_args_requires_a_hook_key() {
+        let dir = temp_dir();
+        let mut args = args(&dir);
+        assert!(Lsps2Policy::from_args(&args).unwrap().is_some());
+
+        args.api_keys = vec![format!("ui:{}:admin+peers:read", "00".repeat(32))];
+        assert!(Lsps2Policy::from_args(&args).unwrap().is_some());
+
+        args.api_keys = vec![format!("ui:{}:peers:read", "00".repeat(32))];
+        assert!(Lsps2Policy::from_args(&args).is_err());
+
+        args.api_keys.clear();
+        assert!(Lsps2Policy::from_args(&args).is_err());
+    }
+
+    #[tokio::test]
+    async fn buys_are_persisted_and_reloaded() {
+        let dir = temp_dir();
+        let service = Lsps2Service::new(&args(&dir)).unwrap();
+        let menu = service.get_info().unwrap().opening_fee_params_menu;
+
+        let result = service
+            .buy(
+                &FakeNode,
+                BuyParams {
+                    public_key: CLIENT.to_string(),
+                    opening_fee_params: menu[0].clone(),
+                    payment_size_msat: Some(20_000_000),
+                },
+            )
+            .await
+            .unwrap();
+        assert!(result.jit_channel_scid.starts_with("800000x"));
+
+        let mut tampered = menu[0].clone();
+        tampered.min_fee_msat = 0;
+        let rejected = service
+            .buy(
+                &FakeNode,
+                BuyParams {
+                    public_key: CLIENT.to_string(),
+                    opening_fee_params: tampered,
+                    payment_size_msat: None,
+                },
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(rejected.code, INVALID_OPENING_FEE_PARAMS);
+
+        let reloaded = Lsps2Service::new(&args(&dir)).unwrap();
+        let buys = reloaded.buys.lock().await;
+        assert_eq!(buys.len(), 1);
+        assert_eq!(buys[0].scid, result.jit_channel_scid);
+        assert_eq!(buys[0].client_node_id, CLIENT);
+        assert_eq!(buys[0].payment_size_msat, Some(20_000_000));
+        assert_eq!(buys[0].state, BuyState::Pending);
+        assert_eq!(buys[0].params, menu[0]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
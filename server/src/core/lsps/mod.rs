@@ -0,0 +1,150 @@
+//! Lightning Service Provider specifications (LSPS), served as JSON-RPC 2.0 over HTTP on
+//! `POST /lsps`. The BOLT8 transport of LSPS0 is not implemented.
+
+pub mod lsps1;
+pub mod lsps2;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub const PARSE_ERROR: i32 = -32700;
+pub const INVALID_REQUEST: i32 = -32600;
+pub const METHOD_NOT_FOUND: i32 = -32601;
+pub const INVALID_PARAMS: i32 = -32602;
+pub const INTERNAL_ERROR: i32 = -32603;
+
+/// JSON-RPC 2.0 request. LSPS0 requires `params` to be an object.
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct LspsRequest {
+    /// Always `2.0`.
+    pub jsonrpc: String,
+    /// Request id, echoed in the response.
+    #[schema(value_type = Object)]
+    pub id: Option<Value>,
+    /// Method name, e.g. `lsps1.get_info`.
+    pub method: String,
+    /// Method parameters.
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub params: Value,
+}
+
+/// JSON-RPC 2.0 error object.
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct LspsError {
+    pub code: i32,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub data: Option<Value>,
+}
+
+impl LspsError {
+    pub fn new(code: i32, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    /// `-32602` naming the offending parameter, as LSPS0 asks.
+    pub fn invalid_params(property: &str, message: impl Into<String>) -> Self {
+        let message = message.into();
+        Self {
+            code: INVALID_PARAMS,
+            data: Some(serde_json::json!({ "property": property, "message": message })),
+            message: "Invalid params".to_string(),
+        }
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(INTERNAL_ERROR, message)
+    }
+}
+
+/// Deserializes the params of a method, mapping failures to `-32602`.
+pub fn parse_params<T: serde::de::DeserializeOwned>(params: Value) -> Result<T, LspsError> {
+    let params = if params.is_null() {
+        Value::Object(Default::default())
+    } else {
+        params
+    };
+
+    serde_json::from_value(params).map_err(|e| LspsError {
+        code: INVALID_PARAMS,
+        message: "Invalid params".to_string(),
+        data: Some(serde_json::json!({ "message": e.to_string() })),
+    })
+}
+
+/// LSPS0 amounts (`sat`, `msat`) are decimal strings, so they survive JSON parsers that read
+/// numbers as doubles.
+pub mod amount_string {
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    pub fn serialize<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse()
+            .map_err(|_| D::Error::custom(format!("invalid amount: {s}")))
+    }
+
+    /// Optional amount; use with `#[serde(default)]`.
+    pub mod option {
+        use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+        pub fn serialize<S: Serializer>(
+            value: &Option<u64>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match value {
+                Some(value) => serializer.serialize_str(&value.to_string()),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<u64>, D::Error> {
+            Option::<String>::deserialize(deserializer)?
+                .map(|s| {
+                    s.parse()
+                        .map_err(|_| D::Error::custom(format!("invalid amount: {s}")))
+                })
+                .transpose()
+        }
+    }
+}
+
+/// JSON-RPC 2.0 response; exactly one of `result` and `error` is set.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct LspsResponse {
+    pub jsonrpc: &'static str,
+    #[schema(value_type = Object)]
+    pub id: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<LspsError>,
+}
+
+impl LspsResponse {
+    pub fn new(id: Option<Value>, result: Result<Value, LspsError>) -> Self {
+        let (result, error) = match result {
+            Ok(result) => (Some(result), None),
+            Err(error) => (None, Some(error)),
+        };
+
+        Self {
+            jsonrpc: "2.0",
+            id,
+            result,
+            error,
+        }
+    }
+}
@@ -1,5 +1,20 @@
+pub mod api_key;
+pub mod auth_session;
 pub mod bitcoin_rpc_connector;
 pub mod cli;
+pub mod config;
+pub mod cors;
+pub mod invoice_events;
 pub mod lightning_rpc_connector;
+pub mod lsps;
+pub mod onion;
+pub mod rate_limit;
 pub mod recent_request;
+pub mod runtime_config;
+pub mod shutdown;
+pub mod spending_limits;
+pub mod spending_policy;
+pub mod tls;
+pub mod trusted_proxies;
 pub mod utils;
+pub mod webhook;
@@ -0,0 +1,108 @@
+use std::future::Future;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::bail;
+use axum::{Extension, Router};
+use tokio::net::{TcpListener, UnixListener};
+
+use crate::core::cli::Args;
+
+/// Marks requests that arrived through the onion listener, so callback URLs point back at the
+/// onion address instead of the clearnet host.
+#[derive(Debug, Clone, Copy)]
+pub struct OnionRequest;
+
+/// Where the Tor daemon forwards hidden service connections (`HiddenServicePort`).
+#[derive(Debug, Clone)]
+pub enum OnionListen {
+    Unix(PathBuf),
+    Tcp(SocketAddr),
+}
+
+impl FromStr for OnionListen {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(path) = s.strip_prefix("unix:") {
+            return Ok(OnionListen::Unix(PathBuf::from(path)));
+        }
+        if let Ok(addr) = SocketAddr::from_str(s) {
+            return Ok(OnionListen::Tcp(addr));
+        }
+        if let Ok(port) = s.parse::<u16>() {
+            return Ok(OnionListen::Tcp(SocketAddr::from((
+                Ipv4Addr::LOCALHOST,
+                port,
+            ))));
+        }
+
+        bail!("expected unix:<path>, <ip>:<port> or <port>, got {s}")
+    }
+}
+
+/// Additional plain HTTP listener for a Tor hidden service.
+pub struct OnionService {
+    listen: OnionListen,
+}
+
+impl OnionService {
+    /// Returns `None` when no onion listener is configured.
+    pub fn from_args(args: &Args) -> anyhow::Result<Option<Self>> {
+        let Some(listen) = &args.onion_listen else {
+            if args.onion_address.is_some() {
+                bail!("--onion-address requires --onion-listen");
+            }
+            return Ok(None);
+        };
+
+        let Some(address) = &args.onion_address else {
+            bail!("--onion-listen requires --onion-address");
+        };
+        if !address.ends_with(".onion") || address.contains(['/', ':']) {
+            bail!("--onion-address must be a bare .onion host name, got {address}");
+        }
+
+        Ok(Some(Self {
+            listen: listen.parse()?,
+        }))
+    }
+
+    /// Serves `app` (the public routes only) on the onion listener. No `ConnectInfo` is
+    /// attached: every connection comes from the local Tor daemon, so neither the peer address
+    /// nor forwarding headers identify the client, and the rate limiter keys them all as
+    /// `onion`. Stops accepting connections once `shutdown` resolves.
+    pub async fn serve(self, app: Router, shutdown: impl Future<Output = ()> + Send + 'static) {
+        let app = app.layer(Extension(OnionRequest));
+
+        let result = match &self.listen {
+            OnionListen::Unix(path) => {
+                // A socket left over from a previous run would make bind fail.
+                let _ = std::fs::remove_file(path);
+                match UnixListener::bind(path) {
+                    Ok(listener) => {
+                        tracing::info!("Onion listener on unix:{}", path.display());
+                        axum::serve(listener, app)
+                            .with_graceful_shutdown(shutdown)
+                            .await
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+            OnionListen::Tcp(addr) => match TcpListener::bind(addr).await {
+                Ok(listener) => {
+                    tracing::info!("Onion listener on {}", addr);
+                    axum::serve(listener, app)
+                        .with_graceful_shutdown(shutdown)
+                        .await
+                }
+                Err(e) => Err(e),
+            },
+        };
+
+        if let Err(e) = result {
+            tracing::error!("Onion listener on {:?} failed: {}", self.listen, e);
+        }
+    }
+}
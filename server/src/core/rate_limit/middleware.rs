@@ -0,0 +1,48 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::{HeaderValue, Request, StatusCode, header},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::{
+    context::Context,
+    core::{onion::OnionRequest, utils},
+};
+
+// Middleware rejecting clients that exceed the rate limit of a route with 429.
+
+pub async fn middleware(
+    State(state): State<Arc<Context>>,
+    req: Request<axum::body::Body>,
+    next: Next,
+) -> Response {
+    // Onion connections all come from the local Tor daemon, so they share one bucket of their
+    // own rather than the "unknown" one of clients without a peer address.
+    let client = if req.extensions().get::<OnionRequest>().is_some() {
+        "onion".to_string()
+    } else {
+        state
+            .trusted_proxies
+            .client_addr(req.headers(), utils::peer_addr(&req))
+    };
+
+    if let Err(retry_after) = state
+        .runtime
+        .load()
+        .rate_limiter
+        .check(req.uri().path(), &client)
+    {
+        let secs = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+        tracing::debug!("Rate limited {} on {}", client, req.uri().path());
+
+        let mut res = utils::json_error(StatusCode::TOO_MANY_REQUESTS, "rate limit exceeded");
+        res.headers_mut()
+            .insert(header::RETRY_AFTER, HeaderValue::from(secs));
+        return res;
+    }
+
+    next.run(req).await
+}
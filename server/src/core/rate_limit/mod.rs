@@ -0,0 +1,162 @@
+pub mod middleware;
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context as AnyhowContext, bail};
+
+use crate::core::cli::Args;
+
+// Buckets that refilled completely are dropped once the map grows past this size.
+const MAX_TRACKED_BUCKETS: usize = 10_000;
+
+// Never limited: CLN's `htlc_accepted` plugin calls `/lsps2/htlc` for every forwarded HTLC, and a
+// rejection there would fail the payment. The route requires an API key.
+const EXEMPT_PATHS: [&str; 1] = ["/lsps2/htlc"];
+
+/// `<requests>/<seconds>`: a bucket of `requests` tokens refilled over `seconds`.
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    capacity: f64,
+    refill_per_sec: f64,
+}
+
+impl FromStr for RateLimit {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (requests, seconds) = s
+            .split_once('/')
+            .with_context(|| format!("expected <requests>/<seconds>, got {s}"))?;
+        let requests: u32 = requests.trim().parse()?;
+        let seconds: u32 = seconds.trim().parse()?;
+
+        if requests == 0 || seconds == 0 {
+            bail!("rate limit {s} must be positive");
+        }
+
+        Ok(Self {
+            capacity: requests as f64,
+            refill_per_sec: requests as f64 / seconds as f64,
+        })
+    }
+}
+
+struct Rule {
+    prefix: String,
+    limit: RateLimit,
+    rejected: AtomicU64,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token-bucket rate limiter keyed by client address and route prefix.
+pub struct RateLimiter {
+    rules: Vec<Rule>,
+    buckets: Mutex<HashMap<(usize, String), Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn from_args(args: &Args) -> anyhow::Result<Self> {
+        let mut rules: Vec<Rule> = Vec::new();
+
+        for entry in &args.rate_limits {
+            let (prefix, limit) = entry
+                .split_once('=')
+                .with_context(|| format!("expected <path>=<requests>/<seconds>, got {entry}"))?;
+            if !prefix.starts_with('/') {
+                bail!("rate limit path must start with '/': {prefix}");
+            }
+
+            rules.push(Rule {
+                prefix: prefix.to_string(),
+                limit: limit.parse()?,
+                rejected: AtomicU64::new(0),
+            });
+        }
+
+        // The default applies to every other route, unless a rule for "/" was given.
+        if !args.rate_limit_default.eq_ignore_ascii_case("off")
+            && !rules.iter().any(|r| r.prefix == "/")
+        {
+            rules.push(Rule {
+                prefix: "/".to_string(),
+                limit: args
+                    .rate_limit_default
+                    .parse()
+                    .context("invalid default rate limit")?,
+                rejected: AtomicU64::new(0),
+            });
+        }
+
+        Ok(Self {
+            rules,
+            buckets: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Takes a token for `client` on `path`. Returns how long to wait when the bucket is empty.
+    pub fn check(&self, path: &str, client: &str) -> Result<(), Duration> {
+        if EXEMPT_PATHS.contains(&path) {
+            return Ok(());
+        }
+
+        // The longest matching prefix wins.
+        let Some((index, rule)) = self
+            .rules
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| path.starts_with(&r.prefix))
+            .max_by_key(|(_, r)| r.prefix.len())
+        else {
+            return Ok(());
+        };
+
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+
+        if buckets.len() >= MAX_TRACKED_BUCKETS {
+            buckets.retain(|(i, _), b| {
+                let limit = self.rules[*i].limit;
+                b.tokens + now.duration_since(b.updated).as_secs_f64() * limit.refill_per_sec
+                    < limit.capacity
+            });
+        }
+
+        let bucket = buckets
+            .entry((index, client.to_string()))
+            .or_insert(Bucket {
+                tokens: rule.limit.capacity,
+                updated: now,
+            });
+
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens =
+            (bucket.tokens + elapsed * rule.limit.refill_per_sec).min(rule.limit.capacity);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+
+        rule.rejected.fetch_add(1, Ordering::Relaxed);
+        Err(Duration::from_secs_f64(
+            (1.0 - bucket.tokens) / rule.limit.refill_per_sec,
+        ))
+    }
+
+    /// Number of rejected requests per route prefix.
+    pub fn rejections(&self) -> Vec<(&str, u64)> {
+        self.rules
+            .iter()
+            .map(|r| (r.prefix.as_str(), r.rejected.load(Ordering::Relaxed)))
+            .collect()
+    }
+}
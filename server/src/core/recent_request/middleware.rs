@@ -10,7 +10,10 @@ use axum::{
     response::Response,
 };
 
-use crate::{context::Context, core::recent_request::entry::RecentRequestEntry};
+use crate::{
+    context::Context,
+    core::recent_request::{entry::RecentRequestEntry, store::MAX_ENTRIES},
+};
 
 fn should_log_path(path: &str) -> bool {
     // Avoid spamming the log with UI polling endpoints and docs.
@@ -20,29 +23,6 @@ fn should_log_path(path: &str) -> bool {
         || path.starts_with("/api-doc/"))
 }
 
-fn extract_client_addr(headers: &HeaderMap, connect: Option<SocketAddr>) -> String {
-    // Prefer X-Forwarded-For (nginx sets it as a comma-separated list).
-    if let Some(v) = headers.get("x-forwarded-for").and_then(|h| h.to_str().ok())
-        && let Some(first) = v.split(',').next().map(str::trim)
-        && !first.is_empty()
-    {
-        return first.to_string();
-    }
-
-    // Next, try X-Real-IP.
-    if let Some(v) = headers.get("x-real-ip").and_then(|h| h.to_str().ok()) {
-        let v = v.trim();
-        if !v.is_empty() {
-            return v.to_string();
-        }
-    }
-
-    // Fallback to the socket address.
-    connect
-        .map(|a| a.to_string())
-        .unwrap_or_else(|| "unknown".to_string())
-}
-
 // Middleware to log recent requests into Context.recent_requests.
 
 pub async fn middleware(
@@ -61,7 +41,7 @@ pub async fn middleware(
 
     let should_log = should_log_path(&path);
     let client_addr = if should_log {
-        extract_client_addr(&headers, connect)
+        state.trusted_proxies.client_addr(&headers, connect)
     } else {
         String::new()
     };
@@ -97,8 +77,8 @@ pub async fn middleware(
     if should_log {
         let mut q = state.recent_requests.lock().await;
         q.push_back(entry);
-        while q.len() > 100 {
-            // Keep only the latest 100 entries
+        while q.len() > MAX_ENTRIES {
+            // Keep only the latest entries
             q.pop_front();
         }
     }
@@ -1,2 +1,3 @@
 pub mod entry;
 pub mod middleware;
+pub mod store;
@@ -0,0 +1,36 @@
+use std::collections::VecDeque;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use crate::core::recent_request::entry::RecentRequestEntry;
+use crate::core::utils;
+
+pub const STORE_FILE: &str = "recent_requests.json";
+
+/// Entries kept in `Context.recent_requests`.
+pub const MAX_ENTRIES: usize = 100;
+
+/// Loads the log saved by the previous run, so the dashboard survives restarts.
+pub fn load(path: &Path) -> VecDeque<RecentRequestEntry> {
+    let entries: VecDeque<RecentRequestEntry> = match std::fs::read(path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring unreadable recent requests log {:?}: {}", path, e);
+            VecDeque::new()
+        }),
+        Err(e) if e.kind() == ErrorKind::NotFound => VecDeque::new(),
+        Err(e) => {
+            tracing::warn!("Could not read recent requests log {:?}: {}", path, e);
+            VecDeque::new()
+        }
+    };
+
+    let skip = entries.len().saturating_sub(MAX_ENTRIES);
+    entries.into_iter().skip(skip).collect()
+}
+
+/// Saves the log on shutdown.
+pub async fn save(path: &Path, entries: &VecDeque<RecentRequestEntry>) -> anyhow::Result<()> {
+    let bytes = serde_json::to_vec(entries)?;
+    utils::write_file_atomic(path, &bytes).await?;
+    Ok(())
+}
@@ -0,0 +1,164 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use anyhow::bail;
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::core::cli::Args;
+use crate::core::rate_limit::RateLimiter;
+use crate::core::spending_policy::SpendingPolicy;
+
+/// Settings that can change without a restart (SIGHUP or `POST /config/reload`).
+pub const RELOADABLE_SETTINGS: [&str; 12] = [
+    "min_withdrawable_msat",
+    "max_withdrawable_msat",
+    "channel_min_sat",
+    "channel_max_sat",
+    "channel_push_msat",
+    "payment_max_msat",
+    "payment_max_fee_ppm",
+    "payment_max_fee_msat",
+    "payment_allowed_destinations",
+    "spending_limits",
+    "rate_limit_default",
+    "rate_limits",
+];
+
+const RATE_LIMIT_SETTINGS: [&str; 2] = ["rate_limit_default", "rate_limits"];
+
+/// Policy values read on every request. `Context` holds the current one behind an `ArcSwap`,
+/// so a reload replaces it atomically while in-flight requests finish with the old one.
+pub struct RuntimeConfig {
+    /// Incremented by every reload that changed a setting.
+    pub version: u64,
+    /// Short SHA256 of the settings, to compare instances.
+    pub hash: String,
+    pub min_withdrawable_msat: u64,
+    pub max_withdrawable_msat: u64,
+    pub channel_min_sat: u64,
+    pub channel_max_sat: u64,
+    pub channel_push_msat: u64,
+    pub spending_policy: SpendingPolicy,
+    pub rate_limiter: Arc<RateLimiter>,
+    settings: BTreeMap<String, Value>,
+}
+
+/// A setting changed by a reload.
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct ConfigChange {
+    /// Setting name, as in the config file.
+    pub setting: String,
+    /// Value before the reload (null when unset).
+    pub old: Value,
+    /// Value after the reload (null when unset).
+    pub new: Value,
+}
+
+impl RuntimeConfig {
+    pub fn from_args(args: &Args) -> anyhow::Result<Self> {
+        Self::build(args, 1, None)
+    }
+
+    /// Builds the successor of `self` from freshly read settings. Rate-limit buckets survive
+    /// reloads that don't touch the rate limits.
+    pub fn reload(&self, args: &Args) -> anyhow::Result<(Self, Vec<ConfigChange>)> {
+        let next = Self::build(args, self.version + 1, Some(self))?;
+
+        let changes: Vec<ConfigChange> = RELOADABLE_SETTINGS
+            .iter()
+            .filter_map(|key| {
+                let old = self.settings.get(*key).cloned().unwrap_or(Value::Null);
+                let new = next.settings.get(*key).cloned().unwrap_or(Value::Null);
+                (old != new).then(|| ConfigChange {
+                    setting: key.to_string(),
+                    old,
+                    new,
+                })
+            })
+            .collect();
+
+        if changes.is_empty() {
+            return Ok((
+                Self {
+                    version: self.version,
+                    ..next
+                },
+                changes,
+            ));
+        }
+
+        Ok((next, changes))
+    }
+
+    fn build(args: &Args, version: u64, previous: Option<&Self>) -> anyhow::Result<Self> {
+        if args.min_withdrawable_msat > args.max_withdrawable_msat {
+            bail!(
+                "min_withdrawable_msat ({}) exceeds max_withdrawable_msat ({})",
+                args.min_withdrawable_msat,
+                args.max_withdrawable_msat
+            );
+        }
+
+        check_channel_bounds(args)?;
+
+        let settings = settings(args);
+
+        let rate_limiter = match previous {
+            Some(prev)
+                if RATE_LIMIT_SETTINGS
+                    .iter()
+                    .all(|key| prev.settings.get(*key) == settings.get(*key)) =>
+            {
+                prev.rate_limiter.clone()
+            }
+            _ => Arc::new(RateLimiter::from_args(args)?),
+        };
+
+        let digest = Sha256::digest(serde_json::to_vec(&settings)?);
+
+        Ok(Self {
+            version,
+            hash: hex::encode(&digest[..6]),
+            min_withdrawable_msat: args.min_withdrawable_msat,
+            max_withdrawable_msat: args.max_withdrawable_msat,
+            channel_min_sat: args.channel_min_sat,
+            channel_max_sat: args.channel_max_sat,
+            channel_push_msat: args.channel_push_msat,
+            spending_policy: SpendingPolicy::from_args(args)?,
+            rate_limiter,
+            settings,
+        })
+    }
+}
+
+/// The push amount must leave the node a balance in the smallest channel it opens.
+pub fn check_channel_bounds(args: &Args) -> anyhow::Result<()> {
+    if args.channel_min_sat > args.channel_max_sat {
+        bail!(
+            "channel_min_sat ({}) exceeds channel_max_sat ({})",
+            args.channel_min_sat,
+            args.channel_max_sat
+        );
+    }
+    if args.channel_push_msat >= args.channel_min_sat.saturating_mul(1_000) {
+        bail!(
+            "channel_push_msat ({}) must be below channel_min_sat ({}) in millisatoshis",
+            args.channel_push_msat,
+            args.channel_min_sat
+        );
+    }
+
+    Ok(())
+}
+
+fn settings(args: &Args) -> BTreeMap<String, Value> {
+    let Ok(Value::Object(all)) = serde_json::to_value(args) else {
+        return BTreeMap::new();
+    };
+
+    all.into_iter()
+        .filter(|(key, _)| RELOADABLE_SETTINGS.contains(&key.as_str()))
+        .collect()
+}
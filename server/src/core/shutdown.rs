@@ -0,0 +1,114 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use axum::{extract::State, http::Request, middleware::Next, response::Response};
+use tokio::signal::unix::{SignalKind, signal};
+use tokio::sync::watch;
+
+use crate::context::Context;
+
+// How often the number of requests still running is logged while draining.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Graceful shutdown state shared by the listeners, the handlers and `main`.
+///
+/// Once triggered, listeners stop accepting connections and finish the requests already
+/// running, event streams end, and handlers refuse to issue new k1 challenges.
+pub struct Shutdown {
+    draining: watch::Sender<bool>,
+    in_flight: AtomicUsize,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self {
+            draining: watch::Sender::new(false),
+            in_flight: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl Shutdown {
+    pub fn trigger(&self) {
+        self.draining.send_replace(true);
+    }
+
+    pub fn is_draining(&self) -> bool {
+        *self.draining.borrow()
+    }
+
+    /// Requests currently being handled (responses not yet produced).
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once shutdown is triggered. Independent of `self`, so it can be handed to
+    /// listeners and streams.
+    pub fn wait(&self) -> impl Future<Output = ()> + Send + use<> {
+        let mut rx = self.draining.subscribe();
+        async move {
+            let _ = rx.wait_for(|draining| *draining).await;
+        }
+    }
+
+    /// Waits for shutdown, then logs the requests still running until `deadline` elapses.
+    pub async fn deadline(&self, deadline: Duration) {
+        self.wait().await;
+
+        let expired = tokio::time::sleep(deadline);
+        tokio::pin!(expired);
+        let mut progress = tokio::time::interval(PROGRESS_INTERVAL);
+
+        loop {
+            tokio::select! {
+                _ = &mut expired => return,
+                _ = progress.tick() => {
+                    let in_flight = self.in_flight();
+                    if in_flight > 0 {
+                        tracing::info!("Shutdown: waiting for {} in-flight requests", in_flight);
+                    }
+                }
+            }
+        }
+    }
+}
+
+struct InFlight<'a>(&'a Shutdown);
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+// Counts requests until their response is produced. CLN calls (fundchannel, withdraw, pay, ...)
+// run inside handlers, so a drained counter means none of them is still pending.
+pub async fn middleware(
+    State(state): State<Arc<Context>>,
+    req: Request<axum::body::Body>,
+    next: Next,
+) -> Response {
+    state.shutdown.in_flight.fetch_add(1, Ordering::SeqCst);
+    let _guard = InFlight(&state.shutdown);
+
+    next.run(req).await
+}
+
+/// Resolves on SIGTERM (docker stop, systemd) or SIGINT (Ctrl-C).
+pub async fn signal_received() {
+    let mut terminate = match signal(SignalKind::terminate()) {
+        Ok(terminate) => terminate,
+        Err(e) => {
+            tracing::warn!("Could not install SIGTERM handler: {}", e);
+            let _ = tokio::signal::ctrl_c().await;
+            return;
+        }
+    };
+
+    tokio::select! {
+        _ = terminate.recv() => tracing::info!("SIGTERM received"),
+        _ = tokio::signal::ctrl_c() => tracing::info!("SIGINT received"),
+    }
+}
@@ -0,0 +1,572 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::{Context as AnyhowContext, bail};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::core::cli::Args;
+use crate::core::utils;
+
+const STORE_FILE: &str = "spending_ledger.json";
+
+/// Kind of outgoing funds movement counted against the spending limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SpendFlow {
+    /// On-chain withdrawals (`/callbacks/issue-withdraw`).
+    Withdraw,
+    /// Channel funding (`/callbacks/open-channel`).
+    Channel,
+    /// Lightning payments, including routing fees (`/payments`, `/offers/pay`).
+    Lightning,
+}
+
+impl SpendFlow {
+    pub const ALL: [SpendFlow; 3] = [
+        SpendFlow::Withdraw,
+        SpendFlow::Channel,
+        SpendFlow::Lightning,
+    ];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            SpendFlow::Withdraw => "withdraw",
+            SpendFlow::Channel => "channel",
+            SpendFlow::Lightning => "lightning",
+        }
+    }
+}
+
+impl fmt::Display for SpendFlow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Window {
+    Request,
+    Hour,
+    Day,
+}
+
+impl Window {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Window::Request => "request",
+            Window::Hour => "hour",
+            Window::Day => "day",
+        }
+    }
+
+    fn millis(&self) -> u64 {
+        match self {
+            Window::Request => 0,
+            Window::Hour => 60 * 60 * 1_000,
+            Window::Day => 24 * 60 * 60 * 1_000,
+        }
+    }
+}
+
+/// Flow a limit applies to; `None` is the total across all flows.
+type LimitKey = (Option<SpendFlow>, Window);
+
+fn flow_name(flow: Option<SpendFlow>) -> &'static str {
+    flow.map_or("total", |f| f.as_str())
+}
+
+/// Ceilings from `SERVER_SPENDING_LIMITS`, entries of the form `<flow>:<window>=<msat>`, where
+/// flow is `withdraw`, `channel`, `lightning` or `total` and window is `request` (a single
+/// spend), `hour` or `day` (rolling).
+#[derive(Debug, Default)]
+pub struct SpendingLimits {
+    limits: HashMap<LimitKey, u64>,
+}
+
+impl SpendingLimits {
+    pub fn from_args(args: &Args) -> anyhow::Result<Self> {
+        let mut limits = HashMap::new();
+
+        for entry in &args.spending_limits {
+            let (key, msat) = entry.split_once('=').with_context(|| {
+                format!("spending limit must be <flow>:<window>=<msat>: {entry}")
+            })?;
+            let (flow, window) = key.trim().split_once(':').with_context(|| {
+                format!("spending limit must be <flow>:<window>=<msat>: {entry}")
+            })?;
+
+            let flow = match flow {
+                "total" => None,
+                other => Some(
+                    SpendFlow::ALL
+                        .into_iter()
+                        .find(|f| f.as_str() == other)
+                        .with_context(|| format!("unknown spending flow: {other}"))?,
+                ),
+            };
+            let window = match window {
+                "request" => Window::Request,
+                "hour" => Window::Hour,
+                "day" => Window::Day,
+                other => bail!("unknown spending window: {other} (request, hour or day)"),
+            };
+            let msat = u64::from_str(msat.trim())
+                .with_context(|| format!("invalid spending limit amount: {entry}"))?;
+
+            if limits.insert((flow, window), msat).is_some() {
+                bail!(
+                    "duplicate spending limit for {}:{}",
+                    flow_name(flow),
+                    window.as_str()
+                );
+            }
+        }
+
+        Ok(Self { limits })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.limits.is_empty()
+    }
+
+    fn get(&self, flow: Option<SpendFlow>, window: Window) -> Option<u64> {
+        self.limits.get(&(flow, window)).copied()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Spend {
+    // Reservations only live in memory; the store holds committed spends.
+    #[serde(skip)]
+    id: u64,
+    #[serde(skip)]
+    pending: bool,
+    flow: SpendFlow,
+    amount_msat: u64,
+    ts_ms: u64,
+}
+
+struct Ledger {
+    next_id: u64,
+    spends: Vec<Spend>,
+}
+
+impl Ledger {
+    fn prune(&mut self, now: u64) {
+        let cutoff = now.saturating_sub(Window::Day.millis());
+        self.spends.retain(|s| s.pending || s.ts_ms > cutoff);
+    }
+
+    fn used_msat(&self, flow: Option<SpendFlow>, window: Window, now: u64) -> u64 {
+        let cutoff = now.saturating_sub(window.millis());
+        self.spends
+            .iter()
+            .filter(|s| flow.is_none_or(|f| f == s.flow))
+            .filter(|s| s.ts_ms > cutoff)
+            .map(|s| s.amount_msat)
+            .sum()
+    }
+}
+
+/// Amount held against the limits while the CLN call runs. Must be passed to
+/// `SpendingLedger::commit` or `SpendingLedger::release`.
+#[must_use]
+pub struct Reservation {
+    id: u64,
+}
+
+/// Usage of one window.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct WindowUsage {
+    /// Amount spent (or reserved by calls in progress) in the window, in millisatoshis.
+    pub used_msat: u64,
+    /// Configured ceiling in millisatoshis (absent when unlimited).
+    pub limit_msat: Option<u64>,
+    /// Amount still available in millisatoshis (absent when unlimited).
+    pub remaining_msat: Option<u64>,
+}
+
+/// Usage of one flow, or of all flows together.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct SpendingUsage {
+    /// Flow the usage applies to; absent for the total across all flows.
+    pub flow: Option<SpendFlow>,
+    /// Maximum amount of a single spend in millisatoshis (absent when unlimited).
+    pub per_request_limit_msat: Option<u64>,
+    /// Rolling last hour.
+    pub hour: WindowUsage,
+    /// Rolling last 24 hours.
+    pub day: WindowUsage,
+}
+
+/// Record of recent spends, persisted in the data directory so a restart doesn't reset the
+/// rolling windows.
+pub struct SpendingLedger {
+    store_path: PathBuf,
+    ledger: Mutex<Ledger>,
+}
+
+impl SpendingLedger {
+    pub fn new(args: &Args) -> Self {
+        let store_path = args.data_dir.join(STORE_FILE);
+        let spends = match std::fs::read(&store_path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                tracing::warn!(
+                    "Ignoring unreadable spending ledger {:?}: {}",
+                    store_path,
+                    e
+                );
+                Vec::new()
+            }),
+            Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(e) => {
+                tracing::warn!("Could not read spending ledger {:?}: {}", store_path, e);
+                Vec::new()
+            }
+        };
+
+        let mut ledger = Ledger { next_id: 1, spends };
+        ledger.prune(utils::unix_time_ms());
+
+        Self {
+            store_path,
+            ledger: Mutex::new(ledger),
+        }
+    }
+
+    /// Reserves `amount_msat` for `flow` if no per-request, hourly or daily ceiling of the flow
+    /// or of the total would be exceeded.
+    pub async fn reserve(
+        &self,
+        limits: &SpendingLimits,
+        flow: SpendFlow,
+        amount_msat: u64,
+    ) -> Result<Reservation, String> {
+        let now = utils::unix_time_ms();
+        let mut ledger = self.ledger.lock().await;
+        ledger.prune(now);
+
+        for scope in [Some(flow), None] {
+            if let Some(limit) = limits.get(scope, Window::Request)
+                && amount_msat > limit
+            {
+                return Err(format!(
+                    "{} msat exceeds the {} per-request limit of {} msat",
+                    amount_msat,
+                    flow_name(scope),
+                    limit
+                ));
+            }
+
+            for window in [Window::Hour, Window::Day] {
+                let Some(limit) = limits.get(scope, window) else {
+                    continue;
+                };
+                let used = ledger.used_msat(scope, window, now);
+                if used.saturating_add(amount_msat) > limit {
+                    return Err(format!(
+                        "{} msat would exceed the {} limit of {} msat per {} ({} msat used)",
+                        amount_msat,
+                        flow_name(scope),
+                        limit,
+                        window.as_str(),
+                        used
+                    ));
+                }
+            }
+        }
+
+        let id = ledger.next_id;
+        ledger.next_id += 1;
+        ledger.spends.push(Spend {
+            id,
+            pending: true,
+            flow,
+            amount_msat,
+            ts_ms: now,
+        });
+
+        Ok(Reservation { id })
+    }
+
+    /// Records the reserved spend with the amount that actually left the node.
+    pub async fn commit(&self, reservation: Reservation, amount_msat: u64) {
+        let mut ledger = self.ledger.lock().await;
+        if let Some(spend) = ledger.spends.iter_mut().find(|s| s.id == reservation.id) {
+            spend.pending = false;
+            spend.amount_msat = amount_msat;
+        }
+
+        let committed: Vec<&Spend> = ledger.spends.iter().filter(|s| !s.pending).collect();
+        let result = match serde_json::to_vec(&committed) {
+            Ok(bytes) => utils::write_file_atomic(&self.store_path, &bytes).await,
+            Err(e) => Err(e.into()),
+        };
+
+        if let Err(e) = result {
+            tracing::error!(
+                "Could not persist spending ledger {:?}: {}",
+                self.store_path,
+                e
+            );
+        }
+    }
+
+    /// Drops a reservation whose CLN call failed.
+    pub async fn release(&self, reservation: Reservation) {
+        let mut ledger = self.ledger.lock().await;
+        ledger.spends.retain(|s| s.id != reservation.id);
+    }
+
+    /// Current usage of every flow and of the total.
+    pub async fn usage(&self, limits: &SpendingLimits) -> Vec<SpendingUsage> {
+        let now = utils::unix_time_ms();
+        let ledger = self.ledger.lock().await;
+
+        let window_usage = |flow, window| {
+            let used_msat = ledger.used_msat(flow, window, now);
+            let limit_msat = limits.get(flow, window);
+            WindowUsage {
+                used_msat,
+                limit_msat,
+                remaining_msat: limit_msat.map(|l| l.saturating_sub(used_msat)),
+            }
+        };
+
+        SpendFlow::ALL
+            .into_iter()
+            .map(Some)
+            .chain([None])
+            .map(|flow| SpendingUsage {
+                flow,
+                per_request_limit_msat: limits.get(flow, Window::Request),
+                hour: window_usage(flow, Window::Hour),
+                day: window_usage(flow, Window::Day),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    const HOUR_MS: u64 = 60 * 60 * 1_000;
+
+    fn args(limits: &[&str], data_dir: &std::path::Path) -> Args {
+        let mut argv = vec![
+            "ln-server".to_string(),
+            format!("--data-dir={}", data_dir.display()),
+        ];
+        argv.extend(limits.iter().map(|l| format!("--spending-limits={l}")));
+        Args::parse_from(argv)
+    }
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("spending-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn setup(limits: &[&str]) -> (SpendingLimits, SpendingLedger) {
+        let args = args(limits, &temp_dir());
+        (
+            SpendingLimits::from_args(&args).unwrap(),
+            SpendingLedger::new(&args),
+        )
+    }
+
+    async fn used(ledger: &SpendingLedger, limits: &SpendingLimits, flow: SpendFlow) -> (u64, u64) {
+        let usage = ledger.usage(limits).await;
+        let usage = usage.iter().find(|u| u.flow == Some(flow)).unwrap();
+        (usage.hour.used_msat, usage.day.used_msat)
+    }
+
+    #[test]
+    fn parses_limits() {
+        let dir = temp_dir();
+        let limits = SpendingLimits::from_args(&args(
+            &[
+                "withdraw:request=10",
+                " total:day = 30 ",
+                "lightning:hour=20",
+            ],
+            &dir,
+        ))
+        .unwrap();
+        assert_eq!(
+            limits.get(Some(SpendFlow::Withdraw), Window::Request),
+            Some(10)
+        );
+        assert_eq!(limits.get(None, Window::Day), Some(30));
+        assert_eq!(
+            limits.get(Some(SpendFlow::Lightning), Window::Hour),
+            Some(20)
+        );
+        assert_eq!(limits.get(Some(SpendFlow::Channel), Window::Day), None);
+
+        for invalid in [
+            "withdraw=10",
+            "withdraw:day",
+            "onchain:day=10",
+            "withdraw:week=10",
+            "withdraw:day=ten",
+        ] {
+            assert!(
+                SpendingLimits::from_args(&args(&[invalid], &dir)).is_err(),
+                "{invalid}"
+            );
+        }
+        assert!(
+            SpendingLimits::from_args(&args(&["total:day=1", "total:day=2"], &dir)).is_err(),
+            "duplicate"
+        );
+    }
+
+    #[tokio::test]
+    async fn enforces_per_request_and_rolling_limits() {
+        let (limits, ledger) =
+            setup(&["withdraw:request=50", "withdraw:hour=100", "total:day=150"]);
+
+        assert!(
+            ledger
+                .reserve(&limits, SpendFlow::Withdraw, 51)
+                .await
+                .is_err()
+        );
+
+        // Pending reservations count against the windows.
+        let first = ledger
+            .reserve(&limits, SpendFlow::Withdraw, 50)
+            .await
+            .unwrap();
+        let second = ledger
+            .reserve(&limits, SpendFlow::Withdraw, 50)
+            .await
+            .unwrap();
+        let err = ledger
+            .reserve(&limits, SpendFlow::Withdraw, 1)
+            .await
+            .err()
+            .unwrap();
+        assert!(err.contains("withdraw limit of 100 msat per hour"), "{err}");
+
+        // Other flows are only held by the total.
+        let channel = ledger
+            .reserve(&limits, SpendFlow::Channel, 50)
+            .await
+            .unwrap();
+        let err = ledger
+            .reserve(&limits, SpendFlow::Lightning, 1)
+            .await
+            .err()
+            .unwrap();
+        assert!(err.contains("total limit of 150 msat per day"), "{err}");
+
+        ledger.commit(first, 50).await;
+        ledger.commit(second, 50).await;
+        ledger.commit(channel, 50).await;
+    }
+
+    #[tokio::test]
+    async fn release_frees_and_commit_records_the_actual_amount() {
+        let (limits, ledger) = setup(&["lightning:hour=100"]);
+
+        let reservation = ledger
+            .reserve(&limits, SpendFlow::Lightning, 100)
+            .await
+            .unwrap();
+        assert_eq!(
+            used(&ledger, &limits, SpendFlow::Lightning).await,
+            (100, 100)
+        );
+        ledger.release(reservation).await;
+        assert_eq!(used(&ledger, &limits, SpendFlow::Lightning).await, (0, 0));
+
+        // A payment reserves its fee budget and records what was actually sent.
+        let reservation = ledger
+            .reserve(&limits, SpendFlow::Lightning, 100)
+            .await
+            .unwrap();
+        ledger.commit(reservation, 60).await;
+        assert_eq!(used(&ledger, &limits, SpendFlow::Lightning).await, (60, 60));
+
+        let reservation = ledger
+            .reserve(&limits, SpendFlow::Lightning, 40)
+            .await
+            .unwrap();
+        ledger.commit(reservation, 40).await;
+        assert!(
+            ledger
+                .reserve(&limits, SpendFlow::Lightning, 1)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn windows_roll_over() {
+        let (limits, ledger) = setup(&["channel:hour=100", "channel:day=150"]);
+        let now = utils::unix_time_ms();
+
+        {
+            let mut inner = ledger.ledger.lock().await;
+            for (ago_ms, amount_msat) in [(2 * HOUR_MS, 100), (25 * HOUR_MS, 1_000)] {
+                inner.spends.push(Spend {
+                    id: 0,
+                    pending: false,
+                    flow: SpendFlow::Channel,
+                    amount_msat,
+                    ts_ms: now - ago_ms,
+                });
+            }
+        }
+
+        // Two hours ago only counts for the day; a day and an hour ago no longer counts.
+        assert_eq!(used(&ledger, &limits, SpendFlow::Channel).await, (0, 100));
+        assert!(
+            ledger
+                .reserve(&limits, SpendFlow::Channel, 51)
+                .await
+                .is_err()
+        );
+        let reservation = ledger
+            .reserve(&limits, SpendFlow::Channel, 50)
+            .await
+            .unwrap();
+        ledger.commit(reservation, 50).await;
+
+        // Reserving pruned the spends older than a day.
+        assert_eq!(ledger.ledger.lock().await.spends.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn persists_committed_spends_only() {
+        let dir = temp_dir();
+        let args = args(&[], &dir);
+        let limits = SpendingLimits::from_args(&args).unwrap();
+
+        let ledger = SpendingLedger::new(&args);
+        let committed = ledger
+            .reserve(&limits, SpendFlow::Withdraw, 70)
+            .await
+            .unwrap();
+        let _pending = ledger
+            .reserve(&limits, SpendFlow::Withdraw, 30)
+            .await
+            .unwrap();
+        ledger.commit(committed, 70).await;
+
+        let reloaded = SpendingLedger::new(&args);
+        assert_eq!(
+            used(&reloaded, &limits, SpendFlow::Withdraw).await,
+            (70, 70)
+        );
+    }
+}
@@ -0,0 +1,73 @@
+use std::str::FromStr;
+
+use anyhow::Context as AnyhowContext;
+use cln_rpc::primitives::PublicKey;
+
+use crate::core::cli::Args;
+use crate::core::spending_limits::SpendingLimits;
+
+/// Limits applied to every outgoing payment made through the gateway, plus the cumulative
+/// ceilings checked by `SpendingLedger` for withdrawals, channel funding and payments.
+pub struct SpendingPolicy {
+    /// Maximum amount of a single payment in millisatoshis.
+    pub max_payment_msat: u64,
+    /// Maximum routing fee in parts per million of the payment amount.
+    pub max_fee_ppm: u64,
+    /// Maximum absolute routing fee in millisatoshis.
+    pub max_fee_msat: Option<u64>,
+    /// Destinations payments are restricted to; empty means any destination.
+    pub allowed_destinations: Vec<PublicKey>,
+    /// Per-request and rolling ceilings per flow and in total.
+    pub limits: SpendingLimits,
+}
+
+impl SpendingPolicy {
+    pub fn from_args(args: &Args) -> anyhow::Result<Self> {
+        let allowed_destinations = args
+            .payment_allowed_destinations
+            .iter()
+            .map(|d| {
+                PublicKey::from_str(d).with_context(|| format!("invalid allowed destination: {d}"))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(Self {
+            max_payment_msat: args.payment_max_msat,
+            max_fee_ppm: args.payment_max_fee_ppm,
+            max_fee_msat: args.payment_max_fee_msat,
+            allowed_destinations,
+            limits: SpendingLimits::from_args(args)?,
+        })
+    }
+
+    /// Checks that paying `amount_msat` to `destination` is allowed.
+    pub fn check(&self, destination: &PublicKey, amount_msat: u64) -> Result<(), String> {
+        if amount_msat == 0 {
+            return Err("payment amount must be positive".to_string());
+        }
+
+        if amount_msat > self.max_payment_msat {
+            return Err(format!(
+                "payment amount {} msat exceeds the limit of {} msat",
+                amount_msat, self.max_payment_msat
+            ));
+        }
+
+        if !self.allowed_destinations.is_empty() && !self.allowed_destinations.contains(destination)
+        {
+            return Err(format!("destination {} is not allowed", destination));
+        }
+
+        Ok(())
+    }
+
+    /// Routing fee budget for a payment of `amount_msat`: the stricter of the ppm and
+    /// absolute limits.
+    pub fn fee_budget_msat(&self, amount_msat: u64) -> u64 {
+        let ppm_budget = (amount_msat as u128 * self.max_fee_ppm as u128 / 1_000_000) as u64;
+        match self.max_fee_msat {
+            Some(max) => ppm_budget.min(max),
+            None => ppm_budget,
+        }
+    }
+}
@@ -0,0 +1,345 @@
+use std::future::Future;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context as AnyhowContext, bail};
+use axum::Router;
+use axum::extract::ConnectInfo;
+use axum::http::{HeaderMap, StatusCode, Uri, header, uri::Authority};
+use axum::response::{IntoResponse, Redirect, Response};
+use futures_util::FutureExt;
+use hyper::body::Incoming;
+use hyper_util::rt::TokioIo;
+use rustls::RootCertStore;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject};
+use rustls::server::{ServerConfig, WebPkiClientVerifier};
+use sha2::{Digest, Sha256};
+use tokio::net::TcpListener;
+use tokio::task::JoinSet;
+use tokio_rustls::TlsAcceptor;
+use tower::ServiceExt;
+
+use crate::core::cli::Args;
+
+// Connections that don't finish the handshake in time are dropped.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Client certificate presented in the TLS handshake. It was verified against
+/// `SERVER_TLS_CLIENT_CA`, so its presence on a request is proof of a trusted client.
+#[derive(Debug, Clone)]
+pub struct ClientCertificate {
+    /// SHA256 fingerprint of the leaf certificate (hex).
+    pub fingerprint: String,
+}
+
+/// PEM files the TLS configuration is built from.
+struct TlsFiles {
+    cert_path: PathBuf,
+    key_path: PathBuf,
+    client_ca_path: Option<PathBuf>,
+}
+
+/// rustls server configuration loaded from PEM files, reloaded when the files change so
+/// renewed certificates (e.g. from certbot) are picked up without a restart.
+pub struct TlsConfig {
+    files: TlsFiles,
+    current: RwLock<Arc<ServerConfig>>,
+    modified: Mutex<Vec<Option<SystemTime>>>,
+}
+
+impl TlsConfig {
+    /// Returns `None` when TLS is not configured.
+    pub fn from_args(args: &Args) -> anyhow::Result<Option<Self>> {
+        let (cert_path, key_path) = match (&args.tls_cert, &args.tls_key) {
+            (Some(cert), Some(key)) => (cert.clone(), key.clone()),
+            (None, None) => {
+                if args.tls_client_ca.is_some() || args.http_redirect_port.is_some() {
+                    bail!(
+                        "--tls-client-ca and --http-redirect-port require --tls-cert and --tls-key"
+                    );
+                }
+                return Ok(None);
+            }
+            _ => bail!("--tls-cert and --tls-key must be set together"),
+        };
+
+        if args.http_redirect_port == Some(args.listening_port) {
+            bail!("--http-redirect-port must differ from --listening-port");
+        }
+
+        let files = TlsFiles {
+            cert_path,
+            key_path,
+            client_ca_path: args.tls_client_ca.clone(),
+        };
+        let modified = files.modified_times();
+        let config = files.load()?;
+
+        Ok(Some(Self {
+            files,
+            current: RwLock::new(Arc::new(config)),
+            modified: Mutex::new(modified),
+        }))
+    }
+
+    fn acceptor(&self) -> TlsAcceptor {
+        let config = self.current.read().unwrap_or_else(|e| e.into_inner());
+        TlsAcceptor::from(config.clone())
+    }
+
+    /// Reloads the configuration if any of the files changed. A broken file keeps the previous
+    /// configuration in place.
+    fn reload_if_changed(&self) {
+        let modified = self.files.modified_times();
+        {
+            let mut last = self.modified.lock().unwrap_or_else(|e| e.into_inner());
+            if *last == modified {
+                return;
+            }
+            *last = modified;
+        }
+
+        match self.files.load() {
+            Ok(config) => {
+                *self.current.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(config);
+                tracing::info!(
+                    "Reloaded TLS certificate from {}",
+                    self.files.cert_path.display()
+                );
+            }
+            Err(e) => tracing::error!("Could not reload TLS certificate: {:#}", e),
+        }
+    }
+}
+
+impl TlsFiles {
+    fn paths(&self) -> impl Iterator<Item = &Path> {
+        [
+            Some(&self.cert_path),
+            Some(&self.key_path),
+            self.client_ca_path.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        .map(PathBuf::as_path)
+    }
+
+    fn modified_times(&self) -> Vec<Option<SystemTime>> {
+        self.paths()
+            .map(|p| std::fs::metadata(p).and_then(|m| m.modified()).ok())
+            .collect()
+    }
+
+    fn load(&self) -> anyhow::Result<ServerConfig> {
+        let certs = CertificateDer::pem_file_iter(&self.cert_path)
+            .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+            .with_context(|| format!("reading certificates from {}", self.cert_path.display()))?;
+        if certs.is_empty() {
+            bail!("no certificates in {}", self.cert_path.display());
+        }
+
+        let key = PrivateKeyDer::from_pem_file(&self.key_path)
+            .with_context(|| format!("reading private key from {}", self.key_path.display()))?;
+
+        let builder = ServerConfig::builder_with_provider(provider())
+            .with_safe_default_protocol_versions()?;
+
+        let builder = match &self.client_ca_path {
+            Some(path) => {
+                let mut roots = RootCertStore::empty();
+                for cert in CertificateDer::pem_file_iter(path)
+                    .with_context(|| format!("reading client CA from {}", path.display()))?
+                {
+                    roots.add(cert?)?;
+                }
+
+                // Clients without a certificate may still connect; only admin routes require one.
+                let verifier =
+                    WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider())
+                        .allow_unauthenticated()
+                        .build()?;
+                builder.with_client_cert_verifier(verifier)
+            }
+            None => builder.with_no_client_auth(),
+        };
+
+        let mut config = builder.with_single_cert(certs, key)?;
+        config.alpn_protocols = vec![b"http/1.1".to_vec()];
+
+        Ok(config)
+    }
+}
+
+fn provider() -> Arc<rustls::crypto::CryptoProvider> {
+    Arc::new(rustls::crypto::ring::default_provider())
+}
+
+/// Periodically checks the certificate, key and client CA files for changes.
+pub fn spawn_reloader(tls: Arc<TlsConfig>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.tick().await;
+
+        loop {
+            ticker.tick().await;
+            tls.reload_if_changed();
+        }
+    });
+}
+
+/// Serves `app` over TLS. Like `into_make_service_with_connect_info`, each request carries the
+/// peer address as `ConnectInfo<SocketAddr>`, plus the `ClientCertificate` if one was presented.
+///
+/// Like axum's `with_graceful_shutdown`, once `shutdown` resolves no new connections are
+/// accepted and the function returns after the open ones finished their current request.
+pub async fn serve(
+    listener: TcpListener,
+    app: Router,
+    tls: Arc<TlsConfig>,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) {
+    let shutdown = shutdown.shared();
+    let mut connections = JoinSet::new();
+
+    loop {
+        let (stream, peer) = tokio::select! {
+            conn = listener.accept() => match conn {
+                Ok(conn) => conn,
+                Err(e) => {
+                    tracing::warn!("Could not accept connection: {}", e);
+                    continue;
+                }
+            },
+            _ = shutdown.clone() => break,
+        };
+
+        // Forget connections that already closed.
+        while connections.try_join_next().is_some() {}
+
+        let acceptor = tls.acceptor();
+        let app = app.clone();
+        let shutdown = shutdown.clone();
+
+        connections.spawn(async move {
+            let stream =
+                match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                    Ok(Ok(stream)) => stream,
+                    Ok(Err(e)) => {
+                        tracing::debug!("TLS handshake with {} failed: {}", peer, e);
+                        return;
+                    }
+                    Err(_) => {
+                        tracing::debug!("TLS handshake with {} timed out", peer);
+                        return;
+                    }
+                };
+
+            let client_certificate = stream
+                .get_ref()
+                .1
+                .peer_certificates()
+                .and_then(|certs| certs.first())
+                .map(|cert| ClientCertificate {
+                    fingerprint: hex::encode(Sha256::digest(cert)),
+                });
+
+            let service = hyper::service::service_fn(move |mut req: hyper::Request<Incoming>| {
+                req.extensions_mut().insert(ConnectInfo(peer));
+                if let Some(cert) = &client_certificate {
+                    req.extensions_mut().insert(cert.clone());
+                }
+                app.clone().oneshot(req.map(axum::body::Body::new))
+            });
+
+            let conn = hyper::server::conn::http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .with_upgrades();
+            tokio::pin!(conn);
+
+            let result = tokio::select! {
+                result = conn.as_mut() => result,
+                _ = shutdown => {
+                    // Finishes the request in progress, then closes the connection.
+                    conn.as_mut().graceful_shutdown();
+                    conn.await
+                }
+            };
+
+            if let Err(e) = result {
+                tracing::debug!("Connection from {} closed: {}", peer, e);
+            }
+        });
+    }
+
+    drop(listener);
+    tracing::info!(
+        "Shutdown: HTTPS listener closed, {} connections open",
+        connections.len()
+    );
+    while connections.join_next().await.is_some() {}
+}
+
+/// Plain HTTP listener that redirects every request to the HTTPS listener.
+pub async fn serve_redirect(
+    port: u16,
+    https_port: u16,
+    public_base_url: Option<String>,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) {
+    let app = Router::new().fallback(move |headers: HeaderMap, uri: Uri| async move {
+        redirect_to_https(&headers, &uri, https_port, public_base_url.as_deref())
+    });
+
+    let addr = SocketAddr::from((Ipv4Addr::UNSPECIFIED, port));
+    let listener = match TcpListener::bind(&addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!(
+                "Could not bind HTTP redirect listener on port {}: {}",
+                port,
+                e
+            );
+            return;
+        }
+    };
+
+    tracing::info!("Redirecting HTTP on port {} to HTTPS", port);
+    if let Err(e) = axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown)
+        .await
+    {
+        tracing::error!("HTTP redirect listener failed: {}", e);
+    }
+}
+
+fn redirect_to_https(
+    headers: &HeaderMap,
+    uri: &Uri,
+    https_port: u16,
+    public_base_url: Option<&str>,
+) -> Response {
+    let path = uri.path_and_query().map_or("/", |p| p.as_str());
+
+    if let Some(base) = public_base_url {
+        return Redirect::permanent(&format!("{base}{path}")).into_response();
+    }
+
+    let Some(host) = headers
+        .get(header::HOST)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| Authority::from_str(h).ok())
+    else {
+        return (StatusCode::BAD_REQUEST, "missing Host header").into_response();
+    };
+
+    let location = if https_port == 443 {
+        format!("https://{}{}", host.host(), path)
+    } else {
+        format!("https://{}:{}{}", host.host(), https_port, path)
+    };
+
+    Redirect::permanent(&location).into_response()
+}
@@ -0,0 +1,359 @@
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+
+use anyhow::Context as AnyhowContext;
+use axum::http::HeaderMap;
+use ipnet::IpNet;
+
+use crate::core::cli::Args;
+
+/// Reverse proxies whose forwarding headers (`Forwarded`, `X-Forwarded-*`, `X-Real-IP`) are
+/// honoured. Requests from any other peer are treated as coming directly from the client.
+pub struct TrustedProxies {
+    nets: Vec<IpNet>,
+}
+
+/// What the proxies in front of the gateway reported about the original request.
+#[derive(Debug, Default)]
+pub struct ForwardedRequest {
+    /// Client address (without port).
+    pub client: String,
+    /// Host the client connected to, if a trusted proxy reported one.
+    pub host: Option<String>,
+    /// Scheme the client used (`http` or `https`), if a trusted proxy reported one.
+    pub proto: Option<String>,
+}
+
+/// One element of an RFC 7239 `Forwarded` header, e.g. `for=192.0.2.60;proto=https;host=a.b`.
+#[derive(Debug, Default)]
+struct ForwardedElement {
+    for_node: Option<String>,
+    host: Option<String>,
+    proto: Option<String>,
+}
+
+impl TrustedProxies {
+    pub fn from_args(args: &Args) -> anyhow::Result<Self> {
+        let nets = args
+            .trusted_proxies
+            .iter()
+            .map(|n| {
+                // Accept bare addresses as single-host networks.
+                IpNet::from_str(n)
+                    .or_else(|_| IpAddr::from_str(n).map(IpNet::from))
+                    .with_context(|| format!("invalid trusted proxy: {n}"))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(Self { nets })
+    }
+
+    pub fn is_trusted(&self, addr: IpAddr) -> bool {
+        let addr = match addr {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(addr),
+            v4 => v4,
+        };
+
+        self.nets.iter().any(|n| n.contains(&addr))
+    }
+
+    /// Client address of a request. Forwarding headers are only honoured when the peer is a
+    /// trusted proxy, so clients connecting directly can't spoof their address.
+    pub fn client_addr(&self, headers: &HeaderMap, peer: Option<SocketAddr>) -> String {
+        self.resolve(headers, peer).client
+    }
+
+    /// Resolves the original client address, host and scheme of a request.
+    ///
+    /// `Forwarded` (RFC 7239) takes precedence over the `X-Forwarded-*` headers. Proxies append
+    /// to these lists, so they are walked from the right: the first hop that isn't one of our
+    /// proxies is the client, and the element describing it carries the host and scheme it used.
+    pub fn resolve(&self, headers: &HeaderMap, peer: Option<SocketAddr>) -> ForwardedRequest {
+        let Some(peer) = peer else {
+            return ForwardedRequest {
+                client: "unknown".to_string(),
+                ..Default::default()
+            };
+        };
+
+        if !self.is_trusted(peer.ip()) {
+            return ForwardedRequest {
+                client: peer.ip().to_string(),
+                ..Default::default()
+            };
+        }
+
+        let elements = parse_forwarded(headers);
+        if let Some(index) = self.client_hop(&elements, |e| e.for_node.as_deref()) {
+            let element = &elements[index];
+            return ForwardedRequest {
+                client: element
+                    .for_node
+                    .as_deref()
+                    .map(|node| node_ip(node).map_or(node.to_string(), |ip| ip.to_string()))
+                    .unwrap_or_else(|| peer.ip().to_string()),
+                host: element.host.clone(),
+                proto: element.proto.clone(),
+            };
+        }
+
+        let hops = header_list(headers, "x-forwarded-for");
+        let client_hop = self.client_hop(&hops, |h| Some(h.as_str()));
+        let client = client_hop
+            .map(|index| hops[index].clone())
+            .or_else(|| header_list(headers, "x-real-ip").into_iter().next())
+            .unwrap_or_else(|| peer.ip().to_string());
+
+        // The host and scheme are taken from the proxy that appended the client's hop; values
+        // further left were sent by the client.
+        let from_right = client_hop.map_or(0, |index| hops.len() - 1 - index);
+        ForwardedRequest {
+            client,
+            host: value_at_hop(header_list(headers, "x-forwarded-host"), from_right),
+            proto: value_at_hop(header_list(headers, "x-forwarded-proto"), from_right),
+        }
+    }
+
+    /// Index of the rightmost hop that isn't a trusted proxy, or of the leftmost one if all of
+    /// them are.
+    fn client_hop<T>(&self, hops: &[T], node: impl Fn(&T) -> Option<&str>) -> Option<usize> {
+        hops.iter()
+            .rposition(|hop| {
+                node(hop)
+                    .and_then(node_ip)
+                    .is_none_or(|ip| !self.is_trusted(ip))
+            })
+            .or((!hops.is_empty()).then_some(0))
+    }
+}
+
+/// Value of an `X-Forwarded-Host`/`-Proto` list at `from_right` places from its end, i.e. the
+/// one appended together with the matching `X-Forwarded-For` hop. Proxies that overwrite the
+/// header instead of appending leave fewer values; the rightmost one is used then.
+fn value_at_hop(mut values: Vec<String>, from_right: usize) -> Option<String> {
+    let last = values.len().checked_sub(1)?;
+    let index = last.checked_sub(from_right).unwrap_or(last);
+    Some(values.swap_remove(index))
+}
+
+/// Comma-separated values of every occurrence of a header.
+fn header_list(headers: &HeaderMap, name: &str) -> Vec<String> {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+fn parse_forwarded(headers: &HeaderMap) -> Vec<ForwardedElement> {
+    header_list(headers, "forwarded")
+        .iter()
+        .map(|element| {
+            let mut parsed = ForwardedElement::default();
+
+            for pair in element.split(';') {
+                let Some((key, value)) = pair.split_once('=') else {
+                    continue;
+                };
+                let value = value.trim().trim_matches('"').to_string();
+
+                match key.trim().to_ascii_lowercase().as_str() {
+                    "for" => parsed.for_node = Some(value),
+                    "host" => parsed.host = Some(value),
+                    "proto" => parsed.proto = Some(value.to_ascii_lowercase()),
+                    _ => {}
+                }
+            }
+
+            parsed
+        })
+        .collect()
+}
+
+/// IP address of a forwarded node such as `192.0.2.43`, `192.0.2.43:47011` or
+/// `[2001:db8::17]:4711`. Obfuscated identifiers (`unknown`, `_hidden`) yield `None`.
+fn node_ip(node: &str) -> Option<IpAddr> {
+    IpAddr::from_str(node)
+        .ok()
+        .or_else(|| SocketAddr::from_str(node).ok().map(|a| a.ip()))
+        .or_else(|| {
+            node.strip_prefix('[')
+                .and_then(|rest| rest.split_once(']'))
+                .and_then(|(ip, _)| IpAddr::from_str(ip).ok())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+    use clap::Parser;
+
+    use super::*;
+
+    fn proxies(nets: &str) -> TrustedProxies {
+        let args = Args::parse_from(["ln-server", &format!("--trusted-proxies={nets}")]);
+        TrustedProxies::from_args(&args).unwrap()
+    }
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(*name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    fn resolve(pairs: &[(&'static str, &str)], peer: &str) -> ForwardedRequest {
+        proxies("10.0.0.0/8,::1").resolve(&headers(pairs), Some(peer.parse().unwrap()))
+    }
+
+    #[test]
+    fn accepts_networks_and_plain_addresses() {
+        let proxies = proxies("10.0.0.0/8,192.0.2.1,::1");
+        for (addr, trusted) in [
+            ("10.1.2.3", true),
+            ("192.0.2.1", true),
+            ("192.0.2.2", false),
+            ("::1", true),
+            ("::ffff:10.0.0.1", true),
+            ("2001:db8::1", false),
+        ] {
+            assert_eq!(proxies.is_trusted(addr.parse().unwrap()), trusted, "{addr}");
+        }
+
+        let args = Args::parse_from(["ln-server", "--trusted-proxies=not-an-ip"]);
+        assert!(TrustedProxies::from_args(&args).is_err());
+    }
+
+    #[test]
+    fn ignores_headers_from_untrusted_peers() {
+        let forwarded = resolve(
+            &[
+                ("x-forwarded-for", "198.51.100.1"),
+                ("x-forwarded-host", "evil.example"),
+                ("forwarded", "for=198.51.100.1;host=evil.example"),
+            ],
+            "203.0.113.9:4000",
+        );
+        assert_eq!(forwarded.client, "203.0.113.9");
+        assert_eq!(forwarded.host, None);
+        assert_eq!(forwarded.proto, None);
+    }
+
+    #[test]
+    fn takes_the_rightmost_untrusted_forwarded_for_hop() {
+        let cases = [
+            // Spoofed entry on the left, real client appended by our proxy.
+            ("1.1.1.1, 198.51.100.7", "198.51.100.7"),
+            // Trusted proxies between the client and us are skipped.
+            ("1.1.1.1, 198.51.100.7, 10.0.0.2, 10.0.0.3", "198.51.100.7"),
+            // Only trusted hops: the leftmost one.
+            ("10.0.0.5, 10.0.0.6", "10.0.0.5"),
+            ("2001:db8::7", "2001:db8::7"),
+        ];
+        for (xff, client) in cases {
+            let forwarded = resolve(&[("x-forwarded-for", xff)], "10.0.0.1:4000");
+            assert_eq!(forwarded.client, client, "{xff}");
+        }
+
+        let split = resolve(
+            &[
+                ("x-forwarded-for", "1.1.1.1"),
+                ("x-forwarded-for", "198.51.100.7"),
+            ],
+            "10.0.0.1:4000",
+        );
+        assert_eq!(
+            split.client, "198.51.100.7",
+            "repeated headers form one list"
+        );
+
+        let real_ip = resolve(&[("x-real-ip", "198.51.100.8")], "10.0.0.1:4000");
+        assert_eq!(real_ip.client, "198.51.100.8");
+
+        let direct = resolve(&[], "10.0.0.1:4000");
+        assert_eq!(direct.client, "10.0.0.1");
+    }
+
+    #[test]
+    fn ignores_spoofed_forwarded_host_and_proto() {
+        // The client sent its own values; our proxy appended the real ones.
+        let forwarded = resolve(
+            &[
+                ("x-forwarded-for", "198.51.100.7"),
+                ("x-forwarded-host", "evil.example, pay.example.com"),
+                ("x-forwarded-proto", "http, https"),
+            ],
+            "10.0.0.1:4000",
+        );
+        assert_eq!(forwarded.client, "198.51.100.7");
+        assert_eq!(forwarded.host.as_deref(), Some("pay.example.com"));
+        assert_eq!(forwarded.proto.as_deref(), Some("https"));
+
+        // Two appending proxies: the client's hop is second from the right.
+        let forwarded = resolve(
+            &[
+                ("x-forwarded-for", "198.51.100.7, 10.0.0.2"),
+                (
+                    "x-forwarded-host",
+                    "evil.example, pay.example.com, internal.lan",
+                ),
+            ],
+            "10.0.0.1:4000",
+        );
+        assert_eq!(forwarded.host.as_deref(), Some("pay.example.com"));
+
+        // A proxy that overwrites the header leaves a single value.
+        let forwarded = resolve(
+            &[
+                ("x-forwarded-for", "198.51.100.7, 10.0.0.2"),
+                ("x-forwarded-host", "pay.example.com"),
+            ],
+            "10.0.0.1:4000",
+        );
+        assert_eq!(forwarded.host.as_deref(), Some("pay.example.com"));
+    }
+
+    #[test]
+    fn parses_forwarded_elements() {
+        let forwarded = resolve(
+            &[
+                (
+                    "forwarded",
+                    r#"for=1.1.1.1;host=evil.example, for="[2001:db8::17]:4711";proto=HTTPS;host="pay.example.com""#,
+                ),
+                ("forwarded", "for=10.0.0.2;host=internal.lan"),
+                // Forwarded wins over X-Forwarded-*.
+                ("x-forwarded-for", "198.51.100.9"),
+            ],
+            "10.0.0.1:4000",
+        );
+        assert_eq!(forwarded.client, "2001:db8::17");
+        assert_eq!(forwarded.host.as_deref(), Some("pay.example.com"));
+        assert_eq!(forwarded.proto.as_deref(), Some("https"));
+
+        // Obfuscated identifiers are kept as they are.
+        let forwarded = resolve(&[("forwarded", "for=_hidden;proto=http")], "10.0.0.1:4000");
+        assert_eq!(forwarded.client, "_hidden");
+        assert_eq!(forwarded.proto.as_deref(), Some("http"));
+    }
+
+    #[test]
+    fn parses_node_addresses() {
+        for (node, ip) in [
+            ("192.0.2.43", Some("192.0.2.43")),
+            ("192.0.2.43:47011", Some("192.0.2.43")),
+            ("[2001:db8::17]:4711", Some("2001:db8::17")),
+            ("[2001:db8::17]", Some("2001:db8::17")),
+            ("2001:db8::17", Some("2001:db8::17")),
+            ("unknown", None),
+            ("_hidden", None),
+        ] {
+            assert_eq!(node_ip(node), ip.map(|ip| ip.parse().unwrap()), "{node}");
+        }
+    }
+}
@@ -9,21 +9,81 @@ pub fn gen_k1_as_string() -> String {
         .collect::<String>()
 }
 
+use std::net::SocketAddr;
+use std::path::Path;
 use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use axum::extract::Request;
-use axum::http::{HeaderMap, header, uri::Authority};
+use anyhow::{Context as AnyhowContext, bail};
+use axum::Json;
+use axum::extract::{ConnectInfo, Request};
+use axum::http::{HeaderMap, StatusCode, Uri, header, uri::Authority};
+use axum::response::{IntoResponse, Response};
 use cln_rpc::model::responses::GetinfoResponse;
 
-pub fn extract_request_host(request: &Request, info: &GetinfoResponse) -> String {
+use crate::context::Context;
+use crate::core::onion::OnionRequest;
+
+/// Address of the peer that opened the connection, as recorded by `ConnectInfo`.
+pub fn peer_addr(request: &Request) -> Option<SocketAddr> {
     request
-        .headers()
-        .get(header::HOST)
-        .and_then(|value| value.to_str().ok())
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|c| c.0)
+}
+
+/// Validates `SERVER_PUBLIC_BASE_URL`: an absolute http(s) URL without query or fragment.
+pub fn parse_public_base_url(raw: &str) -> anyhow::Result<Uri> {
+    let uri = Uri::from_str(raw).with_context(|| format!("invalid public base URL: {raw}"))?;
+
+    if !matches!(uri.scheme_str(), Some("http" | "https")) {
+        bail!("public base URL must start with http:// or https://: {raw}");
+    }
+    if uri.authority().is_none() {
+        bail!("public base URL must include a host: {raw}");
+    }
+    if uri.query().is_some() || raw.contains('#') {
+        bail!("public base URL must not have a query or fragment: {raw}");
+    }
+
+    Ok(uri)
+}
+
+/// Onion host name, if the request came in through the onion listener.
+fn onion_address<'a>(request: &Request, state: &'a Context) -> Option<&'a str> {
+    request.extensions().get::<OnionRequest>()?;
+    state.args.onion_address.as_deref()
+}
+
+/// Host clients use to reach the gateway.
+///
+/// Requests through the onion listener get the onion address. Otherwise uses
+/// `SERVER_PUBLIC_BASE_URL` when set. Otherwise takes the host reported by a trusted
+/// proxy, then the `Host` header, then the addresses CLN announces.
+pub fn extract_request_host(request: &Request, state: &Context, info: &GetinfoResponse) -> String {
+    if let Some(onion) = onion_address(request, state) {
+        return onion.to_owned();
+    }
+
+    if let Some(url) = &state.args.public_base_url
+        && let Some(authority) = parse_public_base_url(url)
+            .ok()
+            .and_then(|u| u.into_parts().authority)
+    {
+        return authority.host().to_owned();
+    }
+
+    let forwarded = state
+        .trusted_proxies
+        .resolve(request.headers(), peer_addr(request));
+
+    forwarded
+        .host
+        .or_else(|| header_string(request.headers(), header::HOST.as_str()))
         .map(|s| {
-            Authority::from_str(s)
+            Authority::from_str(&s)
                 .map(|authority| authority.host().to_owned())
-                .unwrap_or_else(|_| s.to_owned())
+                .unwrap_or(s)
         })
         // If no Host header, try to get from cln getinfo response in the binding or address fields
         .or_else(|| {
@@ -47,17 +107,43 @@ fn header_string(headers: &HeaderMap, name: &str) -> Option<String> {
         .map(str::to_owned)
 }
 
-/// Best-effort base URL builder for endpoints that must return a callback URL.
+/// Base URL for endpoints that must return a callback URL.
 ///
-/// Prefers `X-Forwarded-Proto` and `X-Forwarded-Host` (when behind a reverse proxy),
-/// falls back to `Host`, and finally to the provided `fallback_host`.
-pub fn request_base_url(request: &Request, fallback_host: &str, listening_port: u16) -> String {
-    let headers = request.headers();
-    let proto = header_string(headers, "x-forwarded-proto").unwrap_or_else(|| "http".to_string());
-
-    let forwarded =
-        header_string(headers, "x-forwarded-host").or_else(|| header_string(headers, "host"));
+/// Requests through the onion listener get `http://<onion>`; LNURL allows plain HTTP there. Of
+/// the routes building callback URLs, only `/lnurl-auth-request` is served on that listener.
+/// Otherwise `SERVER_PUBLIC_BASE_URL` wins when set. Otherwise the scheme and host come from the
+/// `Forwarded` / `X-Forwarded-*` headers of a trusted proxy, then from `Host`, and finally from
+/// `fallback_host`.
+pub fn request_base_url(request: &Request, state: &Context, fallback_host: &str) -> String {
+    if let Some(onion) = onion_address(request, state) {
+        return match state.args.onion_virtual_port {
+            80 => format!("http://{}", onion),
+            port => format!("http://{}:{}", onion, port),
+        };
+    }
+
+    if let Some(url) = &state.args.public_base_url {
+        return url.clone();
+    }
+
+    let listening_port = state.args.listening_port;
+    let forwarded = state
+        .trusted_proxies
+        .resolve(request.headers(), peer_addr(request));
+
+    let default_proto = if state.args.tls_cert.is_some() {
+        "https"
+    } else {
+        "http"
+    };
+    let proto = forwarded
+        .proto
+        .filter(|p| p == "http" || p == "https")
+        .unwrap_or_else(|| default_proto.to_string());
+
     let authority = forwarded
+        .host
+        .or_else(|| header_string(request.headers(), header::HOST.as_str()))
         .as_deref()
         .and_then(|raw| Authority::from_str(raw).ok())
         .unwrap_or_else(|| {
@@ -74,3 +160,72 @@ pub fn request_base_url(request: &Request, fallback_host: &str, listening_port:
 
     format!("{}://{}", proto, authority)
 }
+
+/// Current Unix time in milliseconds.
+pub fn unix_time_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// Maps the `network` of CLN `getinfo`; testnet4 shares testnet's address format.
+pub fn address_network(network: &str) -> Option<bitcoin::Network> {
+    match network {
+        "bitcoin" => Some(bitcoin::Network::Bitcoin),
+        "testnet" | "testnet4" => Some(bitcoin::Network::Testnet),
+        "signet" => Some(bitcoin::Network::Signet),
+        "regtest" => Some(bitcoin::Network::Regtest),
+        _ => None,
+    }
+}
+
+/// Formats a Unix time in milliseconds as an RFC 3339 UTC timestamp
+/// (`2025-01-01T00:00:00.000Z`).
+pub fn rfc3339(ms: u64) -> String {
+    let secs = ms / 1_000;
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+
+    // Civil date from days since the epoch (Howard Hinnant's algorithm).
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        rem / 3_600,
+        rem % 3_600 / 60,
+        rem % 60,
+        ms % 1_000
+    )
+}
+
+/// Writes `contents` to a temporary file and renames it over `path`, so readers never observe
+/// a truncated file. Creates the parent directory if needed.
+pub async fn write_file_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+
+    let tmp = path.with_extension("tmp");
+    tokio::fs::write(&tmp, contents).await?;
+    tokio::fs::rename(&tmp, path).await
+}
+
+/// JSON error body in the same shape as route errors, for use by middlewares.
+pub fn json_error(status: StatusCode, message: impl Into<String>) -> Response {
+    let json = serde_json::json!({
+        "status": status.as_u16(),
+        "error": message.into(),
+    });
+    (status, Json(json)).into_response()
+}
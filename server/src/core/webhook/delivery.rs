@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookDeliveryStatus {
+    Pending,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WebhookDelivery {
+    /// Unique delivery identifier (also sent as `X-Webhook-Id`).
+    pub id: String,
+    /// Endpoint the payload is delivered to.
+    pub endpoint: String,
+    /// Event type (e.g. invoice.settled).
+    pub event_type: String,
+    /// JSON body POSTed to the endpoint.
+    pub payload: Value,
+    /// Pending deliveries are retried; failed ones exhausted their attempts.
+    pub status: WebhookDeliveryStatus,
+    /// Number of attempts made so far.
+    pub attempts: u32,
+    /// Unix timestamp in milliseconds when the delivery was created.
+    pub created_ms: u64,
+    /// Unix timestamp in milliseconds of the next attempt (pending deliveries only).
+    pub next_attempt_ms: u64,
+    /// Error reported by the last failed attempt.
+    pub last_error: Option<String>,
+}
@@ -0,0 +1,352 @@
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use axum::http::header;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use tokio::sync::{Mutex, Notify};
+
+use crate::context::Context;
+use crate::core::cli::Args;
+use crate::core::utils;
+use crate::core::webhook::delivery::{WebhookDelivery, WebhookDeliveryStatus};
+use crate::core::webhook::event::WebhookEvent;
+
+const STORE_FILE: &str = "webhook_deliveries.json";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const IDLE_POLL: Duration = Duration::from_secs(30);
+const BASE_BACKOFF_MS: u64 = 5_000;
+const MAX_BACKOFF_MS: u64 = 60 * 60 * 1_000;
+// Changes within this window are written to disk together.
+const PERSIST_DELAY: Duration = Duration::from_secs(1);
+
+/// Persistent outgoing webhook queue.
+///
+/// Every event is turned into one delivery per configured endpoint. Deliveries are stored in
+/// the data directory until they succeed, so pending retries survive restarts. Deliveries that
+/// exhaust their attempts are kept as `failed` until an admin redelivers them. The queue holds
+/// at most `--webhook-max-queue` deliveries and is written to disk at most once per second.
+pub struct WebhookDispatcher {
+    endpoints: Vec<String>,
+    secret: Option<String>,
+    max_attempts: u32,
+    max_queue: usize,
+    store_path: PathBuf,
+    http: reqwest::Client,
+    deliveries: Mutex<Vec<WebhookDelivery>>,
+    notify: Notify,
+    dirty: AtomicBool,
+    persist_notify: Notify,
+}
+
+impl WebhookDispatcher {
+    pub fn new(args: &Args) -> Self {
+        let store_path = args.data_dir.join(STORE_FILE);
+        let deliveries = match std::fs::read(&store_path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring unreadable webhook queue {:?}: {}", store_path, e);
+                Vec::new()
+            }),
+            Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(e) => {
+                tracing::warn!("Could not read webhook queue {:?}: {}", store_path, e);
+                Vec::new()
+            }
+        };
+
+        if !args.webhook_urls.is_empty() && args.webhook_secret.is_none() {
+            tracing::warn!("Webhook secret not configured, payloads will not be signed");
+        }
+
+        Self {
+            endpoints: args.webhook_urls.clone(),
+            secret: args.webhook_secret.clone(),
+            max_attempts: args.webhook_max_attempts.max(1),
+            max_queue: args.webhook_max_queue.max(1),
+            store_path,
+            http: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .expect("valid webhook HTTP client"),
+            deliveries: Mutex::new(deliveries),
+            notify: Notify::new(),
+            dirty: AtomicBool::new(false),
+            persist_notify: Notify::new(),
+        }
+    }
+
+    /// Returns true if at least one webhook endpoint is configured.
+    pub fn is_enabled(&self) -> bool {
+        !self.endpoints.is_empty()
+    }
+
+    /// Queues `event` for delivery to every configured endpoint.
+    pub async fn enqueue(&self, event: WebhookEvent) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let event_type = event.event_type();
+        let body = match serde_json::to_value(&event) {
+            Ok(body) => body,
+            Err(e) => {
+                tracing::error!("Could not serialize {} webhook: {}", event_type, e);
+                return;
+            }
+        };
+
+        let now = utils::unix_time_ms();
+        let mut deliveries = self.deliveries.lock().await;
+        for endpoint in &self.endpoints {
+            let id = uuid::Uuid::new_v4().to_string();
+
+            let mut payload = body.clone();
+            if let Some(obj) = payload.as_object_mut() {
+                obj.insert("id".to_string(), id.clone().into());
+                obj.insert("created_ms".to_string(), now.into());
+            }
+
+            deliveries.push(WebhookDelivery {
+                id,
+                endpoint: endpoint.clone(),
+                event_type: event_type.to_string(),
+                payload,
+                status: WebhookDeliveryStatus::Pending,
+                attempts: 0,
+                created_ms: now,
+                next_attempt_ms: now,
+                last_error: None,
+            });
+        }
+        self.trim(&mut deliveries);
+        drop(deliveries);
+
+        self.mark_dirty();
+
+        self.notify.notify_one();
+    }
+
+    /// Lists queued deliveries, optionally filtered by status.
+    pub async fn list(&self, status: Option<WebhookDeliveryStatus>) -> Vec<WebhookDelivery> {
+        let deliveries = self.deliveries.lock().await;
+        deliveries
+            .iter()
+            .filter(|d| status.is_none_or(|s| s == d.status))
+            .cloned()
+            .collect()
+    }
+
+    /// Schedules a queued delivery for an immediate retry with a fresh attempt budget.
+    pub async fn redeliver(&self, id: &str) -> Option<WebhookDelivery> {
+        let mut deliveries = self.deliveries.lock().await;
+        let delivery = deliveries.iter_mut().find(|d| d.id == id)?;
+
+        delivery.status = WebhookDeliveryStatus::Pending;
+        delivery.attempts = 0;
+        delivery.next_attempt_ms = utils::unix_time_ms();
+        let delivery = delivery.clone();
+        drop(deliveries);
+
+        self.mark_dirty();
+
+        self.notify.notify_one();
+        Some(delivery)
+    }
+
+    /// Writes the queue to disk one last time on shutdown. A delivery interrupted mid-request
+    /// is still pending and will be sent again on the next start.
+    pub async fn flush(&self) {
+        self.dirty.store(false, Ordering::SeqCst);
+        let deliveries = self.deliveries.lock().await;
+        self.persist(&deliveries).await;
+    }
+
+    /// Drops the oldest failed deliveries, then the oldest pending ones, beyond `max_queue`.
+    fn trim(&self, deliveries: &mut Vec<WebhookDelivery>) {
+        let excess = deliveries.len().saturating_sub(self.max_queue);
+        if excess == 0 {
+            return;
+        }
+
+        // Deliveries are appended in creation order, so the first ones are the oldest.
+        let mut remaining = excess;
+        for status in [
+            WebhookDeliveryStatus::Failed,
+            WebhookDeliveryStatus::Pending,
+        ] {
+            deliveries.retain(|d| {
+                if remaining > 0 && d.status == status {
+                    remaining -= 1;
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+
+        tracing::warn!(
+            "Webhook queue full ({} deliveries), dropped the {} oldest",
+            self.max_queue,
+            excess
+        );
+    }
+
+    fn mark_dirty(&self) {
+        self.dirty.store(true, Ordering::SeqCst);
+        self.persist_notify.notify_one();
+    }
+
+    /// Writes the queue shortly after it changed, coalescing bursts of events into one write.
+    async fn run_persister(&self) {
+        loop {
+            self.persist_notify.notified().await;
+            tokio::time::sleep(PERSIST_DELAY).await;
+
+            if self.dirty.swap(false, Ordering::SeqCst) {
+                let snapshot = self.deliveries.lock().await.clone();
+                self.persist(&snapshot).await;
+            }
+        }
+    }
+
+    async fn run(&self) {
+        loop {
+            let now = utils::unix_time_ms();
+            let due: Vec<WebhookDelivery> = {
+                let deliveries = self.deliveries.lock().await;
+                deliveries
+                    .iter()
+                    .filter(|d| d.status == WebhookDeliveryStatus::Pending)
+                    .filter(|d| d.next_attempt_ms <= now)
+                    .cloned()
+                    .collect()
+            };
+
+            for delivery in due {
+                let result = self.send(&delivery).await;
+                self.record_attempt(&delivery.id, result).await;
+            }
+
+            let wait = {
+                let now = utils::unix_time_ms();
+                let deliveries = self.deliveries.lock().await;
+                deliveries
+                    .iter()
+                    .filter(|d| d.status == WebhookDeliveryStatus::Pending)
+                    .map(|d| Duration::from_millis(d.next_attempt_ms.saturating_sub(now)))
+                    .min()
+                    .map_or(IDLE_POLL, |d| d.min(IDLE_POLL))
+            };
+
+            tokio::select! {
+                _ = tokio::time::sleep(wait) => {}
+                _ = self.notify.notified() => {}
+            }
+        }
+    }
+
+    async fn send(&self, delivery: &WebhookDelivery) -> anyhow::Result<()> {
+        let body = serde_json::to_vec(&delivery.payload)?;
+        let timestamp = (utils::unix_time_ms() / 1_000).to_string();
+
+        let mut req = self
+            .http
+            .post(&delivery.endpoint)
+            .header(header::CONTENT_TYPE, "application/json")
+            .header("x-webhook-id", &delivery.id)
+            .header("x-webhook-timestamp", &timestamp);
+
+        if let Some(secret) = &self.secret {
+            req = req.header(
+                "x-webhook-signature",
+                format!("sha256={}", sign(secret, &timestamp, &body)),
+            );
+        }
+
+        let res = req.body(body).send().await?;
+        if !res.status().is_success() {
+            anyhow::bail!("endpoint responded with {}", res.status());
+        }
+
+        Ok(())
+    }
+
+    async fn record_attempt(&self, id: &str, result: anyhow::Result<()>) {
+        let mut deliveries = self.deliveries.lock().await;
+
+        match result {
+            // Delivered payloads are not kept around.
+            Ok(()) => deliveries.retain(|d| d.id != id),
+            Err(e) => {
+                let Some(delivery) = deliveries.iter_mut().find(|d| d.id == id) else {
+                    return;
+                };
+
+                delivery.attempts += 1;
+                delivery.last_error = Some(format!("{:#}", e));
+
+                if delivery.attempts >= self.max_attempts {
+                    delivery.status = WebhookDeliveryStatus::Failed;
+                    tracing::warn!(
+                        "Webhook {} to {} failed after {} attempts: {:#}",
+                        delivery.id,
+                        delivery.endpoint,
+                        delivery.attempts,
+                        e
+                    );
+                } else {
+                    delivery.next_attempt_ms =
+                        utils::unix_time_ms() + backoff_ms(delivery.attempts);
+                }
+            }
+        }
+        drop(deliveries);
+
+        self.mark_dirty();
+    }
+
+    async fn persist(&self, deliveries: &[WebhookDelivery]) {
+        let result = match serde_json::to_vec(deliveries) {
+            Ok(bytes) => utils::write_file_atomic(&self.store_path, &bytes).await,
+            Err(e) => Err(e.into()),
+        };
+
+        if let Err(e) = result {
+            tracing::error!(
+                "Could not persist webhook queue {:?}: {}",
+                self.store_path,
+                e
+            );
+        }
+    }
+}
+
+/// Spawns the background task delivering queued webhooks.
+pub fn spawn_worker(ctx: Arc<Context>) {
+    if !ctx.webhooks.is_enabled() {
+        return;
+    }
+
+    let persister = ctx.clone();
+    tokio::spawn(async move { persister.webhooks.run_persister().await });
+    tokio::spawn(async move { ctx.webhooks.run().await });
+}
+
+/// Exponential backoff: 5s, 10s, 20s, ... capped at one hour.
+fn backoff_ms(attempts: u32) -> u64 {
+    let exp = attempts.saturating_sub(1).min(20);
+    BASE_BACKOFF_MS.saturating_mul(1 << exp).min(MAX_BACKOFF_MS)
+}
+
+/// Hex HMAC-SHA256 of `"{timestamp}.{body}"`, so receivers can reject replayed payloads.
+fn sign(secret: &str, timestamp: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
@@ -0,0 +1,67 @@
+use serde::Serialize;
+
+use crate::core::invoice_events::InvoiceSettledEvent;
+
+/// Gateway events forwarded to the configured webhook endpoints.
+///
+/// Serialized as `{"type": "<event type>", "data": {...}}`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", content = "data")]
+pub enum WebhookEvent {
+    #[serde(rename = "invoice.settled")]
+    InvoiceSettled(InvoiceSettledEvent),
+    #[serde(rename = "withdraw.paid")]
+    WithdrawPaid {
+        txid: String,
+        destination: String,
+        amount_sat: u64,
+    },
+    #[serde(rename = "channel.opened")]
+    ChannelOpened {
+        remote_id: String,
+        channel_id: String,
+        txid: String,
+        amount_sat: u64,
+    },
+    #[serde(rename = "lnurl_auth.completed")]
+    LnUrlAuthCompleted { k1: String, pubkey: String },
+    #[serde(rename = "lsps1.order_completed")]
+    Lsps1OrderCompleted {
+        order_id: String,
+        remote_id: String,
+        channel_id: String,
+        txid: String,
+        lsp_balance_sat: u64,
+        client_balance_sat: u64,
+    },
+    #[serde(rename = "lsps1.order_failed")]
+    Lsps1OrderFailed {
+        order_id: String,
+        reason: String,
+        /// Owed back to the client, to be refunded by hand.
+        refund_sat: u64,
+    },
+    #[serde(rename = "lsps2.channel_opened")]
+    Lsps2ChannelOpened {
+        scid: String,
+        remote_id: String,
+        channel_id: String,
+        txid: String,
+        payment_msat: u64,
+        opening_fee_msat: u64,
+    },
+}
+
+impl WebhookEvent {
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            WebhookEvent::InvoiceSettled(_) => "invoice.settled",
+            WebhookEvent::WithdrawPaid { .. } => "withdraw.paid",
+            WebhookEvent::ChannelOpened { .. } => "channel.opened",
+            WebhookEvent::LnUrlAuthCompleted { .. } => "lnurl_auth.completed",
+            WebhookEvent::Lsps1OrderCompleted { .. } => "lsps1.order_completed",
+            WebhookEvent::Lsps1OrderFailed { .. } => "lsps1.order_failed",
+            WebhookEvent::Lsps2ChannelOpened { .. } => "lsps2.channel_opened",
+        }
+    }
+}
@@ -1,9 +1,10 @@
+use std::sync::Arc;
+use std::time::Duration;
 use std::{net::Ipv4Addr, net::SocketAddr};
 
 use axum::Router;
-use axum::http::Method;
 use tokio::net::TcpListener;
-use tower_http::cors::{Any, CorsLayer};
+use tokio::signal::unix::{SignalKind, signal};
 use tracing_subscriber::EnvFilter;
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
@@ -27,22 +28,42 @@ async fn main() {
     tracing_subscriber::fmt().with_env_filter(env_filter).init();
 
     let args = core::cli::Args::new();
+    if let Some(core::config::Command::Config(command)) = &args.command {
+        std::process::exit(core::config::run(command, &args));
+    }
 
     let addr = SocketAddr::from((Ipv4Addr::UNSPECIFIED, args.listening_port));
+    let onion = match core::onion::OnionService::from_args(&args) {
+        Ok(onion) => onion,
+        Err(e) => {
+            tracing::error!("Invalid onion service configuration: {:#}", e);
+            std::process::exit(2);
+        }
+    };
+    let tls = match core::tls::TlsConfig::from_args(&args) {
+        Ok(tls) => tls.map(Arc::new),
+        Err(e) => {
+            tracing::error!("Invalid TLS configuration: {:#}", e);
+            std::process::exit(2);
+        }
+    };
+
     let ctx = context::Context::new(args.clone()).await;
+    core::invoice_events::spawn_listener(ctx.clone());
+    let lsps1_worker = core::lsps::lsps1::spawn_worker(ctx.clone());
+    core::webhook::dispatcher::spawn_worker(ctx.clone());
+    spawn_reload_on_sighup(ctx.clone());
+    spawn_shutdown_on_signal(ctx.clone());
 
     let swagger =
         SwaggerUi::new("/swagger-ui").url("/api-doc/openapi.json", openapi::ApiDoc::openapi());
 
-    // Only allow CORS in debug mode for easier testing with local frontends. In deployment,
-    // CORS should be handled by a reverse proxy or not needed at all.
-    let cors = if cfg!(debug_assertions) {
-        CorsLayer::new()
-            .allow_origin(Any)
-            .allow_methods([Method::GET, Method::DELETE, Method::OPTIONS])
-            .allow_headers(Any)
-    } else {
-        CorsLayer::new()
+    let cors = match core::cors::layer(&args) {
+        Ok(cors) => cors,
+        Err(e) => {
+            tracing::error!("Invalid CORS configuration: {:#}", e);
+            std::process::exit(2);
+        }
     };
 
     let request_log_middleware = axum::middleware::from_fn_with_state(
@@ -50,17 +71,128 @@ async fn main() {
         ln_server::core::recent_request::middleware::middleware,
     );
 
+    let api_key_middleware = axum::middleware::from_fn_with_state(
+        ctx.clone(),
+        ln_server::core::api_key::middleware::middleware,
+    );
+
+    let rate_limit_middleware = axum::middleware::from_fn_with_state(
+        ctx.clone(),
+        ln_server::core::rate_limit::middleware::middleware,
+    );
+
+    let shutdown_middleware =
+        axum::middleware::from_fn_with_state(ctx.clone(), ln_server::core::shutdown::middleware);
+
+    // Tor clients only reach the public LNURL and LSPS routes; nothing there needs an API key.
+    let onion = onion.map(|onion| {
+        let app = routes::get_onion_router()
+            .with_state(ctx.clone())
+            .layer(shutdown_middleware.clone())
+            .layer(rate_limit_middleware.clone())
+            .layer(request_log_middleware.clone());
+        tokio::spawn(onion.serve(app, ctx.shutdown.wait()))
+    });
+
     let router = Router::new()
         .merge(swagger)
         .merge(routes::get_router())
         .fallback(routes::not_found)
         .with_state(ctx.clone())
+        .layer(shutdown_middleware)
+        .layer(api_key_middleware)
+        .layer(rate_limit_middleware)
         .layer(request_log_middleware)
-        .layer(cors)
-        .into_make_service_with_connect_info::<SocketAddr>(); // Enables ConnectInfo<SocketAddr>
+        .layer(cors);
 
     let listener = TcpListener::bind(&addr).await.unwrap();
 
-    tracing::info!("REST server listening on port {}", args.listening_port);
-    axum::serve(listener, router).await.unwrap();
+    let server = async {
+        match tls {
+            Some(tls) => {
+                core::tls::spawn_reloader(
+                    tls.clone(),
+                    Duration::from_secs(args.tls_reload_interval_secs.max(1)),
+                );
+
+                if let Some(port) = args.http_redirect_port {
+                    tokio::spawn(core::tls::serve_redirect(
+                        port,
+                        args.listening_port,
+                        args.public_base_url.clone(),
+                        ctx.shutdown.wait(),
+                    ));
+                }
+
+                tracing::info!("HTTPS server listening on port {}", args.listening_port);
+                core::tls::serve(listener, router, tls, ctx.shutdown.wait()).await;
+            }
+            None => {
+                tracing::info!("REST server listening on port {}", args.listening_port);
+                // Enables ConnectInfo<SocketAddr>
+                let service = router.into_make_service_with_connect_info::<SocketAddr>();
+                axum::serve(listener, service)
+                    .with_graceful_shutdown(ctx.shutdown.wait())
+                    .await
+                    .unwrap();
+            }
+        }
+
+        if let Some(onion) = onion {
+            let _ = onion.await;
+        }
+
+        // Don't cut an LSPS1 funding short, its order is persisted once the outcome is known.
+        if let Some(worker) = lsps1_worker {
+            let _ = worker.await;
+        }
+    };
+
+    // The server future only returns once shutdown was triggered and the connections drained.
+    let deadline = Duration::from_secs(args.shutdown_timeout_secs);
+    tokio::select! {
+        _ = server => tracing::info!("Shutdown: all requests completed"),
+        _ = ctx.shutdown.deadline(deadline) => tracing::warn!(
+            "Shutdown: deadline of {:?} reached, abandoning {} in-flight requests",
+            deadline,
+            ctx.shutdown.in_flight()
+        ),
+    }
+
+    ctx.flush().await;
+    tracing::info!("Shutdown complete");
+}
+
+// Starts draining on SIGTERM/SIGINT: listeners stop accepting connections and in-flight
+// requests get `--shutdown-timeout-secs` to finish.
+fn spawn_shutdown_on_signal(ctx: Arc<context::Context>) {
+    tokio::spawn(async move {
+        core::shutdown::signal_received().await;
+        tracing::info!(
+            "Shutdown: draining {} in-flight requests (deadline {}s)",
+            ctx.shutdown.in_flight(),
+            ctx.args.shutdown_timeout_secs
+        );
+        ctx.shutdown.trigger();
+    });
+}
+
+// Reloads the runtime config (withdraw limits, spending policy, rate limits) on SIGHUP.
+fn spawn_reload_on_sighup(ctx: Arc<context::Context>) {
+    tokio::spawn(async move {
+        let mut hangup = match signal(SignalKind::hangup()) {
+            Ok(hangup) => hangup,
+            Err(e) => {
+                tracing::warn!("Could not install SIGHUP handler: {}", e);
+                return;
+            }
+        };
+
+        while hangup.recv().await.is_some() {
+            tracing::info!("SIGHUP received, reloading config");
+            if let Err(e) = ctx.reload_runtime_config() {
+                tracing::error!("Config reload failed: {:#}", e);
+            }
+        }
+    });
 }
@@ -0,0 +1,240 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{FromRequestParts, Query, State},
+    http::{HeaderMap, StatusCode, request::Parts},
+};
+use serde::Deserialize;
+
+use crate::{
+    context::Context,
+    core::api_key::ApiKey,
+    core::auth_session::{AuthSession, StreamTicket, bearer_token},
+    core::tls::ClientCertificate,
+    routes::{ApiResponse, api_error},
+};
+
+/// Extractor guarding administrative routes: requires either an API key already checked by the
+/// `api_key` middleware, or a bearer session whose linking key is in `SERVER_ADMIN_LINKING_KEYS`.
+/// Rejects every request when neither admin keys nor API keys are configured.
+pub(super) struct AdminSession;
+
+impl FromRequestParts<Arc<Context>> for AdminSession {
+    type Rejection = ApiResponse<()>;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<Context>,
+    ) -> Result<Self, Self::Rejection> {
+        require_client_certificate(parts, state)?;
+
+        // The middleware only lets a key through when it carries the route's scope.
+        if parts.extensions.get::<ApiKey>().is_some() {
+            return Ok(AdminSession);
+        }
+
+        if !state.auth_sessions.admin_required() && !state.api_keys.is_enabled() {
+            return Err(api_error::build(
+                StatusCode::UNAUTHORIZED,
+                "administrative routes are disabled: configure SERVER_ADMIN_LINKING_KEYS or SERVER_API_KEYS",
+            ));
+        }
+
+        let Some(token) = bearer_token(&parts.headers) else {
+            return Err(api_error::build(
+                StatusCode::UNAUTHORIZED,
+                "admin session or API key required",
+            ));
+        };
+
+        let Some(session) = state.auth_sessions.get(token).await else {
+            return Err(api_error::build(
+                StatusCode::UNAUTHORIZED,
+                "invalid or expired session",
+            ));
+        };
+
+        if !state.auth_sessions.is_admin(&session.linking_key) {
+            return Err(api_error::build(
+                StatusCode::FORBIDDEN,
+                "linking key is not an admin",
+            ));
+        }
+
+        tracing::debug!("Admin request by {}", session.linking_key);
+        Ok(AdminSession)
+    }
+}
+
+/// With mTLS configured, admin routes additionally need a verified client certificate.
+fn require_client_certificate(parts: &Parts, state: &Context) -> Result<(), ApiResponse<()>> {
+    if state.args.tls_client_ca.is_some() {
+        let Some(cert) = parts.extensions.get::<ClientCertificate>() else {
+            return Err(api_error::build(
+                StatusCode::FORBIDDEN,
+                "client certificate required",
+            ));
+        };
+        tracing::debug!("Admin request with client certificate {}", cert.fingerprint);
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct TicketQuery {
+    ticket: Option<String>,
+}
+
+/// Extractor guarding the invoice event streams (`/events`, `/ws`): like `AdminSession`, or a
+/// one-time `?ticket=` from `POST /events/ticket`, since browsers can't set headers there.
+pub(super) struct StreamAccess;
+
+impl FromRequestParts<Arc<Context>> for StreamAccess {
+    type Rejection = ApiResponse<()>;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<Context>,
+    ) -> Result<Self, Self::Rejection> {
+        let ticket = Query::<TicketQuery>::try_from_uri(&parts.uri)
+            .ok()
+            .and_then(|Query(q)| q.ticket);
+        let Some(ticket) = ticket else {
+            return AdminSession::from_request_parts(parts, state)
+                .await
+                .map(|_| StreamAccess);
+        };
+
+        require_client_certificate(parts, state)?;
+        if !state.auth_sessions.redeem_ticket(&ticket).await {
+            return Err(api_error::build(
+                StatusCode::UNAUTHORIZED,
+                "invalid, expired or already used ticket",
+            ));
+        }
+
+        tracing::debug!("Event stream opened with a ticket");
+        Ok(StreamAccess)
+    }
+}
+
+/// Extractor for routes only a service calls with its API key (CLN's `htlc_accepted` plugin). The
+/// `api_key` middleware checked the key's scope; unlike `AdminSession`, admin sessions don't
+/// qualify, and the route stays closed when no API keys are configured.
+pub(super) struct ServiceKey;
+
+impl FromRequestParts<Arc<Context>> for ServiceKey {
+    type Rejection = ApiResponse<()>;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &Arc<Context>,
+    ) -> Result<Self, Self::Rejection> {
+        if parts.extensions.get::<ApiKey>().is_some() {
+            return Ok(ServiceKey);
+        }
+
+        Err(api_error::build(
+            StatusCode::UNAUTHORIZED,
+            "API key required",
+        ))
+    }
+}
+
+/// Extractor guarding monitoring routes (`/health`): open while no API keys are configured,
+/// otherwise it takes an API key with the route's scope or an admin session.
+pub(super) struct MonitoringAccess;
+
+impl FromRequestParts<Arc<Context>> for MonitoringAccess {
+    type Rejection = ApiResponse<()>;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<Context>,
+    ) -> Result<Self, Self::Rejection> {
+        // Keys skip the client certificate check of admin routes, as they always did here.
+        if !state.api_keys.is_enabled() || parts.extensions.get::<ApiKey>().is_some() {
+            return Ok(MonitoringAccess);
+        }
+
+        AdminSession::from_request_parts(parts, state)
+            .await
+            .map(|_| MonitoringAccess)
+    }
+}
+
+pub(super) mod ticket {
+    use super::*;
+
+    type Ret = ApiResponse<StreamTicket>;
+
+    #[utoipa::path(
+        post,
+        path = "/events/ticket",
+        tag = "ln-gateway",
+        operation_id = "createStreamTicket",
+        security(("api_key" = ["invoices:read"]), ("admin_session" = [])),
+        responses(
+            (status = 200, description = "One-time ticket for `/events` or `/ws`", body = StreamTicket),
+            (status = 401, description = "Admin session or API key required"),
+            (status = 403, description = "Not an admin session, or the API key lacks the scope")
+        )
+    )]
+    pub async fn handler(State(state): State<Arc<Context>>, _admin: AdminSession) -> Ret {
+        ApiResponse::make_ok(state.auth_sessions.issue_ticket().await)
+    }
+}
+
+pub(super) mod get {
+    use super::*;
+
+    type Ret = ApiResponse<AuthSession>;
+
+    #[utoipa::path(
+        get,
+        path = "/auth/session",
+        tag = "ln-gateway",
+        operation_id = "getAuthSession",
+        responses(
+            (status = 200, description = "The session of the bearer token", body = AuthSession),
+            (status = 401, description = "Missing, unknown or expired session token")
+        )
+    )]
+    pub async fn handler(State(state): State<Arc<Context>>, headers: HeaderMap) -> Ret {
+        let Some(token) = bearer_token(&headers) else {
+            return api_error::build(StatusCode::UNAUTHORIZED, "missing bearer token");
+        };
+
+        match state.auth_sessions.get(token).await {
+            Some(session) => ApiResponse::make_ok(session),
+            None => api_error::build(StatusCode::UNAUTHORIZED, "invalid or expired session"),
+        }
+    }
+}
+
+pub(super) mod delete {
+    use super::*;
+
+    type Ret = ApiResponse<()>;
+
+    #[utoipa::path(
+        delete,
+        path = "/auth/session",
+        tag = "ln-gateway",
+        operation_id = "logout",
+        responses(
+            (status = 200, description = "Session revoked"),
+            (status = 401, description = "Missing, unknown or expired session token")
+        )
+    )]
+    pub async fn handler(State(state): State<Arc<Context>>, headers: HeaderMap) -> Ret {
+        let Some(token) = bearer_token(&headers) else {
+            return api_error::build(StatusCode::UNAUTHORIZED, "missing bearer token");
+        };
+
+        match state.auth_sessions.revoke(token).await {
+            Some(_) => ApiResponse::make_ok(()),
+            None => api_error::build(StatusCode::UNAUTHORIZED, "invalid or expired session"),
+        }
+    }
+}
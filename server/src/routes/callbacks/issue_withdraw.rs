@@ -1,27 +1,38 @@
+use std::str::FromStr;
 use std::sync::Arc;
 
+use axum::extract::rejection::QueryRejection;
 use axum::{
     extract::{Query, State},
     http::StatusCode,
 };
+use bitcoin::Address;
 use serde::{Deserialize, Serialize};
 
-use cln_rpc::model::responses::WithdrawResponse;
+use cln_rpc::model::responses::{ListfundsOutputsStatus, WithdrawResponse};
+use cln_rpc::primitives::{Amount, AmountOrAll};
 
 use crate::{
     context::Context,
-    routes::{ApiResponse, api_error},
+    core::{
+        lightning_rpc_connector::is_definitive_withdraw_failure, spending_limits::SpendFlow, utils,
+        webhook::event::WebhookEvent,
+    },
+    routes::{ApiResponse, LnurlErrorBody, api_error, rune::CallerRune},
 };
 
 #[derive(Deserialize, Debug, utoipa::ToSchema)]
 pub(super) struct IssueWithdrawRequest {
     pub k1: String,
     pub destination: String,
-    pub amount: Option<u64>,
+    /// Amount in satoshis, or `all` when sweeping is enabled.
+    pub amount: Option<String>,
 }
 
 #[derive(Serialize, utoipa::ToSchema)]
 pub(super) struct IssueWithdrawResponse {
+    /// Always `OK`; failures are returned as errors.
+    pub status: &'static str,
     pub tx: String,
     pub psbt: String,
     pub txid: String,
@@ -30,6 +41,7 @@ pub(super) struct IssueWithdrawResponse {
 impl From<WithdrawResponse> for IssueWithdrawResponse {
     fn from(res: WithdrawResponse) -> Self {
         Self {
+            status: "OK",
             tx: res.tx,
             psbt: res.psbt,
             txid: res.txid,
@@ -37,6 +49,99 @@ impl From<WithdrawResponse> for IssueWithdrawResponse {
     }
 }
 
+/// Reason a withdraw was rejected, returned as `code` next to the LUD-03 `reason`.
+#[derive(Debug, Clone, Copy, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum WithdrawErrorCode {
+    /// `k1` or `destination` is missing, or a parameter is malformed.
+    InvalidRequest,
+    /// `amount` is missing.
+    AmountRequired,
+    /// `amount` is not a positive number of satoshis.
+    InvalidAmount,
+    /// `amount=all` was sent but sweeping is disabled.
+    SweepDisabled,
+    /// The amount is below `minWithdrawable`.
+    AmountBelowMinimum,
+    /// The amount is above `maxWithdrawable`.
+    AmountAboveMaximum,
+    /// `destination` is not a Bitcoin address.
+    InvalidDestination,
+    /// `destination` belongs to another network than the node's.
+    WrongNetwork,
+    /// The withdraw would exceed a spending limit.
+    SpendingLimitExceeded,
+    /// `k1` is unknown or was already used.
+    InvalidK1,
+    /// The presented rune does not allow `withdraw`, or could not be checked.
+    RuneRejected,
+    /// CoreLightning failed to build or broadcast the transaction.
+    NodeError,
+}
+
+impl WithdrawErrorCode {
+    fn as_str(&self) -> &'static str {
+        match self {
+            WithdrawErrorCode::InvalidRequest => "invalid_request",
+            WithdrawErrorCode::AmountRequired => "amount_required",
+            WithdrawErrorCode::InvalidAmount => "invalid_amount",
+            WithdrawErrorCode::SweepDisabled => "sweep_disabled",
+            WithdrawErrorCode::AmountBelowMinimum => "amount_below_minimum",
+            WithdrawErrorCode::AmountAboveMaximum => "amount_above_maximum",
+            WithdrawErrorCode::InvalidDestination => "invalid_destination",
+            WithdrawErrorCode::WrongNetwork => "wrong_network",
+            WithdrawErrorCode::SpendingLimitExceeded => "spending_limit_exceeded",
+            WithdrawErrorCode::InvalidK1 => "invalid_k1",
+            WithdrawErrorCode::RuneRejected => "rune_rejected",
+            WithdrawErrorCode::NodeError => "node_error",
+        }
+    }
+
+    fn status(&self) -> StatusCode {
+        match self {
+            WithdrawErrorCode::SpendingLimitExceeded => StatusCode::FORBIDDEN,
+            WithdrawErrorCode::NodeError => StatusCode::BAD_GATEWAY,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+fn reject<T>(code: WithdrawErrorCode, message: impl Into<String>) -> ApiResponse<T> {
+    api_error::build_lnurl(code.status(), code.as_str(), message)
+}
+
+enum WithdrawAmount {
+    Sat(u64),
+    All,
+}
+
+fn parse_amount(amount: Option<&str>, allow_all: bool) -> Result<WithdrawAmount, Ret> {
+    let Some(amount) = amount.map(str::trim).filter(|a| !a.is_empty()) else {
+        return Err(reject(
+            WithdrawErrorCode::AmountRequired,
+            "amount (satoshis) is required",
+        ));
+    };
+
+    if amount.eq_ignore_ascii_case("all") {
+        if !allow_all {
+            return Err(reject(
+                WithdrawErrorCode::SweepDisabled,
+                "amount=all is not enabled on this server",
+            ));
+        }
+        return Ok(WithdrawAmount::All);
+    }
+
+    match amount.parse::<u64>() {
+        Ok(sat) if sat > 0 => Ok(WithdrawAmount::Sat(sat)),
+        _ => Err(reject(
+            WithdrawErrorCode::InvalidAmount,
+            format!("amount must be a positive number of satoshis, got {amount}"),
+        )),
+    }
+}
+
 type Ret = ApiResponse<IssueWithdrawResponse>;
 
 #[utoipa::path(
@@ -46,33 +151,175 @@ type Ret = ApiResponse<IssueWithdrawResponse>;
     operation_id = "withdraw",
     params(
         ("k1" = String, Query, description = "One-time token from /withdraw-request"),
-        ("destination" = String, Query, description = "Bitcoin address (or other supported withdraw destination)"),
-        ("amount" = Option<u64>, Query, description = "Withdraw amount in satoshis")
+        ("destination" = String, Query, description = "Bitcoin address on the node's network"),
+        ("amount" = String, Query, description = "Withdraw amount in satoshis, within the advertised minWithdrawable/maxWithdrawable (msat); `all` sweeps the confirmed balance when SERVER_WITHDRAW_ALLOW_ALL is set")
     ),
     responses(
-        (status = 200, description = "Withdraw result", body = IssueWithdrawResponse)
+        (status = 200, description = "Withdraw result", body = IssueWithdrawResponse),
+        (status = 400, description = "Invalid amount, destination or k1; see `code`", body = LnurlErrorBody<WithdrawErrorCode>),
+        (status = 403, description = "The presented rune does not allow withdraw, or a spending limit would be exceeded", body = LnurlErrorBody<WithdrawErrorCode>),
+        (status = 502, description = "The CoreLightning node encountered an error", body = LnurlErrorBody<WithdrawErrorCode>)
     )
 )]
 pub(super) async fn handler(
     State(state): State<Arc<Context>>,
-    Query(params): Query<IssueWithdrawRequest>,
+    rune: CallerRune,
+    params: Result<Query<IssueWithdrawRequest>, QueryRejection>,
 ) -> Ret {
+    let params = match params {
+        Ok(Query(params)) => params,
+        Err(e) => return reject(WithdrawErrorCode::InvalidRequest, e.body_text()),
+    };
+
+    let amount = match parse_amount(params.amount.as_deref(), state.args.withdraw_allow_all) {
+        Ok(amount) => amount,
+        Err(e) => return e,
+    };
+
+    // Unknown k1s are turned away before any CLN call; it is consumed further down.
+    if !state.withdrawal_keys_set.lock().await.contains(&params.k1) {
+        return reject(WithdrawErrorCode::InvalidK1, "invalid or already used k1");
+    }
+
+    let mut rpc = state.cln_client.lock().await;
+
+    // Check the rune before consuming k1, so a rejected call can be retried.
+    let rune_params = serde_json::json!({
+        "destination": params.destination,
+        "satoshi": match amount {
+            WithdrawAmount::Sat(sat) => serde_json::json!(sat),
+            WithdrawAmount::All => serde_json::json!("all"),
+        },
+    });
+    if let Err(e) = rune
+        .authorize_if_present(&mut rpc, "withdraw", rune_params)
+        .await
+    {
+        return e.into_lnurl(WithdrawErrorCode::RuneRejected.as_str());
+    }
+
+    let info = match rpc.getinfo().await {
+        Ok(info) => info,
+        Err(e) => return reject(WithdrawErrorCode::NodeError, e.to_string()),
+    };
+
+    let address = match Address::from_str(params.destination.trim()) {
+        Ok(address) => address,
+        Err(e) => {
+            return reject(
+                WithdrawErrorCode::InvalidDestination,
+                format!("invalid bitcoin address: {e}"),
+            );
+        }
+    };
+    match utils::address_network(&info.network) {
+        Some(network) if !address.is_valid_for_network(network) => {
+            return reject(
+                WithdrawErrorCode::WrongNetwork,
+                format!("address is not valid on {}", info.network),
+            );
+        }
+        Some(_) => {}
+        None => tracing::warn!(
+            "Unknown node network {}; not checking the withdraw address",
+            info.network
+        ),
+    }
+
+    // A sweep spends every confirmed, unreserved output; its size is checked like an amount.
+    let amount_sat = match amount {
+        WithdrawAmount::Sat(sat) => sat,
+        WithdrawAmount::All => match rpc.listfunds().await {
+            Ok(funds) => funds
+                .outputs
+                .iter()
+                .filter(|o| matches!(o.status, ListfundsOutputsStatus::CONFIRMED) && !o.reserved)
+                .map(|o| o.amount_msat.msat() / 1_000)
+                .sum(),
+            Err(e) => return reject(WithdrawErrorCode::NodeError, e.to_string()),
+        },
+    };
+
+    // Bounds are advertised in msat, amounts are withdrawn in sat.
+    let amount_msat = amount_sat.saturating_mul(1_000);
+    let runtime = state.runtime.load_full();
+    if amount_msat < runtime.min_withdrawable_msat {
+        return reject(
+            WithdrawErrorCode::AmountBelowMinimum,
+            format!(
+                "{} sat is below the minimum of {} msat",
+                amount_sat, runtime.min_withdrawable_msat
+            ),
+        );
+    }
+    if amount_msat > runtime.max_withdrawable_msat {
+        return reject(
+            WithdrawErrorCode::AmountAboveMaximum,
+            format!(
+                "{} sat is above the maximum of {} msat",
+                amount_sat, runtime.max_withdrawable_msat
+            ),
+        );
+    }
+
+    // Also checked before consuming k1, so a call over the limit can be retried later.
+    let reservation = match state
+        .spending
+        .reserve(
+            &runtime.spending_policy.limits,
+            SpendFlow::Withdraw,
+            amount_msat,
+        )
+        .await
+    {
+        Ok(reservation) => reservation,
+        Err(e) => return reject(WithdrawErrorCode::SpendingLimitExceeded, e),
+    };
+
     {
         let mut set = state.withdrawal_keys_set.lock().await;
         if !set.remove(&params.k1) {
-            return api_error::build(StatusCode::BAD_REQUEST, "invalid or already used k1");
+            state.spending.release(reservation).await;
+            return reject(WithdrawErrorCode::InvalidK1, "invalid or already used k1");
         }
     }
 
-    let amount = params.amount.unwrap_or(0);
-
-    let mut rpc = state.cln_client.lock().await;
-    let res = match rpc.withdraw(params.destination, amount).await {
+    let satoshi = match amount {
+        WithdrawAmount::Sat(sat) => AmountOrAll::Amount(Amount::from_sat(sat)),
+        WithdrawAmount::All => AmountOrAll::All,
+    };
+    let res = match rpc.withdraw(params.destination.clone(), satoshi).await {
         Ok(res) => res,
+        Err(e) if is_definitive_withdraw_failure(&e) => {
+            state.spending.release(reservation).await;
+            return reject(WithdrawErrorCode::NodeError, e.to_string());
+        }
         Err(e) => {
-            return api_error::build(StatusCode::BAD_GATEWAY, e.to_string());
+            // The transaction may have been broadcast: keep it counted against the limits.
+            tracing::warn!(
+                "Withdraw outcome unknown, keeping {} msat counted against the spending limits: {}",
+                amount_msat,
+                e
+            );
+            state.spending.commit(reservation, amount_msat).await;
+            return reject(
+                WithdrawErrorCode::NodeError,
+                format!("withdraw outcome unknown: {e}"),
+            );
         }
     };
+    drop(rpc);
+
+    state.spending.commit(reservation, amount_msat).await;
+
+    state
+        .webhooks
+        .enqueue(WebhookEvent::WithdrawPaid {
+            txid: res.txid.clone(),
+            destination: params.destination,
+            amount_sat,
+        })
+        .await;
 
     ApiResponse::make_ok(res.into())
 }
@@ -11,6 +11,7 @@ use serde_json::Value;
 
 use crate::{
     context::Context,
+    core::{utils, webhook::event::WebhookEvent},
     routes::{ApiResponse, api_error},
 };
 
@@ -53,10 +54,16 @@ pub(super) async fn handler(
     State(state): State<Arc<Context>>,
     Query(params): Query<LnUrlAuthQuery>,
 ) -> Ret {
-    // Verify k1 is expected (pending), and hasn't already been used.
+    // Verify k1 is expected (pending), and hasn't already been used or expired.
     {
-        let pending = state.auth_pending_keys_set.lock().await;
-        if !pending.contains(&params.k1) {
+        let pending = state.auth_pending_keys.lock().await;
+        if pending
+            .get(&params.k1)
+            .is_some_and(|c| c.is_expired(utils::unix_time_ms() / 1000))
+        {
+            return api_error::build(StatusCode::BAD_REQUEST, "k1 expired");
+        }
+        if !pending.contains_key(&params.k1) {
             // Not pending, so either invalid or already used.
             drop(pending);
 
@@ -119,16 +126,36 @@ pub(super) async fn handler(
     }
 
     // Consume k1 on successful verification.
-    {
-        let mut pending = state.auth_pending_keys_set.lock().await;
-        if !pending.remove(&params.k1) {
+    let action = {
+        let mut pending = state.auth_pending_keys.lock().await;
+        let Some(challenge) = pending.remove(&params.k1) else {
             // Another request won the race.
             return api_error::build(StatusCode::CONFLICT, "k1 already used");
-        }
+        };
 
+        // Completed k1s are kept as long as their session could be claimed, so replays get 409 and
+        // status lookups 410.
+        let now = utils::unix_time_ms() / 1000;
+        let ttl_secs = state.auth_sessions.ttl_secs();
         let mut completed = state.auth_completed.lock().await;
-        completed.insert(params.k1.clone(), params.key.clone());
-    }
+        completed.retain(|_, completed_at| now < *completed_at + ttl_secs);
+        completed.insert(params.k1.clone(), now);
+        challenge.action
+    };
+
+    // The session is handed out to the web app polling /lnurl-auth-status/{k1}.
+    state
+        .auth_sessions
+        .issue(params.k1.clone(), params.key.clone(), action)
+        .await;
+
+    state
+        .webhooks
+        .enqueue(WebhookEvent::LnUrlAuthCompleted {
+            k1: params.k1,
+            pubkey: params.key,
+        })
+        .await;
 
     ApiResponse::make_ok(LnUrlAuthResponse {
         ok: true,
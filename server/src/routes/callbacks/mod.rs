@@ -27,8 +27,10 @@ pub(super) fn get_router() -> Router<Arc<Context>> {
         schemas(
             open_channel::OpenChannelRequest,
             open_channel::OpenChannelResponse,
+            open_channel::ChannelErrorCode,
             issue_withdraw::IssueWithdrawRequest,
             issue_withdraw::IssueWithdrawResponse,
+            issue_withdraw::WithdrawErrorCode,
             lnurl_auth::LnUrlAuthQuery,
             lnurl_auth::LnUrlAuthResponse,
         )
@@ -1,30 +1,125 @@
 use std::str::FromStr;
 use std::sync::Arc;
 
+use axum::extract::rejection::QueryRejection;
 use axum::{
     extract::{Query, State},
     http::StatusCode,
 };
 use cln_rpc::primitives::PublicKey;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
 
 use crate::context::Context;
-use crate::routes::{ApiResponse, api_error};
+use crate::core::lightning_rpc_connector::is_definitive_fundchannel_failure;
+use crate::core::spending_limits::SpendFlow;
+use crate::core::webhook::event::WebhookEvent;
+use crate::routes::{ApiResponse, LnurlErrorBody, api_error, rune::CallerRune};
 
 #[derive(Deserialize, Debug, utoipa::ToSchema)]
 pub(super) struct OpenChannelRequest {
     pub k1: String,
-    pub remote_id: String,
+    /// Node id of the wallet; `remote_id` is accepted for older clients.
+    #[serde(alias = "remote_id")]
+    pub remoteid: String,
+    /// `1` to open an unannounced channel.
+    pub private: Option<String>,
+    /// `1` to abandon the request instead of opening a channel.
+    pub cancel: Option<String>,
+    /// Channel size in satoshis; defaults to the configured minimum.
     pub amount: Option<u64>,
-    pub announce: Option<bool>,
 }
 
 #[derive(Serialize, utoipa::ToSchema)]
 pub(super) struct OpenChannelResponse {
-    pub ok: bool,
-    pub result: Option<Value>,
-    pub error: Option<String>,
+    /// Always `OK`; failures are returned as errors.
+    pub status: &'static str,
+    /// Id of the new channel (absent when cancelled).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel_id: Option<String>,
+    /// Funding transaction id (absent when cancelled).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub txid: Option<String>,
+}
+
+impl OpenChannelResponse {
+    fn ok(channel_id: Option<String>, txid: Option<String>) -> Self {
+        Self {
+            status: "OK",
+            channel_id,
+            txid,
+        }
+    }
+}
+
+/// Reason a channel request was rejected, returned as `code` next to the LUD-02 `reason`.
+#[derive(Debug, Clone, Copy, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum ChannelErrorCode {
+    /// `k1` or `remoteid` is missing, or a parameter is malformed.
+    InvalidRequest,
+    /// `remoteid` is not a node id.
+    InvalidRemoteId,
+    /// `private` or `cancel` is not `0` or `1`.
+    InvalidFlag,
+    /// The amount is below the configured minimum channel size.
+    AmountBelowMinimum,
+    /// The amount is above the configured maximum channel size.
+    AmountAboveMaximum,
+    /// The channel would exceed a spending limit.
+    SpendingLimitExceeded,
+    /// The node could not connect to the wallet.
+    PeerUnreachable,
+    /// `k1` is unknown or was already used.
+    InvalidK1,
+    /// The presented rune does not allow `fundchannel`, or could not be checked.
+    RuneRejected,
+    /// CoreLightning failed to fund the channel.
+    NodeError,
+}
+
+impl ChannelErrorCode {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ChannelErrorCode::InvalidRequest => "invalid_request",
+            ChannelErrorCode::InvalidRemoteId => "invalid_remote_id",
+            ChannelErrorCode::InvalidFlag => "invalid_flag",
+            ChannelErrorCode::AmountBelowMinimum => "amount_below_minimum",
+            ChannelErrorCode::AmountAboveMaximum => "amount_above_maximum",
+            ChannelErrorCode::SpendingLimitExceeded => "spending_limit_exceeded",
+            ChannelErrorCode::PeerUnreachable => "peer_unreachable",
+            ChannelErrorCode::InvalidK1 => "invalid_k1",
+            ChannelErrorCode::RuneRejected => "rune_rejected",
+            ChannelErrorCode::NodeError => "node_error",
+        }
+    }
+
+    fn status(&self) -> StatusCode {
+        match self {
+            ChannelErrorCode::SpendingLimitExceeded => StatusCode::FORBIDDEN,
+            ChannelErrorCode::PeerUnreachable | ChannelErrorCode::NodeError => {
+                StatusCode::BAD_GATEWAY
+            }
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+fn reject<T>(code: ChannelErrorCode, message: impl Into<String>) -> ApiResponse<T> {
+    api_error::build_lnurl(code.status(), code.as_str(), message)
+}
+
+/// LUD-02 flags are `0`/`1`; `true`/`false` are accepted as well.
+fn parse_flag(name: &str, value: Option<&str>) -> Result<bool, Ret> {
+    match value.map(str::trim) {
+        None | Some("") | Some("0") => Ok(false),
+        Some("1") => Ok(true),
+        Some(v) if v.eq_ignore_ascii_case("false") => Ok(false),
+        Some(v) if v.eq_ignore_ascii_case("true") => Ok(true),
+        Some(v) => Err(reject(
+            ChannelErrorCode::InvalidFlag,
+            format!("{name} must be 0 or 1, got {v}"),
+        )),
+    }
 }
 
 type Ret = ApiResponse<OpenChannelResponse>;
@@ -36,48 +131,176 @@ type Ret = ApiResponse<OpenChannelResponse>;
     operation_id = "openChannel",
     params(
         ("k1" = String, Query, description = "One-time token from /channel-request"),
-        ("remote_id" = String, Query, description = "Remote node pubkey"),
-        ("amount" = Option<u64>, Query, description = "Channel funding amount in satoshis"),
-        ("announce" = Option<bool>, Query, description = "Whether to announce channel")
+        ("remoteid" = String, Query, description = "Node id of the wallet (`remote_id` is accepted too)"),
+        ("private" = Option<String>, Query, description = "`1` to open an unannounced channel"),
+        ("cancel" = Option<String>, Query, description = "`1` to abandon the request"),
+        ("amount" = Option<u64>, Query, description = "Channel size in satoshis, within SERVER_CHANNEL_MIN_SAT and SERVER_CHANNEL_MAX_SAT; defaults to the minimum")
     ),
     responses(
-        (status = 200, description = "Open channel result", body = OpenChannelResponse)
+        (status = 200, description = "Channel funded, or request cancelled", body = OpenChannelResponse),
+        (status = 400, description = "Invalid remoteid, flag, amount or k1; see `code`", body = LnurlErrorBody<ChannelErrorCode>),
+        (status = 403, description = "The presented rune does not allow fundchannel, or a spending limit would be exceeded", body = LnurlErrorBody<ChannelErrorCode>),
+        (status = 502, description = "The node could not reach the wallet, or CoreLightning failed to fund the channel", body = LnurlErrorBody<ChannelErrorCode>)
     )
 )]
 pub(super) async fn handler(
     State(state): State<Arc<Context>>,
-    Query(params): Query<OpenChannelRequest>,
+    rune: CallerRune,
+    params: Result<Query<OpenChannelRequest>, QueryRejection>,
 ) -> Ret {
-    {
-        let mut set = state.channel_keys_set.lock().await;
-        if !set.remove(&params.k1) {
-            return api_error::build(StatusCode::BAD_REQUEST, "invalid or already used k1");
-        }
-    }
+    let params = match params {
+        Ok(Query(params)) => params,
+        Err(e) => return reject(ChannelErrorCode::InvalidRequest, e.body_text()),
+    };
 
-    let id = match PublicKey::from_str(&params.remote_id) {
+    let id = match PublicKey::from_str(params.remoteid.trim()) {
         Ok(id) => id,
         Err(e) => {
-            return api_error::build(StatusCode::BAD_REQUEST, format!("invalid pubkey: {}", e));
+            return reject(
+                ChannelErrorCode::InvalidRemoteId,
+                format!("invalid remoteid: {}", e),
+            );
         }
     };
+    let private = match parse_flag("private", params.private.as_deref()) {
+        Ok(private) => private,
+        Err(e) => return e,
+    };
+    let cancel = match parse_flag("cancel", params.cancel.as_deref()) {
+        Ok(cancel) => cancel,
+        Err(e) => return e,
+    };
 
-    let amount = params.amount.unwrap_or(0);
+    if cancel {
+        let mut set = state.channel_keys_set.lock().await;
+        if !set.remove(&params.k1) {
+            return reject(ChannelErrorCode::InvalidK1, "invalid or already used k1");
+        }
+        return ApiResponse::make_ok(OpenChannelResponse::ok(None, None));
+    }
+
+    // Unknown k1s are turned away before any CLN call or connection attempt to the caller's
+    // `remoteid`; it is consumed further down.
+    if !state.channel_keys_set.lock().await.contains(&params.k1) {
+        return reject(ChannelErrorCode::InvalidK1, "invalid or already used k1");
+    }
+
+    let runtime = state.runtime.load_full();
+    let amount = params.amount.unwrap_or(runtime.channel_min_sat);
+    if amount < runtime.channel_min_sat {
+        return reject(
+            ChannelErrorCode::AmountBelowMinimum,
+            format!(
+                "{} sat is below the minimum channel size of {} sat",
+                amount, runtime.channel_min_sat
+            ),
+        );
+    }
+    if amount > runtime.channel_max_sat {
+        return reject(
+            ChannelErrorCode::AmountAboveMaximum,
+            format!(
+                "{} sat is above the maximum channel size of {} sat",
+                amount, runtime.channel_max_sat
+            ),
+        );
+    }
+    let push_msat = (runtime.channel_push_msat > 0).then_some(runtime.channel_push_msat);
 
     let mut rpc = state.cln_client.lock().await;
-    let res = match rpc.fundchannel(id, amount, params.announce).await {
+
+    // Check the rune before consuming k1, so a rejected call can be retried.
+    let rune_params = serde_json::json!({
+        "id": id.to_string(),
+        "amount": amount,
+        "announce": !private,
+        "push_msat": push_msat,
+    });
+    if let Err(e) = rune
+        .authorize_if_present(&mut rpc, "fundchannel", rune_params)
+        .await
+    {
+        return e.into_lnurl(ChannelErrorCode::RuneRejected.as_str());
+    }
+    drop(rpc);
+
+    // Connecting can take several connect timeouts and `fundchannel` waits on the peer, so both
+    // run on a connection of their own instead of blocking every other request on the shared one.
+    let mut rpc = match state.dedicated_cln_client().await {
+        Ok(rpc) => rpc,
+        Err(e) => return reject(ChannelErrorCode::NodeError, e.to_string()),
+    };
+
+    // Wallets normally connect to the advertised uri before calling back; if this one didn't,
+    // try the addresses CLN knows from the gossip.
+    if let Err(e) = rpc.ensure_connected(id).await {
+        return reject(
+            ChannelErrorCode::PeerUnreachable,
+            format!("could not connect to {}: {}", id, e),
+        );
+    }
+
+    // Also checked before consuming k1, so a call over the limit can be retried later.
+    let amount_msat = amount.saturating_mul(1_000);
+    let reservation = match state
+        .spending
+        .reserve(
+            &runtime.spending_policy.limits,
+            SpendFlow::Channel,
+            amount_msat,
+        )
+        .await
+    {
+        Ok(reservation) => reservation,
+        Err(e) => return reject(ChannelErrorCode::SpendingLimitExceeded, e),
+    };
+
+    {
+        let mut set = state.channel_keys_set.lock().await;
+        if !set.remove(&params.k1) {
+            state.spending.release(reservation).await;
+            return reject(ChannelErrorCode::InvalidK1, "invalid or already used k1");
+        }
+    }
+
+    let res = match rpc
+        .fundchannel(id, amount, Some(!private), push_msat, None)
+        .await
+    {
         Ok(res) => res,
+        Err(e) if is_definitive_fundchannel_failure(&e) => {
+            state.spending.release(reservation).await;
+            return reject(ChannelErrorCode::NodeError, e.to_string());
+        }
         Err(e) => {
-            return api_error::build(StatusCode::BAD_GATEWAY, e.to_string());
+            // The funding transaction may have been broadcast: keep it counted against the limits.
+            tracing::warn!(
+                "Channel funding outcome unknown, keeping {} msat counted against the spending limits: {}",
+                amount_msat,
+                e
+            );
+            state.spending.commit(reservation, amount_msat).await;
+            return reject(
+                ChannelErrorCode::NodeError,
+                format!("funding outcome unknown: {e}"),
+            );
         }
     };
 
-    let json_value =
-        serde_json::to_value(&res).unwrap_or_else(|_| Value::String(format!("{:?}", res)));
+    state.spending.commit(reservation, amount_msat).await;
+
+    state
+        .webhooks
+        .enqueue(WebhookEvent::ChannelOpened {
+            remote_id: id.to_string(),
+            channel_id: res.channel_id.to_string(),
+            txid: res.txid.clone(),
+            amount_sat: amount,
+        })
+        .await;
 
-    ApiResponse::make_ok(OpenChannelResponse {
-        ok: true,
-        result: Some(json_value),
-        error: None,
-    })
+    ApiResponse::make_ok(OpenChannelResponse::ok(
+        Some(res.channel_id.to_string()),
+        Some(res.txid),
+    ))
 }
@@ -2,13 +2,15 @@ use axum::{
     extract::{Request, State},
     http::StatusCode,
 };
+use cln_rpc::model::responses::{GetinfoAddressType, GetinfoBindingType, GetinfoResponse};
 use serde::Serialize;
+use std::net::IpAddr;
 use std::sync::Arc;
 
 use crate::{
     context::Context,
     core::utils,
-    routes::{ApiResponse, api_error},
+    routes::{ApiResponse, api_error, auth_session::AdminSession},
 };
 
 #[derive(Debug, Serialize, utoipa::ToSchema)]
@@ -25,17 +27,90 @@ pub(super) struct ChannelRequestResponse {
 
 type Ret = ApiResponse<ChannelRequestResponse>;
 
+/// Lightning P2P port CLN listens on when it doesn't report one.
+const DEFAULT_P2P_PORT: u16 = 9735;
+
+fn host_port(host: &str, port: u16) -> String {
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V6(_)) => format!("[{}]:{}", host, port),
+        _ => format!("{}:{}", host, port),
+    }
+}
+
+/// `host:port` wallets can reach the node's P2P listener at. Prefers the announced clearnet
+/// addresses, then the routable bindings, then announced Tor addresses (which clearnet wallets
+/// can't reach), then the host the request came in on with the bound port.
+fn node_address(info: &GetinfoResponse, request_host: &str) -> String {
+    let announced = |clearnet: bool| {
+        info.address.iter().flatten().find_map(|a| {
+            let is_clearnet = matches!(
+                a.item_type,
+                GetinfoAddressType::IPV4 | GetinfoAddressType::IPV6 | GetinfoAddressType::DNS
+            );
+            let address = a.address.as_deref()?;
+            (is_clearnet == clearnet).then_some((address, a.port))
+        })
+    };
+
+    if let Some((address, port)) = announced(true) {
+        return host_port(address, port);
+    }
+
+    let bindings: Vec<_> = info
+        .binding
+        .iter()
+        .flatten()
+        .filter(|b| {
+            matches!(
+                b.item_type,
+                GetinfoBindingType::IPV4 | GetinfoBindingType::IPV6
+            )
+        })
+        .collect();
+
+    if let Some((address, port)) = bindings.iter().find_map(|b| {
+        let address = b.address.as_deref()?;
+        let ip = address.parse::<IpAddr>().ok()?;
+        (!ip.is_unspecified() && !ip.is_loopback())
+            .then_some((address, b.port.unwrap_or(DEFAULT_P2P_PORT)))
+    }) {
+        return host_port(address, port);
+    }
+
+    if let Some((address, port)) = announced(false) {
+        return host_port(address, port);
+    }
+
+    let port = bindings
+        .iter()
+        .find_map(|b| b.port)
+        .unwrap_or(DEFAULT_P2P_PORT);
+    host_port(request_host, port)
+}
+
 #[utoipa::path(
     get,
     path = "/channel-request",
     tag = "ln-gateway",
     operation_id = "channelRequest",
+    security(("api_key" = ["lnurl:channel:issue"]), ("admin_session" = [])),
     responses(
         (status = 200, description = "LNURL Channel Request", body = ChannelRequestResponse),
-        (status = 502, description = "The CoreLightning node encountered an error")
+        (status = 502, description = "The CoreLightning node encountered an error"),
+        (status = 503, description = "The server is shutting down"),
+        (status = 401, description = "Admin session or API key required"),
+        (status = 403, description = "Not an admin session, or the API key lacks the scope")
     )
 )]
-pub(super) async fn handler(State(state): State<Arc<Context>>, request: Request) -> Ret {
+pub(super) async fn handler(
+    State(state): State<Arc<Context>>,
+    _admin: AdminSession,
+    request: Request,
+) -> Ret {
+    if state.shutdown.is_draining() {
+        return api_error::build(StatusCode::SERVICE_UNAVAILABLE, "Server is shutting down");
+    }
+
     let mut rpc = state.cln_client.lock().await;
     let info = match rpc.getinfo().await {
         Ok(r) => r,
@@ -45,8 +120,8 @@ pub(super) async fn handler(State(state): State<Arc<Context>>, request: Request)
     };
 
     let pubkey = info.id.to_string();
-    let hostname = utils::extract_request_host(&request, &info);
-    let base_url = utils::request_base_url(&request, &hostname, state.args.listening_port);
+    let hostname = utils::extract_request_host(&request, &state, &info);
+    let base_url = utils::request_base_url(&request, &state, &hostname);
 
     let k1 = utils::gen_k1_as_string();
     {
@@ -55,7 +130,7 @@ pub(super) async fn handler(State(state): State<Arc<Context>>, request: Request)
     }
 
     let response = ChannelRequestResponse {
-        uri: format!("{}@{}:{}", pubkey, hostname, state.args.listening_port),
+        uri: format!("{}@{}", pubkey, node_address(&info, &hostname)),
         callback: format!("{}/callbacks/open-channel", base_url),
         k1,
         tag: "channelRequest",
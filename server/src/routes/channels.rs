@@ -0,0 +1,468 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use bitcoin::Address;
+use cln_rpc::model::responses::{
+    CloseType, ListpeerchannelsChannels, ListpeerchannelsResponse, SetchannelChannels,
+};
+use cln_rpc::primitives::{ChannelSide, PublicKey};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    context::Context,
+    core::{lightning_rpc_connector::LightningRPCConnector, utils},
+    routes::{ApiResponse, api_error, auth_session::AdminSession, rune::CallerRune},
+};
+
+#[derive(Debug, Clone, Copy, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum ChannelOpener {
+    Local,
+    Remote,
+}
+
+impl From<ChannelSide> for ChannelOpener {
+    fn from(value: ChannelSide) -> Self {
+        match value {
+            ChannelSide::LOCAL => ChannelOpener::Local,
+            ChannelSide::REMOTE => ChannelOpener::Remote,
+        }
+    }
+}
+
+/// Fees and HTLC limits this node advertises for a channel.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub(super) struct ChannelFees {
+    pub fee_base_msat: u64,
+    /// Proportional fee in parts per million.
+    pub fee_ppm: u32,
+    pub htlc_minimum_msat: u64,
+    pub htlc_maximum_msat: u64,
+    pub cltv_expiry_delta: u32,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub(super) struct ChannelEntry {
+    /// Channel id (hex); absent while the channel is being negotiated.
+    pub channel_id: Option<String>,
+    /// Short channel id (`<block>x<tx>x<output>`), once the funding is confirmed.
+    pub short_channel_id: Option<String>,
+    /// Node id of the peer.
+    pub peer_id: String,
+    pub peer_connected: bool,
+    /// CLN channel state, e.g. `CHANNELD_NORMAL` or `ONCHAIN`.
+    pub state: String,
+    pub opener: ChannelOpener,
+    /// Whether the channel is unannounced.
+    pub private: Option<bool>,
+    /// Channel capacity in millisatoshis.
+    pub total_msat: Option<u64>,
+    /// Our balance in millisatoshis.
+    pub to_us_msat: Option<u64>,
+    /// What we can currently send, in millisatoshis.
+    pub spendable_msat: Option<u64>,
+    /// What we can currently receive, in millisatoshis.
+    pub receivable_msat: Option<u64>,
+    pub funding_txid: Option<String>,
+    pub funding_outnum: Option<u32>,
+    /// Absent until the channel is usable.
+    pub fees: Option<ChannelFees>,
+}
+
+impl From<ListpeerchannelsChannels> for ChannelEntry {
+    fn from(value: ListpeerchannelsChannels) -> Self {
+        Self {
+            channel_id: value.channel_id.map(|id| id.to_string()),
+            short_channel_id: value.short_channel_id.map(|scid| scid.to_string()),
+            peer_id: value.peer_id.to_string(),
+            peer_connected: value.peer_connected,
+            state: format!("{:?}", value.state),
+            opener: value.opener.into(),
+            private: value.private,
+            total_msat: value.total_msat.map(|a| a.msat()),
+            to_us_msat: value.to_us_msat.map(|a| a.msat()),
+            spendable_msat: value.spendable_msat.map(|a| a.msat()),
+            receivable_msat: value.receivable_msat.map(|a| a.msat()),
+            funding_txid: value.funding_txid,
+            funding_outnum: value.funding_outnum,
+            fees: value.updates.map(|updates| ChannelFees {
+                fee_base_msat: updates.local.fee_base_msat.msat(),
+                fee_ppm: updates.local.fee_proportional_millionths,
+                htlc_minimum_msat: updates.local.htlc_minimum_msat.msat(),
+                htlc_maximum_msat: updates.local.htlc_maximum_msat.msat(),
+                cltv_expiry_delta: updates.local.cltv_expiry_delta,
+            }),
+        }
+    }
+}
+
+/// Whether `id` (channel id, short channel id or peer id) names one of the listed channels.
+fn matches(channel: &ListpeerchannelsChannels, id: &str) -> bool {
+    channel.channel_id.is_some_and(|c| c.to_string() == id)
+        || channel
+            .short_channel_id
+            .is_some_and(|c| c.to_string() == id)
+        || channel.peer_id.to_string() == id
+}
+
+/// Looks up the channels named by `id`; `Err` is the response to return.
+async fn find_channels<T>(
+    rpc: &mut LightningRPCConnector,
+    id: &str,
+) -> Result<ListpeerchannelsResponse, ApiResponse<T>> {
+    match rpc.listpeerchannels(None).await {
+        Ok(mut res) => {
+            res.channels.retain(|c| matches(c, id));
+            if res.channels.is_empty() {
+                return Err(api_error::build(
+                    StatusCode::NOT_FOUND,
+                    format!("channel not found: {}", id),
+                ));
+            }
+            Ok(res)
+        }
+        Err(e) => Err(api_error::build(StatusCode::BAD_GATEWAY, e.to_string())),
+    }
+}
+
+pub(super) mod list {
+    use super::*;
+
+    #[derive(Debug, Deserialize, utoipa::IntoParams)]
+    pub struct ListChannelsQuery {
+        /// Only return the channels with this peer.
+        pub peer_id: Option<String>,
+    }
+
+    type Ret = ApiResponse<Vec<ChannelEntry>>;
+
+    #[utoipa::path(
+        get,
+        path = "/channels",
+        tag = "ln-gateway",
+        operation_id = "listChannels",
+        params(ListChannelsQuery),
+        security(("api_key" = ["channels:read"]), ("admin_session" = [])),
+        responses(
+            (status = 200, description = "Channels of the node, including pending and closing ones", body = Vec<ChannelEntry>),
+            (status = 400, description = "Invalid peer id"),
+            (status = 401, description = "Admin session or API key required"),
+            (status = 403, description = "Not an admin session, or the API key lacks the scope"),
+            (status = 502, description = "The CoreLightning node encountered an error")
+        )
+    )]
+    pub async fn handler(
+        State(state): State<Arc<Context>>,
+        _admin: AdminSession,
+        Query(q): Query<ListChannelsQuery>,
+    ) -> Ret {
+        let peer_id = match q.peer_id.as_deref().map(str::trim).map(PublicKey::from_str) {
+            Some(Ok(id)) => Some(id),
+            Some(Err(e)) => {
+                return api_error::build(StatusCode::BAD_REQUEST, format!("invalid peer_id: {e}"));
+            }
+            None => None,
+        };
+
+        let mut rpc = state.cln_client.lock().await;
+        match rpc.listpeerchannels(peer_id).await {
+            Ok(res) => {
+                ApiResponse::make_ok(res.channels.into_iter().map(ChannelEntry::from).collect())
+            }
+            Err(e) => api_error::build(StatusCode::BAD_GATEWAY, e.to_string()),
+        }
+    }
+}
+
+pub(super) mod close {
+    use super::*;
+
+    #[derive(Debug, Default, Deserialize, utoipa::ToSchema)]
+    pub struct CloseChannelRequest {
+        /// Seconds to negotiate a mutual close before force-closing; `0` never force-closes.
+        /// Defaults to CLN's 48 hours. The request returns once the closing transaction is
+        /// broadcast.
+        pub unilateral_timeout_secs: Option<u32>,
+        /// Bitcoin address receiving our balance instead of the node wallet.
+        pub destination: Option<String>,
+    }
+
+    #[derive(Debug, Clone, Copy, Serialize, utoipa::ToSchema)]
+    #[serde(rename_all = "snake_case")]
+    pub enum CloseKind {
+        /// Cooperative close.
+        Mutual,
+        /// Force close after the timeout.
+        Unilateral,
+        /// The channel was never funded.
+        Unopened,
+    }
+
+    impl From<CloseType> for CloseKind {
+        fn from(value: CloseType) -> Self {
+            match value {
+                CloseType::MUTUAL => CloseKind::Mutual,
+                CloseType::UNILATERAL => CloseKind::Unilateral,
+                CloseType::UNOPENED => CloseKind::Unopened,
+            }
+        }
+    }
+
+    #[derive(Debug, Serialize, utoipa::ToSchema)]
+    pub struct CloseChannelResponse {
+        #[serde(rename = "type")]
+        pub kind: CloseKind,
+        /// Closing transaction ids (none for `unopened`).
+        pub txids: Vec<String>,
+    }
+
+    type Ret = ApiResponse<CloseChannelResponse>;
+
+    #[utoipa::path(
+        post,
+        path = "/channels/{id}/close",
+        tag = "ln-gateway",
+        operation_id = "closeChannel",
+        params(
+            ("id" = String, Path, description = "Channel id, short channel id, or peer id (when there is a single channel with the peer)")
+        ),
+        request_body = CloseChannelRequest,
+        security(("api_key" = ["channels:manage"]), ("admin_session" = [])),
+        responses(
+            (status = 200, description = "Closing transaction broadcast", body = CloseChannelResponse),
+            (status = 400, description = "Invalid destination, or several channels with the peer"),
+            (status = 401, description = "Admin session or API key required"),
+            (status = 403, description = "Not an admin session, the API key lacks the scope, or the rune does not allow close"),
+            (status = 404, description = "No such channel"),
+            (status = 502, description = "The CoreLightning node encountered an error")
+        )
+    )]
+    pub async fn handler(
+        State(state): State<Arc<Context>>,
+        _admin: AdminSession,
+        rune: CallerRune,
+        Path(id): Path<String>,
+        body: Option<Json<CloseChannelRequest>>,
+    ) -> Ret {
+        let Json(body) = body.unwrap_or_default();
+        let id = id.trim().to_string();
+
+        let mut rpc = state.cln_client.lock().await;
+
+        if let Some(destination) = &body.destination {
+            let address = match Address::from_str(destination.trim()) {
+                Ok(address) => address,
+                Err(e) => {
+                    return api_error::build(
+                        StatusCode::BAD_REQUEST,
+                        format!("invalid destination: {e}"),
+                    );
+                }
+            };
+            let network = match rpc.getinfo().await {
+                Ok(info) => info.network,
+                Err(e) => return api_error::build(StatusCode::BAD_GATEWAY, e.to_string()),
+            };
+            if let Some(expected) = utils::address_network(&network)
+                && !address.is_valid_for_network(expected)
+            {
+                return api_error::build(
+                    StatusCode::BAD_REQUEST,
+                    format!("destination is not valid on {network}"),
+                );
+            }
+        }
+
+        let channels = match find_channels(&mut rpc, &id).await {
+            Ok(res) => res.channels,
+            Err(e) => return e,
+        };
+        if channels.len() > 1 {
+            return api_error::build(
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "{} has {} channels; close them by channel id",
+                    id,
+                    channels.len()
+                ),
+            );
+        }
+
+        let rune_params = serde_json::json!({
+            "id": id,
+            "unilateraltimeout": body.unilateral_timeout_secs,
+            "destination": body.destination,
+        });
+        if let Err(e) = rune.authorize(&state, &mut rpc, "close", rune_params).await {
+            return e;
+        }
+        drop(rpc);
+
+        // `close` can take up to `unilateral_timeout_secs` (48 hours by default), so it gets a
+        // connection of its own instead of blocking every other request on the shared one.
+        let mut rpc = match state.dedicated_cln_client().await {
+            Ok(rpc) => rpc,
+            Err(e) => return api_error::build(StatusCode::BAD_GATEWAY, e.to_string()),
+        };
+        match rpc
+            .close(id.clone(), body.unilateral_timeout_secs, body.destination)
+            .await
+        {
+            Ok(res) => {
+                tracing::info!("Closed channel {} ({:?})", id, res.item_type);
+                ApiResponse::make_ok(CloseChannelResponse {
+                    kind: res.item_type.into(),
+                    txids: res.txids.unwrap_or_default(),
+                })
+            }
+            Err(e) => api_error::build(StatusCode::BAD_GATEWAY, e.to_string()),
+        }
+    }
+}
+
+pub(super) mod fees {
+    use super::*;
+
+    #[derive(Debug, Deserialize, utoipa::ToSchema)]
+    pub struct UpdateChannelFeesRequest {
+        pub fee_base_msat: Option<u64>,
+        /// Proportional fee in parts per million.
+        pub fee_ppm: Option<u32>,
+        pub htlc_minimum_msat: Option<u64>,
+        pub htlc_maximum_msat: Option<u64>,
+    }
+
+    #[derive(Debug, Serialize, utoipa::ToSchema)]
+    pub struct ChannelFeesEntry {
+        pub channel_id: String,
+        pub short_channel_id: Option<String>,
+        pub peer_id: String,
+        pub fee_base_msat: u64,
+        pub fee_ppm: u32,
+        pub htlc_minimum_msat: u64,
+        pub htlc_maximum_msat: u64,
+        /// CLN adjusted an HTLC limit to what the channel allows.
+        pub warnings: Vec<String>,
+    }
+
+    impl From<SetchannelChannels> for ChannelFeesEntry {
+        fn from(value: SetchannelChannels) -> Self {
+            Self {
+                channel_id: value.channel_id.to_string(),
+                short_channel_id: value.short_channel_id.map(|scid| scid.to_string()),
+                peer_id: value.peer_id.to_string(),
+                fee_base_msat: value.fee_base_msat.msat(),
+                fee_ppm: value.fee_proportional_millionths,
+                htlc_minimum_msat: value.minimum_htlc_out_msat.msat(),
+                htlc_maximum_msat: value.maximum_htlc_out_msat.msat(),
+                warnings: [
+                    value.warning_htlcmin_too_low,
+                    value.warning_htlcmax_too_high,
+                ]
+                .into_iter()
+                .flatten()
+                .collect(),
+            }
+        }
+    }
+
+    type Ret = ApiResponse<Vec<ChannelFeesEntry>>;
+
+    #[utoipa::path(
+        post,
+        path = "/channels/{id}/fees",
+        tag = "ln-gateway",
+        operation_id = "updateChannelFees",
+        params(
+            ("id" = String, Path, description = "Channel id, short channel id, peer id (all its channels), or `all`")
+        ),
+        request_body = UpdateChannelFeesRequest,
+        security(("api_key" = ["channels:manage"]), ("admin_session" = [])),
+        responses(
+            (status = 200, description = "The updated channels", body = Vec<ChannelFeesEntry>),
+            (status = 400, description = "Nothing to update, or htlc_minimum_msat above htlc_maximum_msat"),
+            (status = 401, description = "Admin session or API key required"),
+            (status = 403, description = "Not an admin session, the API key lacks the scope, or the rune does not allow setchannel"),
+            (status = 404, description = "No such channel"),
+            (status = 502, description = "The CoreLightning node encountered an error")
+        )
+    )]
+    pub async fn handler(
+        State(state): State<Arc<Context>>,
+        _admin: AdminSession,
+        rune: CallerRune,
+        Path(id): Path<String>,
+        Json(body): Json<UpdateChannelFeesRequest>,
+    ) -> Ret {
+        if body.fee_base_msat.is_none()
+            && body.fee_ppm.is_none()
+            && body.htlc_minimum_msat.is_none()
+            && body.htlc_maximum_msat.is_none()
+        {
+            return api_error::build(StatusCode::BAD_REQUEST, "nothing to update");
+        }
+        if let (Some(min), Some(max)) = (body.htlc_minimum_msat, body.htlc_maximum_msat)
+            && min > max
+        {
+            return api_error::build(
+                StatusCode::BAD_REQUEST,
+                "htlc_minimum_msat is above htlc_maximum_msat",
+            );
+        }
+        let id = id.trim().to_string();
+
+        let mut rpc = state.cln_client.lock().await;
+
+        // `setchannel` fails for unknown ids with a generic RPC error, so look them up first.
+        if id != "all"
+            && let Err(e) = find_channels(&mut rpc, &id).await
+        {
+            return e;
+        }
+
+        let rune_params = serde_json::json!({
+            "id": id,
+            "feebase": body.fee_base_msat,
+            "feeppm": body.fee_ppm,
+            "htlcmin": body.htlc_minimum_msat,
+            "htlcmax": body.htlc_maximum_msat,
+        });
+        if let Err(e) = rune
+            .authorize(&state, &mut rpc, "setchannel", rune_params)
+            .await
+        {
+            return e;
+        }
+
+        match rpc
+            .setchannel(
+                id.clone(),
+                body.fee_base_msat,
+                body.fee_ppm,
+                body.htlc_minimum_msat,
+                body.htlc_maximum_msat,
+            )
+            .await
+        {
+            Ok(res) => {
+                tracing::info!(
+                    "Updated fees of {} channel(s) for {}",
+                    res.channels.len(),
+                    id
+                );
+                ApiResponse::make_ok(
+                    res.channels
+                        .into_iter()
+                        .map(ChannelFeesEntry::from)
+                        .collect(),
+                )
+            }
+            Err(e) => api_error::build(StatusCode::BAD_GATEWAY, e.to_string()),
+        }
+    }
+}
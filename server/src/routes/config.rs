@@ -0,0 +1,56 @@
+use std::sync::Arc;
+
+use axum::{extract::State, http::StatusCode};
+use serde::Serialize;
+
+use crate::{
+    context::Context,
+    core::runtime_config::ConfigChange,
+    routes::{ApiResponse, api_error, auth_session::AdminSession},
+};
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub(super) struct ConfigReloadResponse {
+    /// Version of the runtime config now in effect.
+    pub version: u64,
+    /// Short hash of the reloadable settings now in effect.
+    pub hash: String,
+    /// Settings changed by this reload (empty when nothing changed).
+    pub changes: Vec<ConfigChange>,
+}
+
+pub(super) mod reload {
+    use super::*;
+
+    type Ret = ApiResponse<ConfigReloadResponse>;
+
+    #[utoipa::path(
+        post,
+        path = "/config/reload",
+        tag = "ln-gateway",
+        operation_id = "reloadConfig",
+        security(("api_key" = ["admin"]), ("admin_session" = [])),
+        responses(
+            (status = 200, description = "Reloadable settings re-read from .env and the config file", body = ConfigReloadResponse),
+            (status = 422, description = "The new settings are invalid; the current ones stay in effect"),
+            (status = 401, description = "Admin session or API key required"),
+            (status = 403, description = "Not an admin session, or the API key lacks the scope")
+        )
+    )]
+    pub async fn handler(State(state): State<Arc<Context>>, _admin: AdminSession) -> Ret {
+        match state.reload_runtime_config() {
+            Ok(changes) => {
+                let runtime = state.runtime.load();
+                ApiResponse::make_ok(ConfigReloadResponse {
+                    version: runtime.version,
+                    hash: runtime.hash.clone(),
+                    changes,
+                })
+            }
+            Err(e) => {
+                tracing::error!("Config reload failed: {:#}", e);
+                api_error::build(StatusCode::UNPROCESSABLE_ENTITY, format!("{:#}", e))
+            }
+        }
+    }
+}
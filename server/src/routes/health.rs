@@ -7,7 +7,7 @@ use serde::{Deserialize, Serialize};
 use crate::{
     context::Context,
     core::bitcoin_rpc_connector::BitcoinRPCSnapshot,
-    routes::{ApiResponse, api_error},
+    routes::{ApiResponse, api_error, auth_session::MonitoringAccess},
 };
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, utoipa::ToSchema)]
@@ -137,6 +137,10 @@ pub(super) struct HealthResponse {
     pub warning_bitcoind_sync: Option<String>,
     /// Warning message when lightningd is not in sync (may be absent).
     pub warning_lightningd_sync: Option<String>,
+    /// Version of the runtime config, incremented by every reload that changed a setting.
+    pub config_version: u64,
+    /// Short hash of the reloadable settings in effect.
+    pub config_hash: String,
 }
 
 type Ret = ApiResponse<HealthResponse>;
@@ -146,12 +150,15 @@ type Ret = ApiResponse<HealthResponse>;
     path = "/health",
     tag = "ln-gateway",
     operation_id = "health",
+    security(("api_key" = ["read:health"]), ("admin_session" = [])),
     responses(
         (status = 200, description = "Gateway and CoreLightning status", body = HealthResponse),
+        (status = 401, description = "API key or admin session required (once `SERVER_API_KEYS` is set)"),
+        (status = 403, description = "The API key lacks the scope, or not an admin session"),
         (status = 502, description = "The CoreLightning node encountered an error")
     )
 )]
-pub(super) async fn handler(State(state): State<Arc<Context>>) -> Ret {
+pub(super) async fn handler(State(state): State<Arc<Context>>, _access: MonitoringAccess) -> Ret {
     let mut rpc = state.cln_client.lock().await;
 
     let cln_info = match rpc.getinfo().await {
@@ -174,13 +181,16 @@ pub(super) async fn handler(State(state): State<Arc<Context>>) -> Ret {
         }
     };
 
+    let runtime = state.runtime.load_full();
     let status = HealthResponse {
         lightning: LightningInfo::from(cln_info.clone()),
         bitcoin: btc_info,
-        min_withdrawable_msat: state.args.min_withdrawable_msat,
-        max_withdrawable_msat: state.args.max_withdrawable_msat,
+        min_withdrawable_msat: runtime.min_withdrawable_msat,
+        max_withdrawable_msat: runtime.max_withdrawable_msat,
         warning_bitcoind_sync: cln_info.warning_bitcoind_sync,
         warning_lightningd_sync: cln_info.warning_lightningd_sync,
+        config_version: runtime.version,
+        config_hash: runtime.hash.clone(),
     };
 
     ApiResponse::make_ok(status)
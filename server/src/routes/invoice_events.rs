@@ -0,0 +1,211 @@
+use std::sync::Arc;
+
+use axum::extract::{Query, State};
+use serde::Deserialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::{
+    context::Context, core::invoice_events::InvoiceSettledEvent, routes::auth_session::StreamAccess,
+};
+
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+pub(super) struct InvoiceEventsQuery {
+    /// Only forward settlements of the invoice with this label.
+    pub label: Option<String>,
+    /// Only forward settlements of the invoice with this payment hash (hex).
+    pub payment_hash: Option<String>,
+}
+
+impl InvoiceEventsQuery {
+    fn matches(&self, event: &InvoiceSettledEvent) -> bool {
+        self.label.as_ref().is_none_or(|l| *l == event.label)
+            && self
+                .payment_hash
+                .as_ref()
+                .is_none_or(|h| h.eq_ignore_ascii_case(&event.payment_hash))
+    }
+
+    /// Waits for the next event matching the filter. Returns `None` once the channel is closed.
+    async fn next(
+        &self,
+        rx: &mut broadcast::Receiver<InvoiceSettledEvent>,
+    ) -> Option<InvoiceSettledEvent> {
+        loop {
+            match rx.recv().await {
+                Ok(event) if self.matches(&event) => return Some(event),
+                Ok(_) => continue,
+                Err(RecvError::Lagged(n)) => {
+                    tracing::warn!("Invoice event subscriber lagged, skipped {n} events");
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+pub(super) mod sse {
+    use super::*;
+
+    use std::convert::Infallible;
+
+    use axum::response::sse::{Event, KeepAlive, Sse};
+    use futures_util::{Stream, StreamExt};
+
+    #[utoipa::path(
+        get,
+        path = "/events",
+        tag = "ln-gateway",
+        operation_id = "invoiceEvents",
+        params(
+            InvoiceEventsQuery,
+            ("ticket" = Option<String>, Query, description = "One-time ticket from `POST /events/ticket`, for browsers that can't send credentials in headers")
+        ),
+        security(("api_key" = ["invoices:read"]), ("admin_session" = [])),
+        responses(
+            (status = 200, description = "Server-Sent Events stream of `invoice_settled` events", content_type = "text/event-stream", body = InvoiceSettledEvent),
+            (status = 401, description = "Admin session, API key or a valid ticket required"),
+            (status = 403, description = "Not an admin session, or the API key lacks the scope")
+        )
+    )]
+    pub async fn handler(
+        State(state): State<Arc<Context>>,
+        _access: StreamAccess,
+        Query(q): Query<InvoiceEventsQuery>,
+    ) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+        let rx = state.invoice_events.subscribe();
+
+        let stream = futures_util::stream::unfold((q, rx), |(q, mut rx)| async move {
+            let event = q.next(&mut rx).await?;
+            let sse = Event::default()
+                .event("invoice_settled")
+                .json_data(&event)
+                .unwrap_or_else(|_| Event::default().comment("unserializable event"));
+
+            Some((Ok(sse), (q, rx)))
+        });
+
+        // Ends the stream on shutdown so the connection can drain.
+        let stream = stream.take_until(state.shutdown.wait());
+
+        Sse::new(stream).keep_alive(KeepAlive::default())
+    }
+}
+
+pub(super) mod ws {
+    use super::*;
+
+    use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+    use axum::response::Response;
+
+    #[utoipa::path(
+        get,
+        path = "/ws",
+        tag = "ln-gateway",
+        operation_id = "invoiceEventsWs",
+        params(
+            InvoiceEventsQuery,
+            ("ticket" = Option<String>, Query, description = "One-time ticket from `POST /events/ticket`, for browsers that can't send credentials in headers")
+        ),
+        security(("api_key" = ["invoices:read"]), ("admin_session" = [])),
+        responses(
+            (status = 101, description = "WebSocket upgrade; each text frame is a JSON InvoiceSettledEvent", body = InvoiceSettledEvent),
+            (status = 401, description = "Admin session, API key or a valid ticket required"),
+            (status = 403, description = "Not an admin session, or the API key lacks the scope")
+        )
+    )]
+    pub async fn handler(
+        State(state): State<Arc<Context>>,
+        _access: StreamAccess,
+        Query(q): Query<InvoiceEventsQuery>,
+        ws: WebSocketUpgrade,
+    ) -> Response {
+        let rx = state.invoice_events.subscribe();
+        ws.on_upgrade(move |socket| forward(socket, q, rx, state))
+    }
+
+    async fn forward(
+        mut socket: WebSocket,
+        q: InvoiceEventsQuery,
+        mut rx: broadcast::Receiver<InvoiceSettledEvent>,
+        state: Arc<Context>,
+    ) {
+        let shutdown = state.shutdown.wait();
+        tokio::pin!(shutdown);
+
+        loop {
+            tokio::select! {
+                _ = &mut shutdown => {
+                    let _ = socket.send(Message::Close(None)).await;
+                    break;
+                }
+                event = q.next(&mut rx) => {
+                    let Some(event) = event else { break };
+                    let Ok(json) = serde_json::to_string(&event) else { continue };
+                    if socket.send(Message::Text(json.into())).await.is_err() {
+                        break;
+                    }
+                }
+                // Clients are not expected to send anything; we only watch for close/errors.
+                msg = socket.recv() => match msg {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => continue,
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::{Method, Request, StatusCode};
+    use tower::ServiceExt;
+
+    use crate::routes::tests::{api_key, app, context, send};
+
+    /// Status of `GET uri`, without waiting for the endless stream body.
+    async fn open(ctx: &std::sync::Arc<crate::context::Context>, uri: &str) -> StatusCode {
+        let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        app(ctx).oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn streams_accept_a_one_time_ticket() {
+        let ctx = context(&[api_key("reader", "invoices:read")]).await;
+
+        assert_eq!(open(&ctx, "/events").await, StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            open(&ctx, "/events?ticket=bogus").await,
+            StatusCode::UNAUTHORIZED
+        );
+
+        let (status, _) = send(app(&ctx), Method::POST, "/events/ticket", None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "tickets need credentials");
+
+        let (status, body) = send(app(&ctx), Method::POST, "/events/ticket", Some("reader")).await;
+        assert_eq!(status, StatusCode::OK);
+        let ticket = body["ticket"].as_str().unwrap().to_string();
+
+        let uri = format!("/events?label=x&ticket={ticket}");
+        assert_eq!(open(&ctx, &uri).await, StatusCode::OK);
+        assert_eq!(
+            open(&ctx, &uri).await,
+            StatusCode::UNAUTHORIZED,
+            "a ticket is redeemed once"
+        );
+    }
+
+    #[tokio::test]
+    async fn websocket_accepts_a_ticket() {
+        let ctx = context(&[api_key("reader", "invoices:read")]).await;
+
+        assert_eq!(open(&ctx, "/ws").await, StatusCode::UNAUTHORIZED);
+
+        let ticket = ctx.auth_sessions.issue_ticket().await.ticket;
+        // Past authorization, the plain (non-upgradable) test request is turned down by the
+        // WebSocket extractor instead.
+        let status = open(&ctx, &format!("/ws?ticket={ticket}")).await;
+        assert_ne!(status, StatusCode::UNAUTHORIZED);
+        assert!(status.is_client_error(), "{status}");
+    }
+}
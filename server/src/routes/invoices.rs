@@ -0,0 +1,409 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use cln_rpc::model::requests::DelinvoiceStatus;
+use cln_rpc::model::responses::{
+    DelinvoiceResponse, DelinvoiceStatus as DelinvoiceResponseStatus, InvoiceResponse,
+    ListinvoicesInvoices, ListinvoicesInvoicesStatus,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    context::Context,
+    routes::{ApiResponse, api_error, auth_session::AdminSession, rune::CallerRune},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub(super) enum InvoiceStatus {
+    Unpaid,
+    Paid,
+    Expired,
+}
+
+impl From<ListinvoicesInvoicesStatus> for InvoiceStatus {
+    fn from(value: ListinvoicesInvoicesStatus) -> Self {
+        match value {
+            ListinvoicesInvoicesStatus::UNPAID => InvoiceStatus::Unpaid,
+            ListinvoicesInvoicesStatus::PAID => InvoiceStatus::Paid,
+            ListinvoicesInvoicesStatus::EXPIRED => InvoiceStatus::Expired,
+        }
+    }
+}
+
+impl From<DelinvoiceResponseStatus> for InvoiceStatus {
+    fn from(value: DelinvoiceResponseStatus) -> Self {
+        match value {
+            DelinvoiceResponseStatus::UNPAID => InvoiceStatus::Unpaid,
+            DelinvoiceResponseStatus::PAID => InvoiceStatus::Paid,
+            DelinvoiceResponseStatus::EXPIRED => InvoiceStatus::Expired,
+        }
+    }
+}
+
+impl From<InvoiceStatus> for DelinvoiceStatus {
+    fn from(value: InvoiceStatus) -> Self {
+        match value {
+            InvoiceStatus::Unpaid => DelinvoiceStatus::UNPAID,
+            InvoiceStatus::Paid => DelinvoiceStatus::PAID,
+            InvoiceStatus::Expired => DelinvoiceStatus::EXPIRED,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub(super) struct InvoiceEntry {
+    /// Unique label assigned by the gateway.
+    pub label: String,
+    /// Current invoice status.
+    pub status: InvoiceStatus,
+    /// BOLT11 payment request (absent for BOLT12 invoices).
+    pub bolt11: Option<String>,
+    /// Payment hash (hex).
+    pub payment_hash: String,
+    /// Requested amount in millisatoshis (absent for "any amount" invoices).
+    pub amount_msat: Option<u64>,
+    /// Amount actually received in millisatoshis (only once paid).
+    pub amount_received_msat: Option<u64>,
+    /// Invoice description (absent when only its hash was committed).
+    pub description: Option<String>,
+    /// Unix timestamp (seconds) after which the invoice can no longer be paid.
+    pub expires_at: u64,
+    /// Unix timestamp (seconds) when the invoice was paid.
+    pub paid_at: Option<u64>,
+    /// Monotonic index of the payment among all paid invoices.
+    pub pay_index: Option<u64>,
+    /// Payment preimage (hex), only once paid.
+    pub payment_preimage: Option<String>,
+    /// Creation index, usable as `start` for pagination.
+    pub created_index: Option<u64>,
+}
+
+impl From<ListinvoicesInvoices> for InvoiceEntry {
+    fn from(value: ListinvoicesInvoices) -> Self {
+        Self {
+            label: value.label,
+            status: value.status.into(),
+            bolt11: value.bolt11,
+            payment_hash: value.payment_hash.to_string(),
+            amount_msat: value.amount_msat.map(|a| a.msat()),
+            amount_received_msat: value.amount_received_msat.map(|a| a.msat()),
+            description: value.description,
+            expires_at: value.expires_at,
+            paid_at: value.paid_at,
+            pay_index: value.pay_index,
+            payment_preimage: value.payment_preimage.map(|p| hex::encode(p.to_vec())),
+            created_index: value.created_index,
+        }
+    }
+}
+
+impl From<DelinvoiceResponse> for InvoiceEntry {
+    fn from(value: DelinvoiceResponse) -> Self {
+        Self {
+            label: value.label,
+            status: value.status.into(),
+            bolt11: value.bolt11,
+            payment_hash: value.payment_hash.to_string(),
+            amount_msat: value.amount_msat.map(|a| a.msat()),
+            amount_received_msat: value.amount_received_msat.map(|a| a.msat()),
+            description: value.description,
+            expires_at: value.expires_at,
+            paid_at: value.paid_at,
+            pay_index: value.pay_index,
+            payment_preimage: value.payment_preimage.map(|p| hex::encode(p.to_vec())),
+            created_index: value.created_index,
+        }
+    }
+}
+
+pub(super) mod create {
+    use super::*;
+
+    #[derive(Debug, Deserialize, utoipa::ToSchema)]
+    pub struct CreateInvoiceRequest {
+        /// Amount in millisatoshis. Omit to let the payer choose ("any" amount).
+        pub amount_msat: Option<u64>,
+        /// Invoice description shown to the payer.
+        #[serde(default)]
+        pub description: String,
+        /// Commit only the SHA256 hash of `description` in the invoice (e.g. for LNURL-pay).
+        pub description_hash_only: Option<bool>,
+        /// Expiry in seconds (CoreLightning defaults to one week).
+        pub expiry: Option<u64>,
+    }
+
+    #[derive(Debug, Serialize, utoipa::ToSchema)]
+    pub struct CreateInvoiceResponse {
+        /// Unique label generated by the gateway, used for lookups.
+        pub label: String,
+        /// BOLT11 payment request.
+        pub bolt11: String,
+        /// Payment hash (hex).
+        pub payment_hash: String,
+        /// Unix timestamp (seconds) after which the invoice can no longer be paid.
+        pub expires_at: u64,
+    }
+
+    impl CreateInvoiceResponse {
+        fn new(label: String, res: InvoiceResponse) -> Self {
+            Self {
+                label,
+                bolt11: res.bolt11,
+                payment_hash: res.payment_hash.to_string(),
+                expires_at: res.expires_at,
+            }
+        }
+    }
+
+    type Ret = ApiResponse<CreateInvoiceResponse>;
+
+    #[utoipa::path(
+        post,
+        path = "/invoices",
+        tag = "ln-gateway",
+        operation_id = "createInvoice",
+        request_body = CreateInvoiceRequest,
+        security(("api_key" = ["invoices:write"]), ("admin_session" = [])),
+        responses(
+            (status = 201, description = "Invoice created", body = CreateInvoiceResponse),
+            (status = 400, description = "Invalid invoice parameters"),
+            (status = 401, description = "Admin session or API key required, or a rune (`SERVER_REQUIRE_RUNE`)"),
+            (status = 403, description = "Not an admin session, the API key lacks the scope, or the rune does not allow invoice"),
+            (status = 502, description = "The CoreLightning node encountered an error")
+        )
+    )]
+    pub async fn handler(
+        State(state): State<Arc<Context>>,
+        _admin: AdminSession,
+        rune: CallerRune,
+        Json(body): Json<CreateInvoiceRequest>,
+    ) -> Ret {
+        if body.amount_msat == Some(0) {
+            return api_error::build(StatusCode::BAD_REQUEST, "amount_msat must be positive");
+        }
+
+        if body.expiry == Some(0) {
+            return api_error::build(StatusCode::BAD_REQUEST, "expiry must be positive");
+        }
+
+        let label = format!("ln-gateway-{}", uuid::Uuid::new_v4());
+
+        let mut rpc = state.cln_client.lock().await;
+
+        let rune_params = serde_json::json!({
+            "amount_msat": body.amount_msat.map_or("any".to_string(), |msat| msat.to_string()),
+            "label": label,
+            "description": body.description,
+            "expiry": body.expiry,
+            "deschashonly": body.description_hash_only,
+        });
+        if let Err(e) = rune
+            .authorize(&state, &mut rpc, "invoice", rune_params)
+            .await
+        {
+            return e;
+        }
+
+        let res = match rpc
+            .invoice(
+                body.amount_msat,
+                label.clone(),
+                body.description,
+                body.expiry,
+                body.description_hash_only,
+            )
+            .await
+        {
+            Ok(res) => res,
+            Err(e) => return api_error::build(StatusCode::BAD_GATEWAY, e.to_string()),
+        };
+
+        ApiResponse::Ok {
+            status: StatusCode::CREATED,
+            data: CreateInvoiceResponse::new(label, res),
+        }
+    }
+}
+
+pub(super) mod get {
+    use super::*;
+
+    type Ret = ApiResponse<InvoiceEntry>;
+
+    #[utoipa::path(
+        get,
+        path = "/invoices/{label}",
+        tag = "ln-gateway",
+        operation_id = "getInvoice",
+        params(
+            ("label" = String, Path, description = "Invoice label returned by POST /invoices")
+        ),
+        security(("api_key" = ["invoices:read"]), ("admin_session" = [])),
+        responses(
+            (status = 200, description = "Invoice details", body = InvoiceEntry),
+            (status = 401, description = "Admin session or API key required"),
+            (status = 403, description = "Not an admin session, or the API key lacks the scope"),
+            (status = 404, description = "No invoice with this label"),
+            (status = 502, description = "The CoreLightning node encountered an error")
+        )
+    )]
+    pub async fn handler(
+        State(state): State<Arc<Context>>,
+        _admin: AdminSession,
+        Path(label): Path<String>,
+    ) -> Ret {
+        let mut rpc = state.cln_client.lock().await;
+        let res = match rpc.listinvoices(Some(label.clone()), None, None).await {
+            Ok(res) => res,
+            Err(e) => return api_error::build(StatusCode::BAD_GATEWAY, e.to_string()),
+        };
+
+        match res.invoices.into_iter().next() {
+            Some(invoice) => ApiResponse::make_ok(invoice.into()),
+            None => api_error::build(
+                StatusCode::NOT_FOUND,
+                format!("invoice not found: {}", label),
+            ),
+        }
+    }
+}
+
+pub(super) mod list {
+    use super::*;
+
+    #[derive(Debug, Deserialize, utoipa::IntoParams)]
+    pub struct ListInvoicesQuery {
+        /// Only return invoices with this status.
+        pub status: Option<InvoiceStatus>,
+        /// Creation index to start from (use `next_start` from the previous page).
+        pub start: Option<u64>,
+        /// Maximum number of invoices scanned for this page (default: 25, max: 100).
+        pub limit: Option<u32>,
+    }
+
+    #[derive(Debug, Serialize, utoipa::ToSchema)]
+    pub struct ListInvoicesResponse {
+        /// Invoices in this page, filtered by `status` when requested.
+        pub invoices: Vec<InvoiceEntry>,
+        /// Creation index of the next page (absent when there are no more invoices).
+        pub next_start: Option<u64>,
+    }
+
+    type Ret = ApiResponse<ListInvoicesResponse>;
+
+    #[utoipa::path(
+        get,
+        path = "/invoices",
+        tag = "ln-gateway",
+        operation_id = "listInvoices",
+        params(ListInvoicesQuery),
+        security(("api_key" = ["invoices:read"]), ("admin_session" = [])),
+        responses(
+            (status = 200, description = "Page of invoices in creation order", body = ListInvoicesResponse),
+            (status = 401, description = "Admin session or API key required"),
+            (status = 403, description = "Not an admin session, or the API key lacks the scope"),
+            (status = 502, description = "The CoreLightning node encountered an error")
+        )
+    )]
+    pub async fn handler(
+        State(state): State<Arc<Context>>,
+        _admin: AdminSession,
+        Query(q): Query<ListInvoicesQuery>,
+    ) -> Ret {
+        let limit = q.limit.unwrap_or(25).clamp(1, 100);
+        let start = q.start.unwrap_or(0);
+
+        let mut rpc = state.cln_client.lock().await;
+        let res = match rpc.listinvoices(None, Some(start), Some(limit)).await {
+            Ok(res) => res,
+            Err(e) => return api_error::build(StatusCode::BAD_GATEWAY, e.to_string()),
+        };
+
+        // The status filter is applied per page, so a page may hold fewer than `limit` entries
+        // while `next_start` still points at more invoices.
+        let next_start = if res.invoices.len() as u32 == limit {
+            res.invoices
+                .last()
+                .and_then(|i| i.created_index)
+                .map(|i| i + 1)
+        } else {
+            None
+        };
+
+        let invoices = res
+            .invoices
+            .into_iter()
+            .map(InvoiceEntry::from)
+            .filter(|i| q.status.is_none_or(|s| s == i.status))
+            .collect();
+
+        ApiResponse::make_ok(ListInvoicesResponse {
+            invoices,
+            next_start,
+        })
+    }
+}
+
+pub(super) mod delete {
+    use super::*;
+
+    type Ret = ApiResponse<InvoiceEntry>;
+
+    #[utoipa::path(
+        delete,
+        path = "/invoices/{label}",
+        tag = "ln-gateway",
+        operation_id = "deleteInvoice",
+        params(
+            ("label" = String, Path, description = "Invoice label returned by POST /invoices")
+        ),
+        security(("api_key" = ["admin"]), ("admin_session" = [])),
+        responses(
+            (status = 200, description = "The deleted invoice", body = InvoiceEntry),
+            (status = 404, description = "No invoice with this label"),
+            (status = 502, description = "The CoreLightning node encountered an error"),
+            (status = 401, description = "Admin session or API key required"),
+            (status = 403, description = "Not an admin session, or the API key lacks the scope")
+        )
+    )]
+    pub async fn handler(
+        State(state): State<Arc<Context>>,
+        _admin: AdminSession,
+        rune: CallerRune,
+        Path(label): Path<String>,
+    ) -> Ret {
+        let mut rpc = state.cln_client.lock().await;
+
+        // `delinvoice` only succeeds when the expected status matches, so look it up first.
+        let status = match rpc.listinvoices(Some(label.clone()), None, None).await {
+            Ok(res) => match res.invoices.into_iter().next() {
+                Some(invoice) => InvoiceStatus::from(invoice.status),
+                None => {
+                    return api_error::build(
+                        StatusCode::NOT_FOUND,
+                        format!("invoice not found: {}", label),
+                    );
+                }
+            },
+            Err(e) => return api_error::build(StatusCode::BAD_GATEWAY, e.to_string()),
+        };
+
+        let rune_params = serde_json::json!({ "label": label, "status": status });
+        if let Err(e) = rune
+            .authorize(&state, &mut rpc, "delinvoice", rune_params)
+            .await
+        {
+            return e;
+        }
+
+        match rpc.delinvoice(label, status.into()).await {
+            Ok(res) => ApiResponse::make_ok(res.into()),
+            Err(e) => api_error::build(StatusCode::BAD_GATEWAY, e.to_string()),
+        }
+    }
+}
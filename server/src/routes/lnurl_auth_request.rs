@@ -1,18 +1,18 @@
 use std::sync::Arc;
 
-use axum::extract::{Query, Request, State};
+use axum::{
+    extract::{Query, Request, State},
+    http::StatusCode,
+};
 use serde::{Deserialize, Serialize};
 
-use crate::{context::Context, core::utils, routes::ApiResponse};
+use crate::{
+    context::Context,
+    core::{auth_session::PendingChallenge, utils},
+    routes::{ApiResponse, api_error},
+};
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, utoipa::ToSchema)]
-#[serde(rename_all = "lowercase")]
-pub(super) enum LnUrlAuthRequestAction {
-    Register,
-    Login,
-    Link,
-    Auth,
-}
+pub(super) use crate::core::auth_session::LnUrlAuthRequestAction;
 
 #[derive(Debug, Deserialize, utoipa::ToSchema)]
 pub(super) struct LnUrlAuthRequestQuery {
@@ -43,7 +43,8 @@ type Ret = ApiResponse<LnUrlAuthRequestResponse>;
         ("action" = Option<LnUrlAuthRequestAction>, Query, description = "Optional action enum: register | login | link | auth")
     ),
     responses(
-        (status = 200, description = "LNURL-auth challenge", body = LnUrlAuthRequestResponse)
+        (status = 200, description = "LNURL-auth challenge", body = LnUrlAuthRequestResponse),
+        (status = 503, description = "The server is shutting down")
     )
 )]
 pub(super) async fn handler(
@@ -51,13 +52,19 @@ pub(super) async fn handler(
     Query(query): Query<LnUrlAuthRequestQuery>,
     request: Request,
 ) -> Ret {
+    if state.shutdown.is_draining() {
+        return api_error::build(StatusCode::SERVICE_UNAVAILABLE, "Server is shutting down");
+    }
+
     let k1 = utils::gen_k1_as_string();
     {
-        let mut set = state.auth_pending_keys_set.lock().await;
-        set.insert(k1.clone());
+        let challenge = PendingChallenge::new(query.action);
+        let mut pending = state.auth_pending_keys.lock().await;
+        pending.retain(|_, c| !c.is_expired(challenge.created_at));
+        pending.insert(k1.clone(), challenge);
     }
 
-    let base_url = utils::request_base_url(&request, "0.0.0.0", state.args.listening_port);
+    let base_url = utils::request_base_url(&request, &state, "0.0.0.0");
     ApiResponse::make_ok(LnUrlAuthRequestResponse {
         tag: "login",
         k1,
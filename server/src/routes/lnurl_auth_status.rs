@@ -0,0 +1,170 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+};
+use serde::Serialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::{
+    context::Context,
+    core::{auth_session::AuthSession, utils},
+    routes::{ApiResponse, api_error},
+};
+
+#[derive(Debug, Clone, Copy, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub(super) enum LnUrlAuthStatus {
+    /// The wallet has not signed the challenge yet.
+    Pending,
+    /// The challenge was signed and a session was issued.
+    Authenticated,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub(super) struct LnUrlAuthStatusResponse {
+    pub status: LnUrlAuthStatus,
+    /// Session issued for the linking key; only returned once.
+    pub session: Option<AuthSession>,
+}
+
+type Ret = ApiResponse<LnUrlAuthStatusResponse>;
+
+async fn lookup(state: &Context, k1: &str) -> Ret {
+    let now = utils::unix_time_ms() / 1000;
+    match state.auth_pending_keys.lock().await.get(k1) {
+        Some(c) if c.is_expired(now) => {
+            return api_error::build(StatusCode::GONE, "challenge expired");
+        }
+        Some(_) => {
+            return ApiResponse::make_ok(LnUrlAuthStatusResponse {
+                status: LnUrlAuthStatus::Pending,
+                session: None,
+            });
+        }
+        None => {}
+    }
+
+    if let Some(session) = state.auth_sessions.claim(k1).await {
+        return ApiResponse::make_ok(LnUrlAuthStatusResponse {
+            status: LnUrlAuthStatus::Authenticated,
+            session: Some(session),
+        });
+    }
+
+    if state.auth_completed.lock().await.contains_key(k1) {
+        return api_error::build(StatusCode::GONE, "session already claimed or expired");
+    }
+
+    api_error::build(StatusCode::NOT_FOUND, "unknown k1")
+}
+
+pub(super) mod get {
+    use super::*;
+
+    #[utoipa::path(
+        get,
+        path = "/lnurl-auth-status/{k1}",
+        tag = "ln-gateway",
+        operation_id = "lnurlAuthStatus",
+        params(
+            ("k1" = String, Path, description = "Challenge returned by /lnurl-auth-request")
+        ),
+        responses(
+            (status = 200, description = "Login status, with the session once signed", body = LnUrlAuthStatusResponse),
+            (status = 404, description = "Unknown k1"),
+            (status = 410, description = "The session was already handed out or has expired, or the challenge expired")
+        )
+    )]
+    pub async fn handler(State(state): State<Arc<Context>>, Path(k1): Path<String>) -> Ret {
+        lookup(&state, &k1).await
+    }
+}
+
+pub(super) mod sse {
+    use super::*;
+
+    use std::convert::Infallible;
+
+    use axum::response::sse::{Event, KeepAlive, Sse};
+    use axum::response::{IntoResponse, Response};
+    use futures_util::StreamExt;
+
+    /// Waits until a session is issued for `k1` and claims it.
+    async fn wait_for_session(
+        state: &Context,
+        k1: &str,
+        mut rx: broadcast::Receiver<String>,
+    ) -> Option<AuthSession> {
+        loop {
+            match rx.recv().await {
+                Ok(issued) if issued == k1 => break,
+                Ok(_) => continue,
+                // We may have missed our k1; fall through and check the store directly.
+                Err(RecvError::Lagged(_)) => {
+                    if !state.auth_pending_keys.lock().await.contains_key(k1) {
+                        break;
+                    }
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+
+        state.auth_sessions.claim(k1).await
+    }
+
+    #[utoipa::path(
+        get,
+        path = "/lnurl-auth-status/{k1}/events",
+        tag = "ln-gateway",
+        operation_id = "lnurlAuthStatusEvents",
+        params(
+            ("k1" = String, Path, description = "Challenge returned by /lnurl-auth-request")
+        ),
+        responses(
+            (status = 200, description = "Server-Sent Events stream with a single `authenticated` event", content_type = "text/event-stream", body = LnUrlAuthStatusResponse),
+            (status = 404, description = "Unknown k1"),
+            (status = 410, description = "The session was already handed out or has expired, or the challenge expired")
+        )
+    )]
+    pub async fn handler(State(state): State<Arc<Context>>, Path(k1): Path<String>) -> Response {
+        // Subscribe before the lookup so a login completing in between is not missed.
+        let rx = state.auth_sessions.subscribe();
+
+        let ready = match lookup(&state, &k1).await {
+            ApiResponse::Ok { data, .. } => data.session,
+            err => return err.into_response(),
+        };
+
+        let shutdown = state.shutdown.wait();
+        let stream = futures_util::stream::once(async move {
+            let session = match ready {
+                Some(session) => Some(session),
+                None => wait_for_session(&state, &k1, rx).await,
+            };
+
+            let event = match session {
+                Some(session) => Event::default()
+                    .event("authenticated")
+                    .json_data(LnUrlAuthStatusResponse {
+                        status: LnUrlAuthStatus::Authenticated,
+                        session: Some(session),
+                    })
+                    .unwrap_or_else(|_| Event::default().comment("unserializable event")),
+                None => Event::default()
+                    .event("error")
+                    .data("session already claimed or expired"),
+            };
+
+            Ok::<_, Infallible>(event)
+        });
+
+        // Ends the stream on shutdown so the connection can drain.
+        let stream = stream.take_until(shutdown);
+
+        Sse::new(stream)
+            .keep_alive(KeepAlive::default())
+            .into_response()
+    }
+}
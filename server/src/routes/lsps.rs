@@ -0,0 +1,219 @@
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    body::Bytes,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    context::Context,
+    core::lsps::{
+        self, INVALID_REQUEST, LspsError, LspsRequest, LspsResponse, METHOD_NOT_FOUND, PARSE_ERROR,
+        lsps1::{CreateOrderParams, GetOrderParams, Lsps1Order, Lsps1Refund, Lsps1Service},
+        lsps2::{BuyParams, GetInfoParams},
+    },
+    routes::{ApiResponse, api_error, auth_session::AdminSession},
+};
+
+async fn dispatch(state: &Context, method: &str, params: Value) -> Result<Value, LspsError> {
+    let lsps1 = state.args.lsps1_enabled;
+    let lsps2 = state.lsps2.is_enabled();
+
+    let result = match method {
+        "lsps0.list_protocols" => {
+            let protocols: Vec<u16> = [lsps1.then_some(1), lsps2.then_some(2)]
+                .into_iter()
+                .flatten()
+                .collect();
+            serde_json::json!({ "protocols": protocols })
+        }
+        "lsps1.get_info" if lsps1 => {
+            let runtime = state.runtime.load_full();
+            serde_json::to_value(Lsps1Service::options(&state.args, &runtime))
+                .map_err(|e| LspsError::internal(e.to_string()))?
+        }
+        "lsps1.create_order" if lsps1 => {
+            if state.shutdown.is_draining() {
+                return Err(LspsError::internal("Server is shutting down"));
+            }
+            let params: CreateOrderParams = lsps::parse_params(params)?;
+            let order = state.lsps1.create_order(state, params).await?;
+            serde_json::to_value(order).map_err(|e| LspsError::internal(e.to_string()))?
+        }
+        "lsps1.get_order" if lsps1 => {
+            let params: GetOrderParams = lsps::parse_params(params)?;
+            let order = state.lsps1.get_order(&params.order_id).await?;
+            serde_json::to_value(order).map_err(|e| LspsError::internal(e.to_string()))?
+        }
+        "lsps2.get_info" if lsps2 => {
+            let _params: GetInfoParams = lsps::parse_params(params)?;
+            serde_json::to_value(state.lsps2.get_info()?)
+                .map_err(|e| LspsError::internal(e.to_string()))?
+        }
+        "lsps2.buy" if lsps2 => {
+            if state.shutdown.is_draining() {
+                return Err(LspsError::internal("Server is shutting down"));
+            }
+            let params: BuyParams = lsps::parse_params(params)?;
+            let result = state.lsps2.buy(state, params).await?;
+            serde_json::to_value(result).map_err(|e| LspsError::internal(e.to_string()))?
+        }
+        other => {
+            return Err(LspsError::new(
+                METHOD_NOT_FOUND,
+                format!("Method not found: {other}"),
+            ));
+        }
+    };
+
+    Ok(result)
+}
+
+#[utoipa::path(
+    post,
+    path = "/lsps",
+    tag = "ln-gateway",
+    operation_id = "lsps",
+    request_body = LspsRequest,
+    responses(
+        (status = 200, description = "JSON-RPC 2.0 response; failures are reported in `error`", body = LspsResponse)
+    )
+)]
+pub(super) async fn handler(State(state): State<Arc<Context>>, body: Bytes) -> Json<LspsResponse> {
+    let value: Value = match serde_json::from_slice(&body) {
+        Ok(value) => value,
+        Err(e) => {
+            return Json(LspsResponse::new(
+                None,
+                Err(LspsError::new(PARSE_ERROR, format!("Parse error: {e}"))),
+            ));
+        }
+    };
+
+    let id = value.get("id").cloned();
+    let request = match serde_json::from_value::<LspsRequest>(value) {
+        Ok(request) if request.jsonrpc == "2.0" => request,
+        Ok(_) => {
+            return Json(LspsResponse::new(
+                id,
+                Err(LspsError::new(INVALID_REQUEST, "jsonrpc must be \"2.0\"")),
+            ));
+        }
+        Err(e) => {
+            return Json(LspsResponse::new(
+                id,
+                Err(LspsError::new(
+                    INVALID_REQUEST,
+                    format!("Invalid request: {e}"),
+                )),
+            ));
+        }
+    };
+
+    let result = dispatch(&state, &request.method, request.params).await;
+    if let Err(e) = &result {
+        tracing::debug!("LSPS {} failed: {} ({})", request.method, e.message, e.code);
+    }
+
+    Json(LspsResponse::new(request.id, result))
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub(super) struct Lsps1RefundsResponse {
+    pub refunds: Vec<Lsps1Refund>,
+}
+
+pub(super) mod refunds {
+    use super::*;
+
+    type Ret = ApiResponse<Lsps1RefundsResponse>;
+
+    #[utoipa::path(
+        get,
+        path = "/lsps1/refunds",
+        tag = "ln-gateway",
+        operation_id = "lsps1Refunds",
+        security(("api_key" = ["lsps1:refunds"]), ("admin_session" = [])),
+        responses(
+            (status = 200, description = "Paid LSPS1 orders that failed and have to be refunded by hand", body = Lsps1RefundsResponse),
+            (status = 401, description = "Admin session or API key required"),
+            (status = 403, description = "Not an admin session, or the API key lacks the scope")
+        )
+    )]
+    pub async fn handler(State(state): State<Arc<Context>>, _admin: AdminSession) -> Ret {
+        ApiResponse::make_ok(Lsps1RefundsResponse {
+            refunds: state.lsps1.refunds().await,
+        })
+    }
+}
+
+pub(super) mod refunded {
+    use super::*;
+
+    type Ret = ApiResponse<Lsps1Order>;
+
+    #[utoipa::path(
+        post,
+        path = "/lsps1/refunds/{order_id}",
+        tag = "ln-gateway",
+        operation_id = "lsps1MarkRefunded",
+        params(
+            ("order_id" = String, Path, description = "LSPS1 order id")
+        ),
+        security(("api_key" = ["lsps1:refunds"]), ("admin_session" = [])),
+        responses(
+            (status = 200, description = "The order, its payment now `REFUNDED`. Only records the refund, which has to be sent by other means", body = Lsps1Order),
+            (status = 404, description = "No order with this id awaits a refund"),
+            (status = 401, description = "Admin session or API key required"),
+            (status = 403, description = "Not an admin session, or the API key lacks the scope")
+        )
+    )]
+    pub async fn handler(
+        State(state): State<Arc<Context>>,
+        _admin: AdminSession,
+        Path(order_id): Path<String>,
+    ) -> Ret {
+        match state.lsps1.mark_refunded(&order_id).await {
+            Some(order) => ApiResponse::make_ok(order),
+            None => api_error::build(
+                StatusCode::NOT_FOUND,
+                format!("no order awaiting a refund: {}", order_id),
+            ),
+        }
+    }
+}
+
+pub(super) mod htlc {
+    use super::*;
+    use crate::core::lsps::lsps2::{HtlcAccepted, HtlcResolution};
+    use crate::routes::auth_session::ServiceKey;
+
+    #[utoipa::path(
+        post,
+        path = "/lsps2/htlc",
+        tag = "ln-gateway",
+        operation_id = "lsps2Htlc",
+        security(("api_key" = ["lsps2:hook"])),
+        request_body = HtlcAccepted,
+        responses(
+            (status = 200, description = "Answer for CLN's htlc_accepted hook; HTLCs to an LSPS2 intercept SCID are held until the channel is open", body = HtlcResolution),
+            (status = 401, description = "API key required"),
+            (status = 403, description = "The API key lacks the scope")
+        )
+    )]
+    pub async fn handler(
+        State(state): State<Arc<Context>>,
+        _key: ServiceKey,
+        Json(htlc): Json<HtlcAccepted>,
+    ) -> Json<HtlcResolution> {
+        if !state.lsps2.is_enabled() {
+            return Json(HtlcResolution::resume());
+        }
+
+        Json(state.lsps2.handle_htlc(&state, htlc).await)
+    }
+}
@@ -0,0 +1,41 @@
+use std::fmt::Write;
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::header,
+    response::{IntoResponse, Response},
+};
+
+use crate::context::Context;
+
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "ln-gateway",
+    operation_id = "metrics",
+    security(("api_key" = ["read:metrics"])),
+    responses(
+        (status = 200, description = "Prometheus text exposition of the gateway counters", content_type = "text/plain", body = String),
+        (status = 401, description = "API key required"),
+        (status = 403, description = "The API key lacks the scope")
+    )
+)]
+pub(super) async fn handler(State(state): State<Arc<Context>>) -> Response {
+    let mut body = String::new();
+
+    let _ = writeln!(
+        body,
+        "# HELP ln_gateway_rate_limited_total Requests rejected by the rate limiter."
+    );
+    let _ = writeln!(body, "# TYPE ln_gateway_rate_limited_total counter");
+    let runtime = state.runtime.load();
+    for (route, rejected) in runtime.rate_limiter.rejections() {
+        let _ = writeln!(
+            body,
+            "ln_gateway_rate_limited_total{{route=\"{route}\"}} {rejected}"
+        );
+    }
+
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body).into_response()
+}
@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use axum::http::Uri;
-use axum::routing::delete;
+use axum::routing::{delete, post};
 use axum::{
     Json, Router,
     http::StatusCode,
@@ -13,19 +13,45 @@ use utoipa::OpenApi;
 
 use crate::context::Context;
 
+mod auth_session;
 pub mod callbacks;
 mod channel_request;
+mod channels;
+mod config;
 mod health;
+mod invoice_events;
+mod invoices;
 mod lnurl_auth_request;
+mod lnurl_auth_status;
+mod lsps;
+mod metrics;
+mod offers;
+mod payments;
+mod peers;
+mod policy;
 mod recent_requests;
+mod rune;
+mod webhooks;
 mod withdraw_request;
 
 // TYPES
 
 #[derive(Debug)]
 enum ApiResponse<T> {
-    Ok { status: StatusCode, data: T },
-    Err { status: StatusCode, message: String },
+    Ok {
+        status: StatusCode,
+        data: T,
+    },
+    Err {
+        status: StatusCode,
+        message: String,
+    },
+    // LNURL callbacks answer errors the way the LUDs define them, which wallets show to the user.
+    LnurlErr {
+        status: StatusCode,
+        reason: String,
+        code: &'static str,
+    },
 }
 
 impl<T> ApiResponse<T> {
@@ -35,6 +61,18 @@ impl<T> ApiResponse<T> {
             data,
         }
     }
+
+    /// Turns an error into an LNURL error with `code`; successes are kept as they are.
+    pub fn into_lnurl(self, code: &'static str) -> Self {
+        match self {
+            ApiResponse::Err { status, message } => ApiResponse::LnurlErr {
+                status,
+                reason: message,
+                code,
+            },
+            other => other,
+        }
+    }
 }
 
 // The IntoResponse is needed to convert ApiResponse<T> into an HTTP response.
@@ -61,12 +99,37 @@ where
                     "error": message
                 });
 
+                (status, Json(json)).into_response()
+            }
+            ApiResponse::LnurlErr {
+                status,
+                reason,
+                code,
+            } => {
+                let json = serde_json::json!({
+                    "status": "ERROR",
+                    "reason": reason,
+                    "code": code
+                });
+
                 (status, Json(json)).into_response()
             }
         }
     }
 }
 
+/// Body of an LNURL callback error (LUD-02/LUD-03), as written by `ApiResponse::into_response`.
+/// Only used to document those routes.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct LnurlErrorBody<C> {
+    /// Always `ERROR`.
+    pub status: String,
+    /// Human-readable reason, shown by wallets.
+    pub reason: String,
+    /// Machine-readable reason.
+    pub code: C,
+}
+
 mod api_error {
     use crate::routes::ApiResponse;
     use axum::http::StatusCode;
@@ -77,6 +140,19 @@ mod api_error {
             message: message.into(),
         }
     }
+
+    /// LNURL error (`{"status":"ERROR","reason":...}`) with a machine-readable `code`.
+    pub fn build_lnurl<T>(
+        status: StatusCode,
+        code: &'static str,
+        reason: impl Into<String>,
+    ) -> ApiResponse<T> {
+        ApiResponse::LnurlErr {
+            status,
+            reason: reason.into(),
+            code,
+        }
+    }
 }
 
 // PUBLIC METHODS
@@ -84,14 +160,73 @@ mod api_error {
 pub fn get_router() -> Router<Arc<Context>> {
     Router::new()
         .route("/health", get(health::handler))
+        .route("/metrics", get(metrics::handler))
         .route("/recent-requests", get(recent_requests::get::handler))
         .route("/recent-requests", delete(recent_requests::delete::handler))
         .route("/channel-request", get(channel_request::handler))
         .route("/withdraw-request", get(withdraw_request::handler))
+        .route("/auth/session", get(auth_session::get::handler))
+        .route("/auth/session", delete(auth_session::delete::handler))
+        .route("/invoices", get(invoices::list::handler))
+        .route("/invoices", post(invoices::create::handler))
+        .route("/invoices/{label}", get(invoices::get::handler))
+        .route("/invoices/{label}", delete(invoices::delete::handler))
+        .route("/events", get(invoice_events::sse::handler))
+        .route("/ws", get(invoice_events::ws::handler))
+        .route("/events/ticket", post(auth_session::ticket::handler))
+        .route("/payments", post(payments::create::handler))
+        .route("/payments/keysend", post(payments::keysend::handler))
+        .route("/payments/{payment_hash}", get(payments::get::handler))
+        .route("/offers", get(offers::list::handler))
+        .route("/offers", post(offers::create::handler))
+        .route("/offers/pay", post(offers::pay::handler))
+        .route("/offers/{offer_id}/disable", post(offers::disable::handler))
+        .route("/channels", get(channels::list::handler))
+        .route("/channels/{id}/close", post(channels::close::handler))
+        .route("/channels/{id}/fees", post(channels::fees::handler))
+        .route(
+            "/peers",
+            get(peers::list::handler).post(peers::connect::handler),
+        )
+        .route("/peers/{id}", delete(peers::disconnect::handler))
+        .route("/config/reload", post(config::reload::handler))
+        .route("/policy/usage", get(policy::usage::handler))
+        .route("/lsps1/refunds", get(lsps::refunds::handler))
+        .route("/lsps1/refunds/{order_id}", post(lsps::refunded::handler))
+        .route("/lsps2/htlc", post(lsps::htlc::handler))
+        .route("/webhooks/deliveries", get(webhooks::list::handler))
+        .route(
+            "/webhooks/deliveries/{id}/redeliver",
+            post(webhooks::redeliver::handler),
+        )
+        .merge(get_public_router())
+}
+
+/// Routes wallets and LSP clients call without credentials: LNURL-auth, the LNURL callbacks
+/// and LSPS. These are the only routes served on the onion listener.
+///
+/// `/withdraw-request` and `/channel-request` need credentials, so they are not among them: the
+/// LNURLs they issue always carry clearnet callback URLs.
+pub fn get_public_router() -> Router<Arc<Context>> {
+    Router::new()
         .route("/lnurl-auth-request", get(lnurl_auth_request::handler))
+        .route(
+            "/lnurl-auth-status/{k1}",
+            get(lnurl_auth_status::get::handler),
+        )
+        .route(
+            "/lnurl-auth-status/{k1}/events",
+            get(lnurl_auth_status::sse::handler),
+        )
+        .route("/lsps", post(lsps::handler))
         .nest("/callbacks", callbacks::get_router())
 }
 
+/// Router of the onion listener: the public routes only, without Swagger UI or admin routes.
+pub fn get_onion_router() -> Router<Arc<Context>> {
+    Router::new().merge(get_public_router()).fallback(not_found)
+}
+
 pub async fn not_found(uri: Uri) -> Response {
     let json = serde_json::json!({
         "status": StatusCode::NOT_FOUND.as_u16(),
@@ -104,11 +239,44 @@ pub async fn not_found(uri: Uri) -> Response {
 #[openapi(
     paths(
         health::handler,
+        metrics::handler,
         recent_requests::get::handler,
         recent_requests::delete::handler,
         channel_request::handler,
         withdraw_request::handler,
         lnurl_auth_request::handler,
+        lnurl_auth_status::get::handler,
+        lnurl_auth_status::sse::handler,
+        auth_session::get::handler,
+        auth_session::delete::handler,
+        invoices::create::handler,
+        invoices::list::handler,
+        invoices::get::handler,
+        invoices::delete::handler,
+        invoice_events::sse::handler,
+        invoice_events::ws::handler,
+        auth_session::ticket::handler,
+        webhooks::list::handler,
+        webhooks::redeliver::handler,
+        config::reload::handler,
+        policy::usage::handler,
+        lsps::handler,
+        lsps::refunds::handler,
+        lsps::refunded::handler,
+        lsps::htlc::handler,
+        payments::create::handler,
+        payments::get::handler,
+        payments::keysend::handler,
+        offers::create::handler,
+        offers::list::handler,
+        offers::disable::handler,
+        offers::pay::handler,
+        channels::list::handler,
+        channels::close::handler,
+        channels::fees::handler,
+        peers::list::handler,
+        peers::connect::handler,
+        peers::disconnect::handler,
     ),
     components(
         schemas(
@@ -123,10 +291,290 @@ pub async fn not_found(uri: Uri) -> Response {
             lnurl_auth_request::LnUrlAuthRequestResponse,
             lnurl_auth_request::LnUrlAuthRequestAction,
             lnurl_auth_request::LnUrlAuthRequestQuery,
+            lnurl_auth_status::LnUrlAuthStatus,
+            lnurl_auth_status::LnUrlAuthStatusResponse,
+            crate::core::auth_session::AuthSession,
+            crate::core::auth_session::StreamTicket,
+            invoices::InvoiceStatus,
+            invoices::InvoiceEntry,
+            invoices::create::CreateInvoiceRequest,
+            invoices::create::CreateInvoiceResponse,
+            invoices::list::ListInvoicesResponse,
+            crate::core::invoice_events::InvoiceSettledEvent,
+            crate::core::webhook::delivery::WebhookDelivery,
+            crate::core::webhook::delivery::WebhookDeliveryStatus,
+            crate::core::runtime_config::ConfigChange,
+            config::ConfigReloadResponse,
+            crate::core::spending_limits::SpendFlow,
+            crate::core::spending_limits::SpendingUsage,
+            crate::core::spending_limits::WindowUsage,
+            policy::PolicyUsageResponse,
+            crate::core::lsps::LspsRequest,
+            crate::core::lsps::LspsResponse,
+            crate::core::lsps::LspsError,
+            crate::core::lsps::lsps1::Lsps1Options,
+            crate::core::lsps::lsps1::CreateOrderParams,
+            crate::core::lsps::lsps1::GetOrderParams,
+            crate::core::lsps::lsps1::Lsps1Order,
+            crate::core::lsps::lsps1::Lsps1Payment,
+            crate::core::lsps::lsps1::Lsps1Bolt11Payment,
+            crate::core::lsps::lsps1::Lsps1OnchainPayment,
+            crate::core::lsps::lsps1::Lsps1Channel,
+            crate::core::lsps::lsps1::OrderState,
+            crate::core::lsps::lsps1::PaymentState,
+            crate::core::lsps::lsps1::Lsps1Refund,
+            lsps::Lsps1RefundsResponse,
+            crate::core::lsps::lsps2::OpeningFeeParams,
+            crate::core::lsps::lsps2::GetInfoParams,
+            crate::core::lsps::lsps2::Lsps2Info,
+            crate::core::lsps::lsps2::BuyParams,
+            crate::core::lsps::lsps2::BuyResult,
+            crate::core::lsps::lsps2::HtlcAccepted,
+            crate::core::lsps::lsps2::HtlcOnion,
+            crate::core::lsps::lsps2::HtlcInfo,
+            crate::core::lsps::lsps2::HtlcResult,
+            crate::core::lsps::lsps2::HtlcResolution,
+            payments::PaymentStatus,
+            payments::PaymentResult,
+            payments::create::PayInvoiceRequest,
+            payments::get::PaymentEntry,
+            payments::keysend::KeysendRequest,
+            payments::keysend::TlvRecord,
+            offers::OfferEntry,
+            offers::create::CreateOfferRequest,
+            offers::pay::PayOfferRequest,
+            channels::ChannelOpener,
+            channels::ChannelFees,
+            channels::ChannelEntry,
+            channels::close::CloseChannelRequest,
+            channels::close::CloseKind,
+            channels::close::CloseChannelResponse,
+            channels::fees::UpdateChannelFeesRequest,
+            channels::fees::ChannelFeesEntry,
+            peers::PeerEntry,
+            peers::connect::ConnectPeerRequest,
+            peers::connect::PeerDirection,
+            peers::connect::ConnectPeerResponse,
         )
     ),
+    modifiers(&SecuritySchemes),
     tags(
         (name = "ln-gateway", description = "CoreLightning REST gateway")
     )
 )]
 pub struct CoreApiDoc;
+
+// Schemes referenced by `security(...)` in the route attributes. The `api_key` scopes are also
+// what `core::api_key::middleware` enforces.
+struct SecuritySchemes;
+
+impl utoipa::Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        use utoipa::openapi::security::{
+            ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme,
+        };
+
+        let components = openapi
+            .components
+            .get_or_insert_with(utoipa::openapi::Components::new);
+
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::with_description(
+                "X-API-Key",
+                "Static API key; required scopes are listed per operation",
+            ))),
+        );
+        components.add_security_scheme(
+            "admin_session",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .description(Some("Session token of an admin LNURL-auth login"))
+                    .build(),
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::{Method, Request};
+    use clap::Parser;
+    use sha2::Digest;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::core::cli::Args;
+
+    /// Context on a fresh data dir, connected to a CLN socket that never answers: enough for
+    /// requests that are rejected before they reach the node.
+    pub(super) async fn context(args: &[String]) -> Arc<Context> {
+        let dir = std::env::temp_dir().join(format!("routes-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let sock = dir.join("rpc");
+        let listener = tokio::net::UnixListener::bind(&sock).unwrap();
+        tokio::spawn(async move {
+            let mut connections = Vec::new();
+            while let Ok((stream, _)) = listener.accept().await {
+                connections.push(stream);
+            }
+        });
+
+        let mut argv = vec![
+            "ln-server".to_string(),
+            format!("--rpc-sockpath={}", sock.display()),
+            format!("--data-dir={}", dir.display()),
+        ];
+        argv.extend_from_slice(args);
+        Context::new(Args::parse_from(argv)).await
+    }
+
+    /// `--api-keys` entry for the raw key `name` with the given scopes.
+    pub(super) fn api_key(name: &str, scopes: &str) -> String {
+        let hash = hex::encode(sha2::Sha256::digest(name.as_bytes()));
+        format!("--api-keys={name}:{hash}:{scopes}")
+    }
+
+    /// The main router with the `api_key` middleware, as served on the clearnet listener.
+    pub(super) fn app(ctx: &Arc<Context>) -> Router {
+        get_router()
+            .fallback(not_found)
+            .with_state(ctx.clone())
+            .layer(axum::middleware::from_fn_with_state(
+                ctx.clone(),
+                crate::core::api_key::middleware::middleware,
+            ))
+    }
+
+    pub(super) async fn send(
+        app: Router,
+        method: Method,
+        uri: &str,
+        api_key: Option<&str>,
+    ) -> (StatusCode, serde_json::Value) {
+        let mut request = Request::builder().method(method).uri(uri);
+        if let Some(key) = api_key {
+            request = request.header(crate::core::api_key::API_KEY_HEADER, key);
+        }
+        let request = request
+            .header("content-type", "application/json")
+            .body(Body::from("{}"))
+            .unwrap();
+
+        // Requests that reach CLN wait forever on the silent socket.
+        let response =
+            tokio::time::timeout(std::time::Duration::from_secs(5), app.oneshot(request))
+                .await
+                .expect("request reached CLN")
+                .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    #[tokio::test]
+    async fn offers_require_credentials() {
+        let ctx = context(&[api_key("reader", "invoices:read")]).await;
+
+        for method in [Method::GET, Method::POST] {
+            let (status, _) = send(app(&ctx), method.clone(), "/offers", None).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "{method} without a key");
+        }
+
+        let (status, _) = send(app(&ctx), Method::POST, "/offers", Some("reader")).await;
+        assert_eq!(status, StatusCode::FORBIDDEN, "POST with invoices:read");
+    }
+
+    #[tokio::test]
+    async fn offers_are_closed_without_configured_keys() {
+        let ctx = context(&[]).await;
+
+        for method in [Method::GET, Method::POST] {
+            let (status, _) = send(app(&ctx), method.clone(), "/offers", None).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "{method} without keys");
+        }
+    }
+
+    #[tokio::test]
+    async fn lnurl_callbacks_answer_errors_in_the_lud_shape() {
+        let ctx = context(&[]).await;
+        let node = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+        let cases = [
+            ("/callbacks/open-channel".to_string(), "invalid_request"),
+            (
+                format!("/callbacks/open-channel?k1=unknown&remoteid={node}"),
+                "invalid_k1",
+            ),
+            (
+                "/callbacks/open-channel?k1=unknown&remoteid=x".to_string(),
+                "invalid_remote_id",
+            ),
+            (
+                "/callbacks/issue-withdraw?k1=unknown".to_string(),
+                "invalid_request",
+            ),
+            (
+                "/callbacks/issue-withdraw?k1=unknown&destination=x&amount=1000".to_string(),
+                "invalid_k1",
+            ),
+        ];
+
+        for (uri, code) in cases {
+            let (status, body) = send(app(&ctx), Method::GET, &uri, None).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+            assert_eq!(body["status"], "ERROR", "{uri}");
+            assert_eq!(body["code"], code, "{uri}");
+            assert!(
+                body["reason"].as_str().is_some_and(|r| !r.is_empty()),
+                "{uri}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn onion_listener_serves_only_public_routes() {
+        let ctx = context(&[api_key("admin", "admin")]).await;
+        let onion = || get_onion_router().with_state(ctx.clone());
+
+        let served = [
+            (Method::GET, "/lnurl-auth-request"),
+            (Method::GET, "/lnurl-auth-status/00"),
+            (Method::GET, "/lnurl-auth-status/00/events"),
+            (Method::GET, "/callbacks/lnurl-auth"),
+            (Method::GET, "/callbacks/issue-withdraw"),
+            (Method::GET, "/callbacks/open-channel"),
+            (Method::POST, "/lsps"),
+        ];
+        for (method, path) in served {
+            let (_, body) = send(onion(), method.clone(), path, None).await;
+            assert_ne!(
+                body["error"],
+                format!("Route not found: {path}"),
+                "{method} {path} is served"
+            );
+        }
+
+        let hidden = [
+            (Method::GET, "/withdraw-request"),
+            (Method::GET, "/channel-request"),
+            (Method::GET, "/invoices"),
+            (Method::POST, "/payments"),
+            (Method::GET, "/events"),
+            (Method::GET, "/recent-requests"),
+            (Method::GET, "/health"),
+            (Method::POST, "/lsps2/htlc"),
+            (Method::GET, "/swagger-ui"),
+            (Method::GET, "/api-doc/openapi.json"),
+        ];
+        for (method, path) in hidden {
+            // Even with an admin key: the onion listener has no `api_key` middleware.
+            let (status, body) = send(onion(), method.clone(), path, Some("admin")).await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{method} {path}");
+            assert_eq!(body["error"], format!("Route not found: {path}"));
+        }
+    }
+}
@@ -0,0 +1,414 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use cln_rpc::model::responses::{
+    DecodeType, DisableofferResponse, ListoffersOffers, OfferResponse,
+};
+use cln_rpc::primitives::Sha256;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    context::Context,
+    core::spending_limits::SpendFlow,
+    routes::{
+        ApiResponse, api_error,
+        auth_session::AdminSession,
+        payments::{PaymentResult, payment_failed},
+        rune::CallerRune,
+    },
+};
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub(super) struct OfferEntry {
+    /// Offer id (hex), used to disable the offer.
+    pub offer_id: String,
+    /// BOLT12 offer string to share with payers.
+    pub bolt12: String,
+    /// Whether the offer can still be paid.
+    pub active: bool,
+    /// Whether the offer expires after a single payment.
+    pub single_use: bool,
+    /// Whether the offer has been paid at least once.
+    pub used: bool,
+}
+
+impl From<OfferResponse> for OfferEntry {
+    fn from(value: OfferResponse) -> Self {
+        Self {
+            offer_id: value.offer_id.to_string(),
+            bolt12: value.bolt12,
+            active: value.active,
+            single_use: value.single_use,
+            used: value.used,
+        }
+    }
+}
+
+impl From<ListoffersOffers> for OfferEntry {
+    fn from(value: ListoffersOffers) -> Self {
+        Self {
+            offer_id: value.offer_id.to_string(),
+            bolt12: value.bolt12,
+            active: value.active,
+            single_use: value.single_use,
+            used: value.used,
+        }
+    }
+}
+
+impl From<DisableofferResponse> for OfferEntry {
+    fn from(value: DisableofferResponse) -> Self {
+        Self {
+            offer_id: value.offer_id.to_string(),
+            bolt12: value.bolt12,
+            active: value.active,
+            single_use: value.single_use,
+            used: value.used,
+        }
+    }
+}
+
+pub(super) mod create {
+    use super::*;
+
+    #[derive(Debug, Deserialize, utoipa::ToSchema)]
+    pub struct CreateOfferRequest {
+        /// Amount in millisatoshis. Omit to let the payer choose ("any" amount).
+        pub amount_msat: Option<u64>,
+        /// Offer description shown to the payer (required when `amount_msat` is set).
+        pub description: Option<String>,
+        /// Name of the issuer shown to the payer.
+        pub issuer: Option<String>,
+        /// Maximum quantity a payer may request in one invoice.
+        pub quantity_max: Option<u64>,
+        /// Recurrence period, e.g. `1month` or `2weeks`.
+        pub recurrence: Option<String>,
+        /// Disable the offer after its first payment.
+        pub single_use: Option<bool>,
+    }
+
+    type Ret = ApiResponse<OfferEntry>;
+
+    #[utoipa::path(
+        post,
+        path = "/offers",
+        tag = "ln-gateway",
+        operation_id = "createOffer",
+        request_body = CreateOfferRequest,
+        security(("api_key" = ["invoices:write"]), ("admin_session" = [])),
+        responses(
+            (status = 201, description = "Offer created", body = OfferEntry),
+            (status = 200, description = "An identical offer already exists", body = OfferEntry),
+            (status = 400, description = "Invalid offer parameters"),
+            (status = 401, description = "Admin session or API key required, or a rune (`SERVER_REQUIRE_RUNE`)"),
+            (status = 403, description = "Not an admin session, the API key lacks the scope, or the rune does not allow offer"),
+            (status = 502, description = "The CoreLightning node encountered an error")
+        )
+    )]
+    pub async fn handler(
+        State(state): State<Arc<Context>>,
+        _admin: AdminSession,
+        rune: CallerRune,
+        Json(body): Json<CreateOfferRequest>,
+    ) -> Ret {
+        if body.amount_msat == Some(0) {
+            return api_error::build(StatusCode::BAD_REQUEST, "amount_msat must be positive");
+        }
+
+        if body.amount_msat.is_some() && body.description.is_none() {
+            return api_error::build(
+                StatusCode::BAD_REQUEST,
+                "description is required for offers with an amount",
+            );
+        }
+
+        if body.quantity_max == Some(0) {
+            return api_error::build(StatusCode::BAD_REQUEST, "quantity_max must be positive");
+        }
+
+        let mut rpc = state.cln_client.lock().await;
+
+        let rune_params = serde_json::json!({
+            "amount": body.amount_msat.map_or("any".to_string(), |msat| format!("{msat}msat")),
+            "description": body.description,
+            "issuer": body.issuer,
+            "quantity_max": body.quantity_max,
+            "recurrence": body.recurrence,
+            "single_use": body.single_use,
+        });
+        if let Err(e) = rune.authorize(&state, &mut rpc, "offer", rune_params).await {
+            return e;
+        }
+
+        let res = match rpc
+            .offer(
+                body.amount_msat,
+                body.description,
+                body.issuer,
+                body.quantity_max,
+                body.recurrence,
+                body.single_use,
+            )
+            .await
+        {
+            Ok(res) => res,
+            Err(e) => return api_error::build(StatusCode::BAD_GATEWAY, e.to_string()),
+        };
+
+        // CLN returns the existing offer instead of failing when the parameters are identical.
+        let status = if res.created {
+            StatusCode::CREATED
+        } else {
+            StatusCode::OK
+        };
+
+        ApiResponse::Ok {
+            status,
+            data: res.into(),
+        }
+    }
+}
+
+pub(super) mod list {
+    use super::*;
+
+    #[derive(Debug, Deserialize, utoipa::IntoParams)]
+    pub struct ListOffersQuery {
+        /// Only return offers that can still be paid.
+        pub active_only: Option<bool>,
+    }
+
+    type Ret = ApiResponse<Vec<OfferEntry>>;
+
+    #[utoipa::path(
+        get,
+        path = "/offers",
+        tag = "ln-gateway",
+        operation_id = "listOffers",
+        params(ListOffersQuery),
+        security(("api_key" = ["invoices:read"]), ("admin_session" = [])),
+        responses(
+            (status = 200, description = "Offers created on this node", body = Vec<OfferEntry>),
+            (status = 401, description = "Admin session or API key required"),
+            (status = 403, description = "Not an admin session, or the API key lacks the scope"),
+            (status = 502, description = "The CoreLightning node encountered an error")
+        )
+    )]
+    pub async fn handler(
+        State(state): State<Arc<Context>>,
+        _admin: AdminSession,
+        Query(q): Query<ListOffersQuery>,
+    ) -> Ret {
+        let mut rpc = state.cln_client.lock().await;
+        match rpc.listoffers(None, q.active_only).await {
+            Ok(res) => ApiResponse::make_ok(res.offers.into_iter().map(OfferEntry::from).collect()),
+            Err(e) => api_error::build(StatusCode::BAD_GATEWAY, e.to_string()),
+        }
+    }
+}
+
+pub(super) mod disable {
+    use super::*;
+
+    type Ret = ApiResponse<OfferEntry>;
+
+    #[utoipa::path(
+        post,
+        path = "/offers/{offer_id}/disable",
+        tag = "ln-gateway",
+        operation_id = "disableOffer",
+        params(
+            ("offer_id" = String, Path, description = "Offer id (hex)")
+        ),
+        security(("api_key" = ["admin"]), ("admin_session" = [])),
+        responses(
+            (status = 200, description = "The disabled offer", body = OfferEntry),
+            (status = 400, description = "Invalid offer id"),
+            (status = 404, description = "No offer with this id"),
+            (status = 502, description = "The CoreLightning node encountered an error"),
+            (status = 401, description = "Admin session or API key required"),
+            (status = 403, description = "Not an admin session, or the API key lacks the scope")
+        )
+    )]
+    pub async fn handler(
+        State(state): State<Arc<Context>>,
+        _admin: AdminSession,
+        rune: CallerRune,
+        Path(offer_id): Path<String>,
+    ) -> Ret {
+        let Ok(offer_id) = Sha256::from_str(&offer_id) else {
+            return api_error::build(StatusCode::BAD_REQUEST, "invalid offer id");
+        };
+
+        let mut rpc = state.cln_client.lock().await;
+
+        // `disableoffer` fails for unknown ids with a generic RPC error, so look it up first.
+        match rpc.listoffers(Some(offer_id), None).await {
+            Ok(res) if res.offers.is_empty() => {
+                return api_error::build(
+                    StatusCode::NOT_FOUND,
+                    format!("offer not found: {}", offer_id),
+                );
+            }
+            Ok(_) => {}
+            Err(e) => return api_error::build(StatusCode::BAD_GATEWAY, e.to_string()),
+        }
+
+        let rune_params = serde_json::json!({ "offer_id": offer_id.to_string() });
+        if let Err(e) = rune
+            .authorize(&state, &mut rpc, "disableoffer", rune_params)
+            .await
+        {
+            return e;
+        }
+
+        match rpc.disableoffer(offer_id).await {
+            Ok(res) => ApiResponse::make_ok(res.into()),
+            Err(e) => api_error::build(StatusCode::BAD_GATEWAY, e.to_string()),
+        }
+    }
+}
+
+pub(super) mod pay {
+    use super::*;
+
+    #[derive(Debug, Deserialize, utoipa::ToSchema)]
+    pub struct PayOfferRequest {
+        /// BOLT12 offer to pay.
+        pub offer: String,
+        /// Amount in millisatoshis, required only for offers without an amount.
+        pub amount_msat: Option<u64>,
+        /// Quantity to request, for offers with `quantity_max`.
+        pub quantity: Option<u64>,
+        /// Note sent to the offer issuer along with the invoice request.
+        pub payer_note: Option<String>,
+    }
+
+    type Ret = ApiResponse<PaymentResult>;
+
+    #[utoipa::path(
+        post,
+        path = "/offers/pay",
+        tag = "ln-gateway",
+        operation_id = "payOffer",
+        request_body = PayOfferRequest,
+        security(("api_key" = ["payments:send"]), ("admin_session" = [])),
+        responses(
+            (status = 200, description = "Payment result", body = PaymentResult),
+            (status = 400, description = "Invalid offer or amount"),
+            (status = 401, description = "Admin session or API key required"),
+            (status = 403, description = "Not an admin session, the API key lacks the scope, the rune does not allow pay, or the payment violates the spending policy or a spending limit"),
+            (status = 502, description = "The CoreLightning node encountered an error")
+        )
+    )]
+    pub async fn handler(
+        State(state): State<Arc<Context>>,
+        _admin: AdminSession,
+        rune: CallerRune,
+        Json(body): Json<PayOfferRequest>,
+    ) -> Ret {
+        // `fetchinvoice` and `pay` can take a minute; a connection of its own keeps the shared one free meanwhile.
+        let mut rpc = match state.dedicated_cln_client().await {
+            Ok(rpc) => rpc,
+            Err(e) => return api_error::build(StatusCode::BAD_GATEWAY, e.to_string()),
+        };
+
+        let decoded = match rpc.decode(body.offer.clone()).await {
+            Ok(res) => res,
+            Err(e) => {
+                return api_error::build(StatusCode::BAD_REQUEST, format!("invalid offer: {e}"));
+            }
+        };
+
+        if !decoded.valid || !matches!(decoded.item_type, DecodeType::BOLT12_OFFER) {
+            return api_error::build(StatusCode::BAD_REQUEST, "not a valid BOLT12 offer");
+        }
+
+        // Ask the issuer for an invoice over onion messages.
+        let fetched = match rpc
+            .fetchinvoice(body.offer, body.amount_msat, body.quantity, body.payer_note)
+            .await
+        {
+            Ok(res) => res,
+            Err(e) => return api_error::build(StatusCode::BAD_GATEWAY, e.to_string()),
+        };
+
+        let invoice = match rpc.decode(fetched.invoice.clone()).await {
+            Ok(res) if res.valid && matches!(res.item_type, DecodeType::BOLT12_INVOICE) => res,
+            Ok(_) => {
+                return api_error::build(
+                    StatusCode::BAD_GATEWAY,
+                    "issuer returned an invalid BOLT12 invoice",
+                );
+            }
+            Err(e) => return api_error::build(StatusCode::BAD_GATEWAY, e.to_string()),
+        };
+
+        let (Some(invoice_node_id), Some(amount_msat)) = (
+            invoice.invoice_node_id,
+            invoice.invoice_amount_msat.map(|a| a.msat()),
+        ) else {
+            return api_error::build(
+                StatusCode::BAD_GATEWAY,
+                "issuer returned an invoice without node id or amount",
+            );
+        };
+
+        let runtime = state.runtime.load_full();
+
+        // The invoice node id is a blinded key when the offer has blinded paths, so the
+        // allowlist is matched against the offer's issuer id instead.
+        let destination = match decoded.offer_issuer_id {
+            Some(issuer_id) => issuer_id,
+            None if !runtime.spending_policy.allowed_destinations.is_empty() => {
+                return api_error::build(
+                    StatusCode::FORBIDDEN,
+                    "offer has no issuer id, so its destination can't be checked against the allowed destinations",
+                );
+            }
+            None => invoice_node_id,
+        };
+
+        if let Err(e) = runtime.spending_policy.check(&destination, amount_msat) {
+            return api_error::build(StatusCode::FORBIDDEN, e);
+        }
+
+        // Only the final `pay` is checked; fetching an invoice moves no funds.
+        let rune_params = serde_json::json!({ "bolt11": fetched.invoice });
+        if let Err(e) = rune.authorize(&state, &mut rpc, "pay", rune_params).await {
+            return e;
+        }
+
+        let maxfee_msat = runtime.spending_policy.fee_budget_msat(amount_msat);
+        let reserved_msat = amount_msat.saturating_add(maxfee_msat);
+        let reservation = match state
+            .spending
+            .reserve(
+                &runtime.spending_policy.limits,
+                SpendFlow::Lightning,
+                reserved_msat,
+            )
+            .await
+        {
+            Ok(reservation) => reservation,
+            Err(e) => return api_error::build(StatusCode::FORBIDDEN, e),
+        };
+
+        match rpc.pay(fetched.invoice, None, Some(maxfee_msat)).await {
+            Ok(res) => {
+                let result = PaymentResult::from(res);
+                state
+                    .spending
+                    .commit(reservation, result.amount_sent_msat)
+                    .await;
+                ApiResponse::make_ok(result)
+            }
+            Err(e) => payment_failed(&state, reservation, reserved_msat, e).await,
+        }
+    }
+}
@@ -0,0 +1,518 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use cln_rpc::model::responses::{
+    DecodeType, KeysendResponse, ListpaysPays, ListpaysPaysStatus, PayResponse, PayStatus,
+};
+use cln_rpc::primitives::{PublicKey, Sha256, TlvEntry, TlvStream};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    context::Context,
+    core::lightning_rpc_connector::is_definitive_pay_failure,
+    core::spending_limits::{Reservation, SpendFlow},
+    routes::{ApiResponse, api_error, auth_session::AdminSession, rune::CallerRune},
+};
+
+/// Settles the reservation of a failed `pay`/`keysend`. Only a definitive failure releases it;
+/// otherwise parts may still be in flight, so the reserved amount stays counted against the
+/// spending limits.
+pub(super) async fn payment_failed<T>(
+    state: &Context,
+    reservation: Reservation,
+    reserved_msat: u64,
+    e: anyhow::Error,
+) -> ApiResponse<T> {
+    if is_definitive_pay_failure(&e) {
+        state.spending.release(reservation).await;
+        return api_error::build(StatusCode::BAD_GATEWAY, e.to_string());
+    }
+
+    tracing::warn!(
+        "Payment outcome unknown, keeping {} msat counted against the spending limits: {}",
+        reserved_msat,
+        e
+    );
+    state.spending.commit(reservation, reserved_msat).await;
+    api_error::build(
+        StatusCode::BAD_GATEWAY,
+        format!("payment may still complete, check GET /payments/{{payment_hash}}: {e}"),
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub(super) enum PaymentStatus {
+    Pending,
+    Complete,
+    Failed,
+}
+
+impl From<PayStatus> for PaymentStatus {
+    fn from(value: PayStatus) -> Self {
+        match value {
+            PayStatus::PENDING => PaymentStatus::Pending,
+            PayStatus::COMPLETE => PaymentStatus::Complete,
+            PayStatus::FAILED => PaymentStatus::Failed,
+        }
+    }
+}
+
+impl From<ListpaysPaysStatus> for PaymentStatus {
+    fn from(value: ListpaysPaysStatus) -> Self {
+        match value {
+            ListpaysPaysStatus::PENDING => PaymentStatus::Pending,
+            ListpaysPaysStatus::COMPLETE => PaymentStatus::Complete,
+            ListpaysPaysStatus::FAILED => PaymentStatus::Failed,
+        }
+    }
+}
+
+/// Outcome of a payment. CLN's `pay`, `xpay` and `keysend` don't report the route they used, so
+/// there is no route length; `htlc_parts` counts the parts of a multi-part payment instead.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub(super) struct PaymentResult {
+    /// Payment hash (hex).
+    pub payment_hash: String,
+    /// Payment status.
+    pub status: PaymentStatus,
+    /// Payment preimage (hex), proof of payment.
+    pub payment_preimage: String,
+    /// Destination node public key (hex).
+    pub destination: Option<String>,
+    /// Amount delivered to the destination in millisatoshis.
+    pub amount_msat: u64,
+    /// Amount sent including routing fees in millisatoshis.
+    pub amount_sent_msat: u64,
+    /// Routing fee paid in millisatoshis.
+    pub fee_msat: u64,
+    /// Number of HTLC parts the payment was split into (not a route length).
+    pub htlc_parts: u32,
+}
+
+impl From<PayResponse> for PaymentResult {
+    fn from(value: PayResponse) -> Self {
+        let amount_msat = value.amount_msat.msat();
+        let amount_sent_msat = value.amount_sent_msat.msat();
+
+        Self {
+            payment_hash: value.payment_hash.to_string(),
+            status: value.status.into(),
+            payment_preimage: hex::encode(value.payment_preimage.to_vec()),
+            destination: value.destination.map(|d| d.to_string()),
+            amount_msat,
+            amount_sent_msat,
+            fee_msat: amount_sent_msat.saturating_sub(amount_msat),
+            htlc_parts: value.parts,
+        }
+    }
+}
+
+impl From<KeysendResponse> for PaymentResult {
+    fn from(value: KeysendResponse) -> Self {
+        let amount_msat = value.amount_msat.msat();
+        let amount_sent_msat = value.amount_sent_msat.msat();
+
+        Self {
+            payment_hash: value.payment_hash.to_string(),
+            // `keysend` only returns once the payment completed; failures are RPC errors.
+            status: PaymentStatus::Complete,
+            payment_preimage: hex::encode(value.payment_preimage.to_vec()),
+            destination: value.destination.map(|d| d.to_string()),
+            amount_msat,
+            amount_sent_msat,
+            fee_msat: amount_sent_msat.saturating_sub(amount_msat),
+            htlc_parts: value.parts,
+        }
+    }
+}
+
+pub(super) mod create {
+    use super::*;
+
+    #[derive(Debug, Deserialize, utoipa::ToSchema)]
+    pub struct PayInvoiceRequest {
+        /// BOLT11 invoice to pay.
+        pub bolt11: String,
+        /// Amount in millisatoshis, required only for invoices without an amount.
+        pub amount_msat: Option<u64>,
+    }
+
+    type Ret = ApiResponse<PaymentResult>;
+
+    #[utoipa::path(
+        post,
+        path = "/payments",
+        tag = "ln-gateway",
+        operation_id = "payInvoice",
+        request_body = PayInvoiceRequest,
+        security(("api_key" = ["payments:send"]), ("admin_session" = [])),
+        responses(
+            (status = 200, description = "Payment result", body = PaymentResult),
+            (status = 400, description = "Invalid invoice or amount"),
+            (status = 401, description = "Admin session or API key required"),
+            (status = 403, description = "Not an admin session, the API key lacks the scope, the rune does not allow pay, or the payment violates the spending policy or a spending limit"),
+            (status = 502, description = "The CoreLightning node encountered an error")
+        )
+    )]
+    pub async fn handler(
+        State(state): State<Arc<Context>>,
+        _admin: AdminSession,
+        rune: CallerRune,
+        Json(body): Json<PayInvoiceRequest>,
+    ) -> Ret {
+        // `pay` can take a minute; a connection of its own keeps the shared one free meanwhile.
+        let mut rpc = match state.dedicated_cln_client().await {
+            Ok(rpc) => rpc,
+            Err(e) => return api_error::build(StatusCode::BAD_GATEWAY, e.to_string()),
+        };
+
+        let decoded = match rpc.decode(body.bolt11.clone()).await {
+            Ok(res) => res,
+            Err(e) => {
+                return api_error::build(StatusCode::BAD_REQUEST, format!("invalid invoice: {e}"));
+            }
+        };
+
+        if !decoded.valid || !matches!(decoded.item_type, DecodeType::BOLT11_INVOICE) {
+            return api_error::build(StatusCode::BAD_REQUEST, "not a valid BOLT11 invoice");
+        }
+
+        let Some(destination) = decoded.payee else {
+            return api_error::build(StatusCode::BAD_REQUEST, "invoice has no payee");
+        };
+
+        let amount_msat = match (decoded.amount_msat.map(|a| a.msat()), body.amount_msat) {
+            (Some(amount), None) => amount,
+            (None, Some(amount)) => amount,
+            (Some(_), Some(_)) => {
+                return api_error::build(
+                    StatusCode::BAD_REQUEST,
+                    "amount_msat must be omitted for invoices with an amount",
+                );
+            }
+            (None, None) => {
+                return api_error::build(
+                    StatusCode::BAD_REQUEST,
+                    "amount_msat is required for invoices without an amount",
+                );
+            }
+        };
+
+        let runtime = state.runtime.load_full();
+
+        if let Err(e) = runtime.spending_policy.check(&destination, amount_msat) {
+            return api_error::build(StatusCode::FORBIDDEN, e);
+        }
+
+        let rune_params = serde_json::json!({
+            "bolt11": body.bolt11,
+            "amount_msat": body.amount_msat,
+        });
+        if let Err(e) = rune.authorize(&state, &mut rpc, "pay", rune_params).await {
+            return e;
+        }
+
+        let maxfee_msat = runtime.spending_policy.fee_budget_msat(amount_msat);
+        let reserved_msat = amount_msat.saturating_add(maxfee_msat);
+        let reservation = match state
+            .spending
+            .reserve(
+                &runtime.spending_policy.limits,
+                SpendFlow::Lightning,
+                reserved_msat,
+            )
+            .await
+        {
+            Ok(reservation) => reservation,
+            Err(e) => return api_error::build(StatusCode::FORBIDDEN, e),
+        };
+
+        match rpc
+            .pay(body.bolt11, body.amount_msat, Some(maxfee_msat))
+            .await
+        {
+            Ok(res) => {
+                let result = PaymentResult::from(res);
+                state
+                    .spending
+                    .commit(reservation, result.amount_sent_msat)
+                    .await;
+                ApiResponse::make_ok(result)
+            }
+            Err(e) => payment_failed(&state, reservation, reserved_msat, e).await,
+        }
+    }
+}
+
+pub(super) mod get {
+    use super::*;
+
+    #[derive(Debug, Serialize, utoipa::ToSchema)]
+    pub struct PaymentEntry {
+        /// Payment hash (hex).
+        pub payment_hash: String,
+        /// Payment status.
+        pub status: PaymentStatus,
+        /// Payment preimage (hex), only once complete.
+        pub payment_preimage: Option<String>,
+        /// Destination node public key (hex).
+        pub destination: Option<String>,
+        /// Amount delivered to the destination in millisatoshis.
+        pub amount_msat: Option<u64>,
+        /// Amount sent including routing fees in millisatoshis.
+        pub amount_sent_msat: Option<u64>,
+        /// Routing fee paid in millisatoshis, when both amounts are known.
+        pub fee_msat: Option<u64>,
+        /// Number of HTLC parts the payment was split into (not a route length).
+        pub htlc_parts: Option<u64>,
+        /// BOLT11 invoice that was paid.
+        pub bolt11: Option<String>,
+        /// Invoice description.
+        pub description: Option<String>,
+        /// Unix timestamp (seconds) when the payment was started.
+        pub created_at: u64,
+        /// Unix timestamp (seconds) when the payment completed.
+        pub completed_at: Option<u64>,
+    }
+
+    impl From<ListpaysPays> for PaymentEntry {
+        fn from(value: ListpaysPays) -> Self {
+            let amount_msat = value.amount_msat.map(|a| a.msat());
+            let amount_sent_msat = value.amount_sent_msat.map(|a| a.msat());
+
+            Self {
+                payment_hash: value.payment_hash.to_string(),
+                status: value.status.into(),
+                payment_preimage: value.preimage.map(|p| hex::encode(p.to_vec())),
+                destination: value.destination.map(|d| d.to_string()),
+                amount_msat,
+                amount_sent_msat,
+                fee_msat: amount_sent_msat
+                    .zip(amount_msat)
+                    .map(|(sent, amount)| sent.saturating_sub(amount)),
+                htlc_parts: value.number_of_parts,
+                bolt11: value.bolt11,
+                description: value.description,
+                created_at: value.created_at,
+                completed_at: value.completed_at,
+            }
+        }
+    }
+
+    type Ret = ApiResponse<PaymentEntry>;
+
+    #[utoipa::path(
+        get,
+        path = "/payments/{payment_hash}",
+        tag = "ln-gateway",
+        operation_id = "getPayment",
+        params(
+            ("payment_hash" = String, Path, description = "Payment hash (hex)")
+        ),
+        security(("api_key" = ["payments:read"]), ("admin_session" = [])),
+        responses(
+            (status = 200, description = "Payment details", body = PaymentEntry),
+            (status = 400, description = "Invalid payment hash"),
+            (status = 404, description = "No payment with this hash"),
+            (status = 502, description = "The CoreLightning node encountered an error"),
+            (status = 401, description = "Admin session or API key required"),
+            (status = 403, description = "Not an admin session, or the API key lacks the scope")
+        )
+    )]
+    pub async fn handler(
+        State(state): State<Arc<Context>>,
+        _admin: AdminSession,
+        Path(payment_hash): Path<String>,
+    ) -> Ret {
+        let hash = match Sha256::from_str(&payment_hash) {
+            Ok(hash) => hash,
+            Err(_) => return api_error::build(StatusCode::BAD_REQUEST, "invalid payment hash"),
+        };
+
+        let mut rpc = state.cln_client.lock().await;
+        let res = match rpc.listpays(Some(hash)).await {
+            Ok(res) => res,
+            Err(e) => return api_error::build(StatusCode::BAD_GATEWAY, e.to_string()),
+        };
+
+        // A hash may have several attempts; report the successful one if there is any.
+        let mut pays = res.pays;
+        let pay = match pays
+            .iter()
+            .position(|p| matches!(p.status, ListpaysPaysStatus::COMPLETE))
+        {
+            Some(i) => Some(pays.swap_remove(i)),
+            None => pays.pop(),
+        };
+
+        match pay {
+            Some(pay) => ApiResponse::make_ok(pay.into()),
+            None => api_error::build(
+                StatusCode::NOT_FOUND,
+                format!("payment not found: {}", payment_hash),
+            ),
+        }
+    }
+}
+
+pub(super) mod keysend {
+    use super::*;
+
+    /// First TLV type of the custom records range (BOLT 1).
+    const CUSTOM_TLV_MIN_TYPE: u64 = 1 << 16;
+
+    #[derive(Debug, Deserialize, utoipa::ToSchema)]
+    pub struct TlvRecord {
+        /// Record type: odd and in the custom range (>= 65536).
+        #[serde(rename = "type")]
+        pub typ: u64,
+        /// Record value (hex).
+        pub value: String,
+    }
+
+    #[derive(Debug, Deserialize, utoipa::ToSchema)]
+    pub struct KeysendRequest {
+        /// Destination node public key (hex).
+        pub destination: String,
+        /// Amount in millisatoshis.
+        pub amount_msat: u64,
+        /// Extra TLV records attached to the payment (e.g. podcasting 2.0 metadata).
+        #[serde(default)]
+        pub tlv_records: Vec<TlvRecord>,
+    }
+
+    /// Validates custom records: types must be odd (so unaware receivers can ignore them), in
+    /// the custom range, unique, and values must be valid hex.
+    fn parse_tlv_records(records: Vec<TlvRecord>) -> Result<Option<TlvStream>, String> {
+        if records.is_empty() {
+            return Ok(None);
+        }
+
+        let mut entries: Vec<TlvEntry> = Vec::with_capacity(records.len());
+        for record in records {
+            if record.typ < CUSTOM_TLV_MIN_TYPE {
+                return Err(format!(
+                    "TLV type {} is outside the custom range (>= {})",
+                    record.typ, CUSTOM_TLV_MIN_TYPE
+                ));
+            }
+
+            if record.typ % 2 == 0 {
+                return Err(format!("TLV type {} must be odd", record.typ));
+            }
+
+            if entries.iter().any(|e| e.typ == record.typ) {
+                return Err(format!("duplicate TLV type {}", record.typ));
+            }
+
+            let value = hex::decode(&record.value)
+                .map_err(|_| format!("invalid hex value for TLV type {}", record.typ))?;
+
+            entries.push(TlvEntry {
+                typ: record.typ,
+                value,
+            });
+        }
+
+        Ok(Some(TlvStream { entries }))
+    }
+
+    type Ret = ApiResponse<PaymentResult>;
+
+    #[utoipa::path(
+        post,
+        path = "/payments/keysend",
+        tag = "ln-gateway",
+        operation_id = "keysend",
+        request_body = KeysendRequest,
+        security(("api_key" = ["payments:send"]), ("admin_session" = [])),
+        responses(
+            (status = 200, description = "Payment result", body = PaymentResult),
+            (status = 400, description = "Invalid destination, amount or TLV records"),
+            (status = 401, description = "Admin session or API key required"),
+            (status = 403, description = "Not an admin session, the API key lacks the scope, the rune does not allow keysend, or the payment violates the spending policy or a spending limit"),
+            (status = 502, description = "The CoreLightning node encountered an error")
+        )
+    )]
+    pub async fn handler(
+        State(state): State<Arc<Context>>,
+        _admin: AdminSession,
+        rune: CallerRune,
+        Json(body): Json<KeysendRequest>,
+    ) -> Ret {
+        let destination = match PublicKey::from_str(&body.destination) {
+            Ok(id) => id,
+            Err(e) => {
+                return api_error::build(StatusCode::BAD_REQUEST, format!("invalid pubkey: {}", e));
+            }
+        };
+
+        let extratlvs = match parse_tlv_records(body.tlv_records) {
+            Ok(tlvs) => tlvs,
+            Err(e) => return api_error::build(StatusCode::BAD_REQUEST, e),
+        };
+
+        let runtime = state.runtime.load_full();
+
+        if let Err(e) = runtime
+            .spending_policy
+            .check(&destination, body.amount_msat)
+        {
+            return api_error::build(StatusCode::FORBIDDEN, e);
+        }
+
+        let maxfee_msat = runtime.spending_policy.fee_budget_msat(body.amount_msat);
+
+        // `keysend` can take a minute; a connection of its own keeps the shared one free meanwhile.
+        let mut rpc = match state.dedicated_cln_client().await {
+            Ok(rpc) => rpc,
+            Err(e) => return api_error::build(StatusCode::BAD_GATEWAY, e.to_string()),
+        };
+
+        let rune_params = serde_json::json!({
+            "destination": body.destination,
+            "amount_msat": body.amount_msat,
+        });
+        if let Err(e) = rune
+            .authorize(&state, &mut rpc, "keysend", rune_params)
+            .await
+        {
+            return e;
+        }
+
+        let reserved_msat = body.amount_msat.saturating_add(maxfee_msat);
+        let reservation = match state
+            .spending
+            .reserve(
+                &runtime.spending_policy.limits,
+                SpendFlow::Lightning,
+                reserved_msat,
+            )
+            .await
+        {
+            Ok(reservation) => reservation,
+            Err(e) => return api_error::build(StatusCode::FORBIDDEN, e),
+        };
+
+        match rpc
+            .keysend(destination, body.amount_msat, Some(maxfee_msat), extratlvs)
+            .await
+        {
+            Ok(res) => {
+                let result = PaymentResult::from(res);
+                state
+                    .spending
+                    .commit(reservation, result.amount_sent_msat)
+                    .await;
+                ApiResponse::make_ok(result)
+            }
+            Err(e) => payment_failed(&state, reservation, reserved_msat, e).await,
+        }
+    }
+}
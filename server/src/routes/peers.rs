@@ -0,0 +1,437 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use cln_rpc::model::responses::{ConnectDirection, ConnectResponse, ListpeersPeers};
+use cln_rpc::primitives::PublicKey;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    context::Context,
+    routes::{ApiResponse, api_error, auth_session::AdminSession, rune::CallerRune},
+};
+
+/// BOLT 9 features worth showing by name; the number is the even (required) bit.
+const KNOWN_FEATURES: &[(usize, &str)] = &[
+    (0, "option_data_loss_protect"),
+    (4, "option_upfront_shutdown_script"),
+    (6, "gossip_queries"),
+    (8, "var_onion_optin"),
+    (10, "gossip_queries_ex"),
+    (12, "option_static_remotekey"),
+    (14, "payment_secret"),
+    (16, "basic_mpp"),
+    (18, "option_support_large_channel"),
+    (22, "option_anchors_zero_fee_htlc_tx"),
+    (24, "option_route_blinding"),
+    (26, "option_shutdown_anysegwit"),
+    (28, "option_dual_fund"),
+    (34, "option_quiesce"),
+    (38, "option_onion_messages"),
+    (44, "option_channel_type"),
+    (46, "option_scid_alias"),
+    (48, "option_payment_metadata"),
+    (50, "option_zeroconf"),
+];
+
+/// Names of the known features set (as required or optional) in a hex feature bitmap.
+fn feature_names(features: &str) -> Vec<String> {
+    let Ok(bytes) = hex::decode(features) else {
+        return Vec::new();
+    };
+    // Bit 0 is the least significant bit of the last byte.
+    let is_set = |bit: usize| {
+        bit / 8 < bytes.len() && bytes[bytes.len() - 1 - bit / 8] & (1 << (bit % 8)) != 0
+    };
+    KNOWN_FEATURES
+        .iter()
+        .filter(|(bit, _)| is_set(*bit) || is_set(bit + 1))
+        .map(|(_, name)| name.to_string())
+        .collect()
+}
+
+fn parse_peer_id(id: &str) -> Result<PublicKey, String> {
+    PublicKey::from_str(id.trim()).map_err(|e| format!("invalid node id: {e}"))
+}
+
+/// Splits `id[@host[:port]]`; IPv6 hosts with a port go in brackets.
+fn parse_peer_uri(uri: &str) -> Result<(PublicKey, Option<String>, Option<u16>), String> {
+    let Some((id, address)) = uri.trim().split_once('@') else {
+        return Ok((parse_peer_id(uri)?, None, None));
+    };
+    let id = parse_peer_id(id)?;
+
+    let (host, port) = if let Some(rest) = address.strip_prefix('[') {
+        let (host, rest) = rest
+            .split_once(']')
+            .ok_or_else(|| format!("invalid address: {address}"))?;
+        match rest.strip_prefix(':') {
+            Some(port) => (host, Some(port)),
+            None if rest.is_empty() => (host, None),
+            None => return Err(format!("invalid address: {address}")),
+        }
+    } else {
+        match address.split_once(':') {
+            // More than one colon: a bare IPv6 address without a port.
+            Some((_, port)) if port.contains(':') => (address, None),
+            Some((host, port)) => (host, Some(port)),
+            None => (address, None),
+        }
+    };
+    if host.is_empty() {
+        return Err(format!("invalid address: {address}"));
+    }
+    let port = port
+        .map(|p| p.parse::<u16>().map_err(|_| format!("invalid port: {p}")))
+        .transpose()?;
+
+    Ok((id, Some(host.to_string()), port))
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub(super) struct PeerEntry {
+    /// Node id of the peer.
+    pub id: String,
+    pub connected: bool,
+    /// Channels with the peer, in any state.
+    pub num_channels: u32,
+    /// Addresses of the current connection.
+    pub netaddr: Vec<String>,
+    /// Our address as seen by the peer.
+    pub remote_addr: Option<String>,
+    /// Feature bitmap (hex) the peer sent in `init`.
+    pub features: Option<String>,
+    /// Names of the well-known features in `features`.
+    pub known_features: Vec<String>,
+}
+
+impl From<ListpeersPeers> for PeerEntry {
+    fn from(value: ListpeersPeers) -> Self {
+        Self {
+            id: value.id.to_string(),
+            connected: value.connected,
+            num_channels: value.num_channels.unwrap_or_default(),
+            netaddr: value.netaddr.unwrap_or_default(),
+            remote_addr: value.remote_addr,
+            known_features: value
+                .features
+                .as_deref()
+                .map(feature_names)
+                .unwrap_or_default(),
+            features: value.features,
+        }
+    }
+}
+
+pub(super) mod list {
+    use super::*;
+
+    type Ret = ApiResponse<Vec<PeerEntry>>;
+
+    #[utoipa::path(
+        get,
+        path = "/peers",
+        tag = "ln-gateway",
+        operation_id = "listPeers",
+        security(("api_key" = ["peers:read"]), ("admin_session" = [])),
+        responses(
+            (status = 200, description = "Connected peers and peers we have channels with", body = Vec<PeerEntry>),
+            (status = 401, description = "Admin session or API key required"),
+            (status = 403, description = "Not an admin session, or the API key lacks the scope"),
+            (status = 502, description = "The CoreLightning node encountered an error")
+        )
+    )]
+    pub async fn handler(State(state): State<Arc<Context>>, _admin: AdminSession) -> Ret {
+        let mut rpc = state.cln_client.lock().await;
+        match rpc.listpeers(None).await {
+            Ok(res) => ApiResponse::make_ok(res.peers.into_iter().map(PeerEntry::from).collect()),
+            Err(e) => api_error::build(StatusCode::BAD_GATEWAY, e.to_string()),
+        }
+    }
+}
+
+pub(super) mod connect {
+    use super::*;
+
+    #[derive(Debug, Deserialize, utoipa::ToSchema)]
+    pub struct ConnectPeerRequest {
+        /// `id@host:port`, or a bare node id to use the addresses it announced in the gossip.
+        pub id: String,
+    }
+
+    #[derive(Debug, Clone, Copy, Serialize, utoipa::ToSchema)]
+    #[serde(rename_all = "snake_case")]
+    pub enum PeerDirection {
+        /// The peer connected to us (an existing connection).
+        In,
+        Out,
+    }
+
+    #[derive(Debug, Serialize, utoipa::ToSchema)]
+    pub struct ConnectPeerResponse {
+        pub id: String,
+        pub direction: PeerDirection,
+        /// Address of the connection (`host:port`, or a local socket path).
+        pub address: Option<String>,
+        /// Feature bitmap (hex) the peer sent in `init`.
+        pub features: String,
+        /// Names of the well-known features in `features`.
+        pub known_features: Vec<String>,
+    }
+
+    impl From<ConnectResponse> for ConnectPeerResponse {
+        fn from(value: ConnectResponse) -> Self {
+            let address = match (value.address.socket, value.address.address) {
+                (Some(socket), _) => Some(socket),
+                (None, Some(host)) => Some(match value.address.port {
+                    Some(port) if host.contains(':') => format!("[{host}]:{port}"),
+                    Some(port) => format!("{host}:{port}"),
+                    None => host,
+                }),
+                (None, None) => None,
+            };
+            Self {
+                id: value.id.to_string(),
+                direction: match value.direction {
+                    ConnectDirection::IN => PeerDirection::In,
+                    ConnectDirection::OUT => PeerDirection::Out,
+                },
+                address,
+                known_features: feature_names(&value.features),
+                features: value.features,
+            }
+        }
+    }
+
+    type Ret = ApiResponse<ConnectPeerResponse>;
+
+    #[utoipa::path(
+        post,
+        path = "/peers",
+        tag = "ln-gateway",
+        operation_id = "connectPeer",
+        request_body = ConnectPeerRequest,
+        security(("api_key" = ["peers:manage"]), ("admin_session" = [])),
+        responses(
+            (status = 200, description = "Connected (or already connected)", body = ConnectPeerResponse),
+            (status = 400, description = "Invalid node id or address"),
+            (status = 401, description = "Admin session or API key required"),
+            (status = 403, description = "Not an admin session, the API key lacks the scope, or the rune does not allow connect"),
+            (status = 404, description = "No address given and none known from the gossip"),
+            (status = 502, description = "The CoreLightning node encountered an error or the peer is unreachable")
+        )
+    )]
+    pub async fn handler(
+        State(state): State<Arc<Context>>,
+        _admin: AdminSession,
+        rune: CallerRune,
+        Json(body): Json<ConnectPeerRequest>,
+    ) -> Ret {
+        let (id, host, port) = match parse_peer_uri(&body.id) {
+            Ok(parsed) => parsed,
+            Err(e) => return api_error::build(StatusCode::BAD_REQUEST, e),
+        };
+
+        let mut rpc = state.cln_client.lock().await;
+
+        let rune_params = serde_json::json!({ "id": id.to_string(), "host": host, "port": port });
+        if let Err(e) = rune
+            .authorize(&state, &mut rpc, "connect", rune_params)
+            .await
+        {
+            return e;
+        }
+
+        let connected = match host {
+            Some(host) => rpc.connect(id, Some(host), port).await,
+            None => match rpc.listpeers(Some(id)).await {
+                // Peers that connected to us may not be in the gossip at all.
+                Ok(res) if res.peers.iter().any(|p| p.connected) => {
+                    rpc.connect(id, None, None).await
+                }
+                Ok(_) => match rpc.connect_via_gossip(id).await {
+                    Ok(Some(res)) => Ok(res),
+                    Ok(None) => {
+                        return api_error::build(
+                            StatusCode::NOT_FOUND,
+                            format!(
+                                "no address of {} known from the gossip; use id@host:port",
+                                id
+                            ),
+                        );
+                    }
+                    Err(e) => Err(e),
+                },
+                Err(e) => Err(e),
+            },
+        };
+
+        match connected {
+            Ok(res) => {
+                tracing::info!("Connected to peer {}", id);
+                ApiResponse::make_ok(res.into())
+            }
+            Err(e) => api_error::build(StatusCode::BAD_GATEWAY, e.to_string()),
+        }
+    }
+}
+
+pub(super) mod disconnect {
+    use super::*;
+
+    #[derive(Debug, Deserialize, utoipa::IntoParams)]
+    pub struct DisconnectPeerQuery {
+        /// Also disconnect when channels with the peer are active.
+        pub force: Option<bool>,
+    }
+
+    type Ret = ApiResponse<PeerEntry>;
+
+    #[utoipa::path(
+        delete,
+        path = "/peers/{id}",
+        tag = "ln-gateway",
+        operation_id = "disconnectPeer",
+        params(
+            ("id" = String, Path, description = "Node id of the peer"),
+            DisconnectPeerQuery
+        ),
+        security(("api_key" = ["peers:manage"]), ("admin_session" = [])),
+        responses(
+            (status = 200, description = "The disconnected peer", body = PeerEntry),
+            (status = 400, description = "Invalid node id"),
+            (status = 401, description = "Admin session or API key required"),
+            (status = 403, description = "Not an admin session, the API key lacks the scope, or the rune does not allow disconnect"),
+            (status = 404, description = "Not connected to this peer"),
+            (status = 502, description = "The CoreLightning node encountered an error, e.g. active channels without force")
+        )
+    )]
+    pub async fn handler(
+        State(state): State<Arc<Context>>,
+        _admin: AdminSession,
+        rune: CallerRune,
+        Path(id): Path<String>,
+        Query(q): Query<DisconnectPeerQuery>,
+    ) -> Ret {
+        let id = match parse_peer_id(&id) {
+            Ok(id) => id,
+            Err(e) => return api_error::build(StatusCode::BAD_REQUEST, e),
+        };
+
+        let mut rpc = state.cln_client.lock().await;
+
+        let peer = match rpc.listpeers(Some(id)).await {
+            Ok(res) => match res.peers.into_iter().find(|p| p.connected) {
+                Some(peer) => peer,
+                None => {
+                    return api_error::build(
+                        StatusCode::NOT_FOUND,
+                        format!("not connected to {}", id),
+                    );
+                }
+            },
+            Err(e) => return api_error::build(StatusCode::BAD_GATEWAY, e.to_string()),
+        };
+
+        let rune_params = serde_json::json!({ "id": id.to_string(), "force": q.force });
+        if let Err(e) = rune
+            .authorize(&state, &mut rpc, "disconnect", rune_params)
+            .await
+        {
+            return e;
+        }
+
+        match rpc.disconnect(id, q.force).await {
+            Ok(_) => {
+                tracing::info!("Disconnected peer {}", id);
+                let mut entry = PeerEntry::from(peer);
+                entry.connected = false;
+                ApiResponse::make_ok(entry)
+            }
+            Err(e) => api_error::build(StatusCode::BAD_GATEWAY, e.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+
+    #[test]
+    fn feature_names_reads_required_and_optional_bits() {
+        let cases: &[(&str, &[&str])] = &[
+            ("", &[]),
+            ("zz", &[]),
+            ("00", &[]),
+            // Bit 0 (required) and bit 5 (optional of 4).
+            (
+                "21",
+                &["option_data_loss_protect", "option_upfront_shutdown_script"],
+            ),
+            // Bit 15 (optional of 14) in the second-to-last byte.
+            ("8000", &["payment_secret"]),
+            // Bit 51 (optional of 50), seven bytes from the end.
+            ("08000000000000", &["option_zeroconf"]),
+            // Unknown bit 2 only.
+            ("04", &[]),
+        ];
+        for (features, expected) in cases {
+            assert_eq!(feature_names(features), *expected, "features {features:?}");
+        }
+    }
+
+    #[test]
+    fn parse_peer_uri_splits_host_and_port() {
+        let id = PublicKey::from_str(ID).unwrap();
+        let cases: &[(String, Option<&str>, Option<u16>)] = &[
+            (ID.to_string(), None, None),
+            (format!(" {ID} "), None, None),
+            (format!("{ID}@1.2.3.4"), Some("1.2.3.4"), None),
+            (format!("{ID}@1.2.3.4:9735"), Some("1.2.3.4"), Some(9735)),
+            (
+                format!("{ID}@example.com:19735"),
+                Some("example.com"),
+                Some(19735),
+            ),
+            (format!("{ID}@::1"), Some("::1"), None),
+            (format!("{ID}@[::1]"), Some("::1"), None),
+            (
+                format!("{ID}@[2001:db8::1]:9735"),
+                Some("2001:db8::1"),
+                Some(9735),
+            ),
+        ];
+        for (uri, host, port) in cases {
+            assert_eq!(
+                parse_peer_uri(uri),
+                Ok((id, host.map(str::to_string), *port)),
+                "uri {uri:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_peer_uri_rejects_malformed_input() {
+        let cases = [
+            "".to_string(),
+            "02abc".to_string(),
+            "02abc@1.2.3.4".to_string(),
+            format!("{ID}@"),
+            format!("{ID}@:9735"),
+            format!("{ID}@1.2.3.4:"),
+            format!("{ID}@1.2.3.4:70000"),
+            format!("{ID}@[::1"),
+            format!("{ID}@[::1]9735"),
+            format!("{ID}@[]:9735"),
+        ];
+        for uri in cases {
+            assert!(parse_peer_uri(&uri).is_err(), "uri {uri:?}");
+        }
+    }
+}
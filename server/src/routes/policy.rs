@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+use serde::Serialize;
+
+use crate::{
+    context::Context,
+    core::spending_limits::SpendingUsage,
+    routes::{ApiResponse, auth_session::AdminSession},
+};
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub(super) struct PolicyUsageResponse {
+    /// Usage per flow (`withdraw`, `channel`, `lightning`), then the total across flows.
+    pub usage: Vec<SpendingUsage>,
+    /// Version of the runtime config the limits come from.
+    pub config_version: u64,
+}
+
+pub(super) mod usage {
+    use super::*;
+
+    type Ret = ApiResponse<PolicyUsageResponse>;
+
+    #[utoipa::path(
+        get,
+        path = "/policy/usage",
+        tag = "ln-gateway",
+        operation_id = "policyUsage",
+        security(("api_key" = ["read:policy"]), ("admin_session" = [])),
+        responses(
+            (status = 200, description = "Spending over the rolling windows against the configured limits", body = PolicyUsageResponse),
+            (status = 401, description = "Admin session or API key required"),
+            (status = 403, description = "Not an admin session, or the API key lacks the scope")
+        )
+    )]
+    pub async fn handler(State(state): State<Arc<Context>>, _admin: AdminSession) -> Ret {
+        let runtime = state.runtime.load_full();
+        let usage = state.spending.usage(&runtime.spending_policy.limits).await;
+
+        ApiResponse::make_ok(PolicyUsageResponse {
+            usage,
+            config_version: runtime.version,
+        })
+    }
+}
@@ -4,7 +4,9 @@ use axum::extract::{Query, State};
 use serde::Deserialize;
 
 use crate::{
-    context::Context, core::recent_request::entry::RecentRequestEntry, routes::ApiResponse,
+    context::Context,
+    core::recent_request::entry::RecentRequestEntry,
+    routes::{ApiResponse, auth_session::AdminSession},
 };
 
 pub(super) mod get {
@@ -24,12 +26,16 @@ pub(super) mod get {
     tag = "ln-gateway",
     operation_id = "recent_requests",
     params(RecentRequestsQuery),
+    security(("api_key" = ["admin"]), ("admin_session" = [])),
     responses(
-        (status = 200, description = "Most recent requests processed by the gateway", body = [RecentRequestEntry])
+        (status = 200, description = "Most recent requests processed by the gateway", body = [RecentRequestEntry]),
+        (status = 401, description = "Admin session or API key required"),
+        (status = 403, description = "Not an admin session, or the API key lacks the scope")
     )
 )]
     pub async fn handler(
         State(state): State<Arc<Context>>,
+        _admin: AdminSession,
         Query(q): Query<RecentRequestsQuery>,
     ) -> Ret {
         let limit = q.limit.unwrap_or(15).clamp(1, 50);
@@ -50,11 +56,14 @@ pub(super) mod delete {
     path = "/recent-requests",
     tag = "ln-gateway",
     operation_id = "clear_recent_requests",
+    security(("api_key" = ["admin"]), ("admin_session" = [])),
     responses(
-        (status = 200, description = "Clears the recent requests log")
+        (status = 200, description = "Clears the recent requests log"),
+        (status = 401, description = "Admin session or API key required"),
+        (status = 403, description = "Not an admin session, or the API key lacks the scope")
     )
     )]
-    pub async fn handler(State(state): State<Arc<Context>>) -> Ret {
+    pub async fn handler(State(state): State<Arc<Context>>, _admin: AdminSession) -> Ret {
         let mut q = state.recent_requests.lock().await;
         q.clear();
 
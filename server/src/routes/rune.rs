@@ -0,0 +1,93 @@
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use axum::{
+    extract::FromRequestParts,
+    http::{StatusCode, request::Parts},
+};
+use serde_json::Value;
+
+use crate::{
+    context::Context,
+    core::lightning_rpc_connector::LightningRPCConnector,
+    routes::{ApiResponse, api_error},
+};
+
+/// Header carrying the caller's CLN rune.
+const RUNE_HEADER: &str = "x-cln-rune";
+
+/// CLN rune presented by the caller, checked with `checkrune` against the exact method and
+/// named parameters a handler is about to call. A rejected rune is a `403`; any other
+/// `checkrune` failure (e.g. a malformed rune or an unreachable node) is a `502`.
+pub(super) struct CallerRune(Option<String>);
+
+impl FromRequestParts<Arc<Context>> for CallerRune {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &Arc<Context>,
+    ) -> Result<Self, Self::Rejection> {
+        let rune = parts
+            .headers
+            .get(RUNE_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty());
+
+        Ok(CallerRune(rune))
+    }
+}
+
+impl CallerRune {
+    /// Checks the rune for `method`. Without a rune the call is allowed unless
+    /// `SERVER_REQUIRE_RUNE` is set.
+    pub async fn authorize<T>(
+        &self,
+        state: &Context,
+        rpc: &mut LightningRPCConnector,
+        method: &str,
+        params: Value,
+    ) -> Result<(), ApiResponse<T>> {
+        self.check(rpc, method, params, state.args.require_rune)
+            .await
+    }
+
+    /// Like `authorize`, but never requires a rune. Used by LNURL callbacks, which wallets call
+    /// without custom headers and which are already authorized by their one-time `k1`.
+    pub async fn authorize_if_present<T>(
+        &self,
+        rpc: &mut LightningRPCConnector,
+        method: &str,
+        params: Value,
+    ) -> Result<(), ApiResponse<T>> {
+        self.check(rpc, method, params, false).await
+    }
+
+    async fn check<T>(
+        &self,
+        rpc: &mut LightningRPCConnector,
+        method: &str,
+        params: Value,
+        required: bool,
+    ) -> Result<(), ApiResponse<T>> {
+        let Some(rune) = &self.0 else {
+            if required {
+                return Err(api_error::build(
+                    StatusCode::UNAUTHORIZED,
+                    format!("a rune allowing {method} is required"),
+                ));
+            }
+            return Ok(());
+        };
+
+        match rpc.checkrune(rune.clone(), method, params).await {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(reason)) => Err(api_error::build(
+                StatusCode::FORBIDDEN,
+                format!("rune does not allow {method}: {reason}"),
+            )),
+            Err(e) => Err(api_error::build(StatusCode::BAD_GATEWAY, e.to_string())),
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use serde::Deserialize;
+
+use crate::{
+    context::Context,
+    core::webhook::delivery::{WebhookDelivery, WebhookDeliveryStatus},
+    routes::{ApiResponse, api_error, auth_session::AdminSession},
+};
+
+pub(super) mod list {
+    use super::*;
+
+    #[derive(Debug, Deserialize, utoipa::IntoParams)]
+    pub struct WebhookDeliveriesQuery {
+        /// Only return deliveries with this status (e.g. failed).
+        pub status: Option<WebhookDeliveryStatus>,
+    }
+
+    type Ret = ApiResponse<Vec<WebhookDelivery>>;
+
+    #[utoipa::path(
+        get,
+        path = "/webhooks/deliveries",
+        tag = "ln-gateway",
+        operation_id = "listWebhookDeliveries",
+        params(WebhookDeliveriesQuery),
+        security(("api_key" = ["admin"]), ("admin_session" = [])),
+        responses(
+            (status = 200, description = "Webhook deliveries not yet delivered", body = [WebhookDelivery]),
+            (status = 401, description = "Admin session or API key required"),
+            (status = 403, description = "Not an admin session, or the API key lacks the scope")
+        )
+    )]
+    pub async fn handler(
+        State(state): State<Arc<Context>>,
+        _admin: AdminSession,
+        Query(q): Query<WebhookDeliveriesQuery>,
+    ) -> Ret {
+        ApiResponse::make_ok(state.webhooks.list(q.status).await)
+    }
+}
+
+pub(super) mod redeliver {
+    use super::*;
+
+    type Ret = ApiResponse<WebhookDelivery>;
+
+    #[utoipa::path(
+        post,
+        path = "/webhooks/deliveries/{id}/redeliver",
+        tag = "ln-gateway",
+        operation_id = "redeliverWebhook",
+        params(
+            ("id" = String, Path, description = "Delivery identifier")
+        ),
+        security(("api_key" = ["admin"]), ("admin_session" = [])),
+        responses(
+            (status = 200, description = "Delivery scheduled for an immediate retry", body = WebhookDelivery),
+            (status = 404, description = "No queued delivery with this identifier"),
+            (status = 401, description = "Admin session or API key required"),
+            (status = 403, description = "Not an admin session, or the API key lacks the scope")
+        )
+    )]
+    pub async fn handler(
+        State(state): State<Arc<Context>>,
+        _admin: AdminSession,
+        Path(id): Path<String>,
+    ) -> Ret {
+        match state.webhooks.redeliver(&id).await {
+            Some(delivery) => ApiResponse::make_ok(delivery),
+            None => api_error::build(
+                StatusCode::NOT_FOUND,
+                format!("webhook delivery not found: {}", id),
+            ),
+        }
+    }
+}
@@ -1,9 +1,16 @@
 use std::sync::Arc;
 
-use axum::extract::{Request, State};
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+};
 use serde::Serialize;
 
-use crate::{context::Context, core::utils, routes::ApiResponse};
+use crate::{
+    context::Context,
+    core::utils,
+    routes::{ApiResponse, api_error, auth_session::AdminSession},
+};
 
 #[derive(Serialize, utoipa::ToSchema)]
 pub(super) struct WithdrawRequestResponse {
@@ -31,11 +38,23 @@ type Ret = ApiResponse<WithdrawRequestResponse>;
     path = "/withdraw-request",
     tag = "ln-gateway",
     operation_id = "withdrawRequest",
+    security(("api_key" = ["lnurl:withdraw:issue"]), ("admin_session" = [])),
     responses(
-        (status = 200, description = "LNURL Withdraw Request", body = WithdrawRequestResponse)
+        (status = 200, description = "LNURL Withdraw Request", body = WithdrawRequestResponse),
+        (status = 503, description = "The server is shutting down"),
+        (status = 401, description = "Admin session or API key required"),
+        (status = 403, description = "Not an admin session, or the API key lacks the scope")
     )
 )]
-pub(super) async fn handler(State(state): State<Arc<Context>>, request: Request) -> Ret {
+pub(super) async fn handler(
+    State(state): State<Arc<Context>>,
+    _admin: AdminSession,
+    request: Request,
+) -> Ret {
+    if state.shutdown.is_draining() {
+        return api_error::build(StatusCode::SERVICE_UNAVAILABLE, "Server is shutting down");
+    }
+
     let k1 = utils::gen_k1_as_string();
 
     // Store k1 for one-time validation
@@ -44,14 +63,15 @@ pub(super) async fn handler(State(state): State<Arc<Context>>, request: Request)
         set.insert(k1.clone());
     }
 
-    let base_url = utils::request_base_url(&request, "0.0.0.0", state.args.listening_port);
+    let base_url = utils::request_base_url(&request, &state, "0.0.0.0");
+    let runtime = state.runtime.load();
     let response = WithdrawRequestResponse {
         default_description: "Withdraw funds from CoreLightning REST server",
         tag: "withdrawRequest",
-        callback: format!("{}/callbacks/withdraw-request", base_url),
+        callback: format!("{}/callbacks/issue-withdraw", base_url),
         k1,
-        min_withdrawable: state.args.min_withdrawable_msat,
-        max_withdrawable: state.args.max_withdrawable_msat,
+        min_withdrawable: runtime.min_withdrawable_msat,
+        max_withdrawable: runtime.max_withdrawable_msat,
     };
 
     ApiResponse::make_ok(response)